@@ -0,0 +1,659 @@
+use std::collections::VecDeque;
+
+// A small constraint-satisfaction engine.
+// Variables range over finite domains of small integers, constraints prune those domains, and
+// AC-3 runs every constraint touching a changed variable again until nothing changes any more.
+// A search then branches on one variable at a time, propagating after every choice. A puzzle is
+// a Model: its variables, their domains and the constraints between them, so a new puzzle type
+// (or a variant of one) only needs a new model.
+// https://en.wikipedia.org/wiki/AC-3_algorithm
+
+// The values a variable can still take, as a bitset: values are 0 to 63
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Domain(u64);
+
+impl Domain {
+    pub fn new(values: impl IntoIterator<Item = u32>) -> Self {
+        Domain(values.into_iter().fold(0, |bits, value| {
+            assert!(value < 64, "domain values must be below 64");
+            bits | 1 << value
+        }))
+    }
+
+    pub fn contains(&self, value: u32) -> bool {
+        value < 64 && self.0 & 1 << value != 0
+    }
+
+    pub fn remove(&mut self, value: u32) {
+        if value < 64 {
+            self.0 &= !(1 << value);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    // The only value left, if there is exactly one
+    pub fn value(&self) -> Option<u32> {
+        (self.len() == 1).then(|| self.0.trailing_zeros())
+    }
+
+    pub fn min(&self) -> Option<u32> {
+        (!self.is_empty()).then(|| self.0.trailing_zeros())
+    }
+
+    pub fn max(&self) -> Option<u32> {
+        (!self.is_empty()).then(|| 63 - self.0.leading_zeros())
+    }
+
+    // Values in increasing order
+    pub fn iter(&self) -> impl Iterator<Item = u32> {
+        let bits = self.0;
+        (0..64).filter(move |value| bits & 1 << value != 0)
+    }
+
+    // Keep only the values from low to high
+    fn clamp(&mut self, low: i64, high: i64) {
+        if high < low || high < 0 || low > 63 {
+            self.0 = 0;
+            return;
+        }
+        let (low, high) = (low.max(0) as u32, high.min(63) as u32);
+        let mask = if high == 63 { u64::MAX } else { (1 << (high + 1)) - 1 };
+        self.0 &= mask & !((1 << low) - 1);
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum Constraint {
+    // No two of the variables take the same value
+    AllDifferent(Vec<usize>),
+    // The variables add up to the total
+    Sum { variables: Vec<usize>, total: i64 },
+    NotEqual(usize, usize),
+    // The first variable is strictly smaller than the second
+    Less(usize, usize),
+}
+
+impl Constraint {
+    fn scope(&self) -> Vec<usize> {
+        match self {
+            Constraint::AllDifferent(variables) | Constraint::Sum { variables, .. } => variables.clone(),
+            Constraint::NotEqual(a, b) | Constraint::Less(a, b) => vec![*a, *b],
+        }
+    }
+}
+
+// How an AllDifferent constraint prunes domains.
+// Pairwise: as the not-equal constraints between every two of its variables, so a fixed
+// variable's value is removed from the others. Matching: generalised arc consistency (Régin,
+// 1994), so every value left can be extended to an assignment of all the variables.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AllDifferentFiltering {
+    Pairwise,
+    Matching,
+}
+
+// A puzzle: variables with their initial domains, and the constraints between them
+#[derive(Clone, Debug, Default)]
+pub struct Model {
+    domains: Vec<Domain>,
+    constraints: Vec<Constraint>,
+    // Constraints on each variable
+    watchers: Vec<Vec<usize>>,
+}
+
+impl Model {
+    pub fn new() -> Self {
+        Model::default()
+    }
+
+    // Add a variable taking one of the values, returns its index
+    pub fn add_variable(&mut self, values: impl IntoIterator<Item = u32>) -> usize {
+        self.domains.push(Domain::new(values));
+        self.watchers.push(Vec::new());
+        self.domains.len() - 1
+    }
+
+    pub fn add_constraint(&mut self, constraint: Constraint) {
+        for var in constraint.scope() {
+            assert!(var < self.domains.len(), "constraint on unknown variable {}", var);
+            if !self.watchers[var].contains(&self.constraints.len()) {
+                self.watchers[var].push(self.constraints.len());
+            }
+        }
+        self.constraints.push(constraint);
+    }
+
+    pub fn variables(&self) -> usize {
+        self.domains.len()
+    }
+
+    pub fn domains(&self) -> &[Domain] {
+        &self.domains
+    }
+
+    pub fn constraints(&self) -> &[Constraint] {
+        &self.constraints
+    }
+
+    // AC-3: revise constraints until no domain changes, starting from the given ones.
+    // Returns false if a domain runs empty.
+    pub fn propagate(&self, domains: &mut [Domain], filtering: AllDifferentFiltering, start: impl IntoIterator<Item = usize>) -> bool {
+        let mut queued = vec![false; self.constraints.len()];
+        let mut queue = VecDeque::new();
+        for c in start {
+            if !queued[c] {
+                queued[c] = true;
+                queue.push_back(c);
+            }
+        }
+        while let Some(c) = queue.pop_front() {
+            queued[c] = false;
+            let scope = self.constraints[c].scope();
+            let before: Vec<Domain> = scope.iter().map(|&var| domains[var]).collect();
+            if !self.revise(c, domains, filtering) {
+                return false;
+            }
+            for (&var, old) in scope.iter().zip(&before) {
+                if domains[var] == *old {
+                    continue;
+                }
+                if domains[var].is_empty() {
+                    return false;
+                }
+                for &other in &self.watchers[var] {
+                    if !queued[other] {
+                        queued[other] = true;
+                        queue.push_back(other);
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    // Prune the domains of one constraint's variables, returns false if it cannot be satisfied
+    fn revise(&self, c: usize, domains: &mut [Domain], filtering: AllDifferentFiltering) -> bool {
+        match &self.constraints[c] {
+            Constraint::NotEqual(a, b) => {
+                if let Some(value) = domains[*a].value() {
+                    domains[*b].remove(value);
+                }
+                if let Some(value) = domains[*b].value() {
+                    domains[*a].remove(value);
+                }
+                !domains[*a].is_empty() && !domains[*b].is_empty()
+            }
+            Constraint::Less(a, b) => {
+                let (Some(low), Some(high)) = (domains[*a].min(), domains[*b].max()) else {
+                    return false;
+                };
+                domains[*a].clamp(0, high as i64 - 1);
+                domains[*b].clamp(low as i64 + 1, 63);
+                !domains[*a].is_empty() && !domains[*b].is_empty()
+            }
+            Constraint::Sum { variables, total } => Self::revise_sum(variables, *total, domains),
+            Constraint::AllDifferent(variables) => match filtering {
+                AllDifferentFiltering::Pairwise => Self::revise_pairwise(variables, domains),
+                AllDifferentFiltering::Matching => Self::revise_matching(variables, domains),
+            },
+        }
+    }
+
+    // Bounds consistency: each variable lies between the total minus the others' largest values
+    // and the total minus their smallest values
+    fn revise_sum(variables: &[usize], total: i64, domains: &mut [Domain]) -> bool {
+        let mut changed = true;
+        while changed {
+            changed = false;
+            if variables.iter().any(|&var| domains[var].is_empty()) {
+                return false;
+            }
+            let low: i64 = variables.iter().map(|&var| domains[var].min().unwrap() as i64).sum();
+            let high: i64 = variables.iter().map(|&var| domains[var].max().unwrap() as i64).sum();
+            if total < low || total > high {
+                return false;
+            }
+            for &var in variables {
+                let (min, max) = (domains[var].min().unwrap() as i64, domains[var].max().unwrap() as i64);
+                let before = domains[var];
+                domains[var].clamp(total - (high - max), total - (low - min));
+                changed |= domains[var] != before;
+            }
+        }
+        true
+    }
+
+    fn revise_pairwise(variables: &[usize], domains: &mut [Domain]) -> bool {
+        let mut queue: Vec<usize> = variables.iter().cloned().filter(|&var| domains[var].value().is_some()).collect();
+        while let Some(var) = queue.pop() {
+            let value = domains[var].value().unwrap();
+            for &other in variables {
+                if other == var || !domains[other].contains(value) {
+                    continue;
+                }
+                domains[other].remove(value);
+                match domains[other].len() {
+                    0 => return false,
+                    1 => queue.push(other),
+                    _ => {}
+                }
+            }
+        }
+        true
+    }
+
+    // Régin's filtering: find a maximum matching of variables to values; every variable must be
+    // matched. Orient matching edges from variable to value and the other edges from value to
+    // variable. An unmatched edge can then belong to some maximum matching if and only if it lies
+    // on an alternating cycle (both ends in the same strongly connected component) or on an
+    // alternating path starting at a free value; all other edges are removed.
+    fn revise_matching(variables: &[usize], domains: &mut [Domain]) -> bool {
+        let n = variables.len();
+        // Values matched to each variable, and variables matched to each value
+        let mut matched: Vec<Option<u32>> = vec![None; n];
+        let mut owner: [Option<usize>; 64] = [None; 64];
+        for x in 0..n {
+            let mut seen = 0u64;
+            if !Self::augment(x, variables, domains, &mut matched, &mut owner, &mut seen) {
+                return false;
+            }
+        }
+
+        // Vertices: variables 0..n, then values n..n + 64
+        let vertices = n + 64;
+        let mut edges: Vec<Vec<usize>> = vec![Vec::new(); vertices];
+        for (x, &var) in variables.iter().enumerate() {
+            for value in domains[var].iter() {
+                if matched[x] == Some(value) {
+                    edges[x].push(n + value as usize);
+                } else {
+                    edges[n + value as usize].push(x);
+                }
+            }
+        }
+
+        // Reachable from a free value, along the oriented edges
+        let mut reachable = vec![false; vertices];
+        let mut stack: Vec<usize> = (0..64)
+            .filter(|&value| owner[value].is_none() && !edges[n + value].is_empty())
+            .map(|value| n + value)
+            .collect();
+        for &v in &stack {
+            reachable[v] = true;
+        }
+        while let Some(v) = stack.pop() {
+            for &w in &edges[v] {
+                if !reachable[w] {
+                    reachable[w] = true;
+                    stack.push(w);
+                }
+            }
+        }
+
+        let component = Self::components(&edges);
+        for (x, &var) in variables.iter().enumerate() {
+            for value in domains[var].iter() {
+                let v = n + value as usize;
+                if matched[x] != Some(value) && !reachable[v] && component[v] != component[x] {
+                    domains[var].remove(value);
+                }
+            }
+        }
+        true
+    }
+
+    // Kuhn's augmenting path from variable x, seen marks the values already tried
+    fn augment(x: usize, variables: &[usize], domains: &[Domain], matched: &mut [Option<u32>], owner: &mut [Option<usize>; 64], seen: &mut u64) -> bool {
+        for value in domains[variables[x]].iter() {
+            if *seen & 1 << value != 0 {
+                continue;
+            }
+            *seen |= 1 << value;
+            let free = match owner[value as usize] {
+                None => true,
+                Some(y) => Self::augment(y, variables, domains, matched, owner, seen),
+            };
+            if free {
+                matched[x] = Some(value);
+                owner[value as usize] = Some(x);
+                return true;
+            }
+        }
+        false
+    }
+
+    // Strongly connected components (Tarjan), as the component index of each vertex
+    fn components(edges: &[Vec<usize>]) -> Vec<usize> {
+        let n = edges.len();
+        let mut index = vec![usize::MAX; n];
+        let mut low = vec![0; n];
+        let mut on_stack = vec![false; n];
+        let mut stack = Vec::new();
+        let mut component = vec![usize::MAX; n];
+        let mut next_index = 0;
+        let mut next_component = 0;
+        for root in 0..n {
+            if index[root] != usize::MAX {
+                continue;
+            }
+            // Iterative DFS: (vertex, next edge to follow)
+            let mut calls = vec![(root, 0)];
+            index[root] = next_index;
+            low[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            while let Some(&mut (v, ref mut edge)) = calls.last_mut() {
+                if let Some(&w) = edges[v].get(*edge) {
+                    *edge += 1;
+                    if index[w] == usize::MAX {
+                        index[w] = next_index;
+                        low[w] = next_index;
+                        next_index += 1;
+                        stack.push(w);
+                        on_stack[w] = true;
+                        calls.push((w, 0));
+                    } else if on_stack[w] {
+                        low[v] = low[v].min(index[w]);
+                    }
+                    continue;
+                }
+                calls.pop();
+                if let Some(&(parent, _)) = calls.last() {
+                    low[parent] = low[parent].min(low[v]);
+                }
+                if low[v] == index[v] {
+                    while let Some(w) = stack.pop() {
+                        on_stack[w] = false;
+                        component[w] = next_component;
+                        if w == v {
+                            break;
+                        }
+                    }
+                    next_component += 1;
+                }
+            }
+        }
+        component
+    }
+}
+
+// Which variable to branch on next, and in which order to try its values
+pub trait Branching {
+    // None once every variable is fixed
+    fn variable(&self, model: &Model, domains: &[Domain]) -> Option<usize>;
+
+    fn values(&self, domain: Domain) -> Vec<u32> {
+        domain.iter().collect()
+    }
+}
+
+// The first unfixed variable, in the order they were added
+pub struct InputOrder;
+
+impl Branching for InputOrder {
+    fn variable(&self, _model: &Model, domains: &[Domain]) -> Option<usize> {
+        domains.iter().position(|domain| domain.len() > 1)
+    }
+}
+
+// The unfixed variable with the smallest domain, ties broken by the most constraints (first fail)
+pub struct FirstFail;
+
+impl Branching for FirstFail {
+    fn variable(&self, model: &Model, domains: &[Domain]) -> Option<usize> {
+        (0..domains.len())
+            .filter(|&var| domains[var].len() > 1)
+            .min_by_key(|&var| (domains[var].len(), std::cmp::Reverse(model.watchers[var].len())))
+    }
+}
+
+// Returned by Engine::solve_until when the search was told to stop
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Interrupted;
+
+// Follows the search of an Engine, and may stop it
+pub trait Monitor {
+    // Called before each node with the number of nodes visited so far; true stops the search
+    fn interrupt(&mut self, _nodes: usize) -> bool {
+        false
+    }
+    // The search tries value for var
+    fn on_choice(&mut self, _var: usize, _value: u32) {}
+    // Propagation narrowed the domain of var
+    fn on_propagate(&mut self, _var: usize, _before: Domain, _after: Domain) {}
+    // Trying value for var failed, in its propagation or further down
+    fn on_failure(&mut self, _var: usize, _value: u32) {}
+}
+
+// A closure only decides when to stop
+impl<F: FnMut(usize) -> bool> Monitor for F {
+    fn interrupt(&mut self, nodes: usize) -> bool {
+        self(nodes)
+    }
+}
+
+// Depth-first search over a model, propagating after every choice
+pub struct Engine<B: Branching> {
+    branching: B,
+    filtering: AllDifferentFiltering,
+    nodes: usize,
+    failures: usize,
+    guesses: usize,
+    max_depth: usize,
+}
+
+impl<B: Branching> Engine<B> {
+    pub fn new(branching: B, filtering: AllDifferentFiltering) -> Self {
+        Engine { branching, filtering, nodes: 0, failures: 0, guesses: 0, max_depth: 0 }
+    }
+
+    // Search nodes and failed propagations of the last solve
+    pub fn nodes(&self) -> usize {
+        self.nodes
+    }
+
+    pub fn failures(&self) -> usize {
+        self.failures
+    }
+
+    // Values tried by branching, and the most of them on the stack at once, in the last solve
+    pub fn guesses(&self) -> usize {
+        self.guesses
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth
+    }
+
+    // A value for every variable satisfying all the constraints, if there is one
+    pub fn solve(&mut self, model: &Model) -> Option<Vec<u32>> {
+        self.solve_until(model, |_| false).unwrap_or(None)
+    }
+
+    // solve, giving up as soon as interrupt returns true. It is called before each node with the
+    // number of nodes visited so far.
+    pub fn solve_until(&mut self, model: &Model, mut interrupt: impl FnMut(usize) -> bool) -> Result<Option<Vec<u32>>, Interrupted> {
+        self.solve_monitored(model, &mut interrupt)
+    }
+
+    // solve_until, telling monitor about every choice, propagation and failure
+    pub fn solve_monitored(&mut self, model: &Model, monitor: &mut dyn Monitor) -> Result<Option<Vec<u32>>, Interrupted> {
+        self.nodes = 0;
+        self.failures = 0;
+        self.guesses = 0;
+        self.max_depth = 0;
+        let mut domains = model.domains.clone();
+        // Propagation only notices domains it narrows, so a variable created without values has
+        // to be caught here
+        if domains.iter().any(Domain::is_empty) || !model.propagate(&mut domains, self.filtering, 0..model.constraints.len()) {
+            self.failures += 1;
+            return Ok(None);
+        }
+        Self::report(&model.domains, &domains, monitor);
+        self.search(model, domains, 0, monitor)
+    }
+
+    // Tell monitor about the domains propagation narrowed from before to after
+    fn report(before: &[Domain], after: &[Domain], monitor: &mut dyn Monitor) {
+        for (var, (&before, &after)) in before.iter().zip(after).enumerate() {
+            if before != after {
+                monitor.on_propagate(var, before, after);
+            }
+        }
+    }
+
+    fn search(&mut self, model: &Model, domains: Vec<Domain>, depth: usize, monitor: &mut dyn Monitor) -> Result<Option<Vec<u32>>, Interrupted> {
+        if monitor.interrupt(self.nodes) {
+            return Err(Interrupted);
+        }
+        self.nodes += 1;
+        self.max_depth = self.max_depth.max(depth);
+        let Some(var) = self.branching.variable(model, &domains) else {
+            return Ok(Some(domains.iter().map(|domain| domain.value().unwrap()).collect()));
+        };
+        for value in self.branching.values(domains[var]) {
+            let mut child = domains.clone();
+            child[var] = Domain::new([value]);
+            self.guesses += 1;
+            monitor.on_choice(var, value);
+            if !model.propagate(&mut child, self.filtering, model.watchers[var].iter().cloned()) {
+                self.failures += 1;
+                monitor.on_failure(var, value);
+                continue;
+            }
+            let mut chosen = domains.clone();
+            chosen[var] = child[var];
+            Self::report(&chosen, &child, monitor);
+            if let Some(solution) = self.search(model, child, depth + 1, monitor)? {
+                return Ok(Some(solution));
+            }
+            monitor.on_failure(var, value);
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn propagated(model: &Model, filtering: AllDifferentFiltering) -> Option<Vec<Domain>> {
+        let mut domains = model.domains().to_vec();
+        model.propagate(&mut domains, filtering, 0..model.constraints().len()).then_some(domains)
+    }
+
+    #[test]
+    fn matching_filters_more_than_pairwise() {
+        // a and b share the values 1 and 2 between them, so c can only be 3
+        let mut model = Model::new();
+        let a = model.add_variable([1, 2]);
+        let b = model.add_variable([1, 2]);
+        let c = model.add_variable([1, 2, 3]);
+        model.add_constraint(Constraint::AllDifferent(vec![a, b, c]));
+        let pairwise = propagated(&model, AllDifferentFiltering::Pairwise).unwrap();
+        assert_eq!(pairwise[c], Domain::new([1, 2, 3]));
+        let matching = propagated(&model, AllDifferentFiltering::Matching).unwrap();
+        assert_eq!(matching, vec![Domain::new([1, 2]), Domain::new([1, 2]), Domain::new([3])]);
+
+        // Three variables sharing two values: only matching sees the pigeonhole
+        let mut model = Model::new();
+        let vars: Vec<usize> = (0..3).map(|_| model.add_variable([1, 2])).collect();
+        model.add_constraint(Constraint::AllDifferent(vars));
+        assert!(propagated(&model, AllDifferentFiltering::Pairwise).is_some());
+        assert!(propagated(&model, AllDifferentFiltering::Matching).is_none());
+    }
+
+    #[test]
+    fn pairwise_removes_fixed_values() {
+        let mut model = Model::new();
+        let a = model.add_variable([4]);
+        let b = model.add_variable([4, 5]);
+        let c = model.add_variable([4, 5, 6]);
+        model.add_constraint(Constraint::AllDifferent(vec![a, b, c]));
+        let domains = propagated(&model, AllDifferentFiltering::Pairwise).unwrap();
+        assert_eq!(domains, vec![Domain::new([4]), Domain::new([5]), Domain::new([6])]);
+    }
+
+    #[test]
+    fn sum_narrows_bounds() {
+        let mut model = Model::new();
+        let a = model.add_variable(0..=9);
+        let b = model.add_variable(0..=9);
+        model.add_constraint(Constraint::Sum { variables: vec![a, b], total: 17 });
+        let domains = propagated(&model, AllDifferentFiltering::Matching).unwrap();
+        assert_eq!(domains, vec![Domain::new([8, 9]), Domain::new([8, 9])]);
+
+        let mut model = Model::new();
+        let a = model.add_variable(0..=9);
+        let b = model.add_variable(0..=9);
+        model.add_constraint(Constraint::Sum { variables: vec![a, b], total: 19 });
+        assert!(propagated(&model, AllDifferentFiltering::Matching).is_none());
+    }
+
+    #[test]
+    fn less_narrows_bounds() {
+        let mut model = Model::new();
+        let a = model.add_variable(1..=3);
+        let b = model.add_variable(1..=3);
+        let c = model.add_variable(1..=3);
+        model.add_constraint(Constraint::Less(a, b));
+        model.add_constraint(Constraint::Less(b, c));
+        let domains = propagated(&model, AllDifferentFiltering::Matching).unwrap();
+        assert_eq!(domains, vec![Domain::new([1]), Domain::new([2]), Domain::new([3])]);
+
+        let mut model = Model::new();
+        let a = model.add_variable(1..=3);
+        let b = model.add_variable(1..=3);
+        model.add_constraint(Constraint::Less(a, b));
+        model.add_constraint(Constraint::Less(b, a));
+        assert!(propagated(&model, AllDifferentFiltering::Matching).is_none());
+    }
+
+    #[test]
+    fn engine_solves_small_model() {
+        // A 3x3 Latin square with its first row ordered and its diagonal adding up to 3
+        for filtering in [AllDifferentFiltering::Pairwise, AllDifferentFiltering::Matching] {
+            let mut model = Model::new();
+            let cells: Vec<usize> = (0..9).map(|_| model.add_variable(1..=3)).collect();
+            for i in 0..3 {
+                model.add_constraint(Constraint::AllDifferent((0..3).map(|j| cells[i * 3 + j]).collect()));
+                model.add_constraint(Constraint::AllDifferent((0..3).map(|j| cells[j * 3 + i]).collect()));
+            }
+            model.add_constraint(Constraint::Less(cells[0], cells[1]));
+            model.add_constraint(Constraint::Less(cells[1], cells[2]));
+            model.add_constraint(Constraint::Sum { variables: vec![cells[0], cells[4], cells[8]], total: 3 });
+            let solution = Engine::new(FirstFail, filtering).solve(&model);
+            assert_eq!(solution, Some(vec![1, 2, 3, 3, 1, 2, 2, 3, 1]), "{:?}", filtering);
+
+            // The only such square has a 3 below the first row's 3
+            model.add_constraint(Constraint::NotEqual(cells[2], cells[3]));
+            assert_eq!(Engine::new(InputOrder, filtering).solve(&model), None, "{:?}", filtering);
+        }
+    }
+
+    #[test]
+    fn engine_fails_on_empty_domains() {
+        for filtering in [AllDifferentFiltering::Pairwise, AllDifferentFiltering::Matching] {
+            // Unconstrained
+            let mut model = Model::new();
+            model.add_variable(1..=2);
+            model.add_variable([]);
+            assert_eq!(Engine::new(FirstFail, filtering).solve(&model), None, "{:?}", filtering);
+
+            // Only under an AllDifferent, which pairwise filtering never revises while nothing is fixed
+            let mut model = Model::new();
+            let a = model.add_variable(1..=2);
+            let b = model.add_variable([]);
+            model.add_constraint(Constraint::AllDifferent(vec![a, b]));
+            assert_eq!(Engine::new(FirstFail, filtering).solve(&model), None, "{:?}", filtering);
+        }
+    }
+}
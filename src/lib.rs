@@ -1 +1,5 @@
-mod utils;
\ No newline at end of file
+#[macro_use] extern crate prettytable;
+
+pub mod csp;
+pub mod sudoku;
+pub mod utils;
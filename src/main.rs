@@ -1,34 +1,50 @@
 use std::fs::File;
 use std::io::{prelude::*, BufReader};
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use csv::Writer;
-mod sudoku;
-mod utils;
-#[macro_use] extern crate prettytable;
-use prettytable::{Table, Row, Cell};
 
-
-use crate::sudoku::{Sudoku, BruteForceSolver, RuleBasedSolver, StochasticSolver, DeepDFSSolver, Solver};
+use sudoku::sudoku::{Sudoku, BruteForceSolver, RuleBasedSolver, StochasticSolver, AnnealingConfig, GeneticSolver, GeneticConfig, AntColonySolver, AntColonyConfig, LocalSearchSolver, LocalSearchConfig, StoppingCriterion, RestartPolicy, TabuSearch, LateAcceptance, DeepDFSSolver, SatSolver, CspSolver, IlpSolver, FastSolver, DlxSolver, ParallelSolver, Solver, Budget};
+use sudoku::csp::AllDifferentFiltering;
 
 fn main() {
     let mut writer = Writer::from_path("./data/output.csv").unwrap();
-    writer.write_record(&["Puzzle", "Model", "Time", "Correct"]).unwrap();
+    writer.write_record([
+        "Puzzle", "Model", "Time", "Outcome", "Correct",
+        "Nodes", "Backtracks", "Guesses", "MaxDepth", "Assigns", "Eliminates",
+        "Iterations", "AcceptedMoves", "Restarts", "RelaxationIntegral", "Techniques",
+    ]).unwrap();
 
     // Get the first line (puzzle) from the file
     let first_line = {
         let file = File::open("./data/easy.txt").unwrap();
-        let mut reader = BufReader::new(file);
+        let reader = BufReader::new(file);
         reader.lines().next().unwrap().unwrap()
     };
 
-    let first_sudoku = Sudoku::new(Some(&first_line)).unwrap();
-
-    // Instantiate the solvers using the first puzzle
+    // Instantiate the solvers
     let mut solvers: Vec<Box<dyn Solver>> = vec![
         Box::new(BruteForceSolver::new()),
         Box::new(RuleBasedSolver::new()),
         Box::new(DeepDFSSolver::new()),
-        Box::new(StochasticSolver::new(10000.0, 0.999, first_sudoku.clone())),
+        Box::new(SatSolver::new()),
+        Box::new(CspSolver::new(AllDifferentFiltering::Matching)),
+        Box::new(IlpSolver::new()),
+        Box::new(FastSolver::new()),
+        Box::new(DlxSolver::new()),
+        Box::new(ParallelSolver::new()),
+        Box::new(StochasticSolver::new(AnnealingConfig::default())),
+        Box::new(GeneticSolver::new(GeneticConfig::default())),
+        Box::new(AntColonySolver::new(AntColonyConfig::default())),
+        Box::new(LocalSearchSolver::new(TabuSearch::new(10), LocalSearchConfig {
+            stopping: StoppingCriterion::Iterations(100_000),
+            restart: RestartPolicy::Stalled { iterations: 2_000 },
+            seed: None,
+        })),
+        Box::new(LocalSearchSolver::new(LateAcceptance::new(20), LocalSearchConfig {
+            stopping: StoppingCriterion::Iterations(2_000_000),
+            restart: RestartPolicy::Stalled { iterations: 100_000 },
+            seed: None,
+        })),
     ];
 
     // Re-instantiate the BufReader
@@ -43,18 +59,37 @@ fn main() {
             // solver.reset(); // reset the solver state for a new puzzle
 
             let mut sudoku_clone = sudoku.clone();
+            // A pathological puzzle must not hold up the whole run
+            let budget = Budget::new().with_time_limit(Duration::from_secs(60));
             let start = Instant::now();
             solver.initialize_candidates(&mut sudoku_clone);
-            let result = solver.solve(&mut sudoku_clone);
+            let outcome = solver.solve(&mut sudoku_clone, &budget);
             let duration = start.elapsed();
 
             let is_correct = solver.is_correct(&mut sudoku_clone);
+            let stats = solver.stats();
+            // Technique:placements/eliminations, separated by semicolons
+            let techniques: Vec<String> = stats.techniques.iter()
+                .map(|(technique, tally)| format!("{}:{}/{}", technique, tally.placements, tally.eliminations))
+                .collect();
 
-            writer.write_record(&[
-                &line, 
-                &solver.name(), 
-                &format!("{:?}", duration), 
-                &format!("{}", is_correct)
+            writer.write_record([
+                &line,
+                &solver.name(),
+                &format!("{:?}", duration),
+                &format!("{}", outcome),
+                &format!("{}", is_correct),
+                &stats.nodes.to_string(),
+                &stats.backtracks.to_string(),
+                &stats.guesses.to_string(),
+                &stats.max_depth.to_string(),
+                &stats.assigns.to_string(),
+                &stats.eliminates.to_string(),
+                &stats.iterations.to_string(),
+                &stats.accepted_moves.to_string(),
+                &stats.restarts.to_string(),
+                &stats.relaxation_integral.map_or(String::new(), |integral| integral.to_string()),
+                &techniques.join(";"),
             ]).unwrap();
 
             writer.flush().unwrap();
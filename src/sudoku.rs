@@ -1,16 +1,27 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::clone::Clone;
 use std::{str, vec};
 use rand::Rng;
 use crate::utils;
+use crate::csp::{AllDifferentFiltering, Constraint, Domain, Engine, FirstFail, Model, Monitor};
 use rand::prelude::SliceRandom;
-use rand::thread_rng;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use prettytable::{Table, Row, Cell};
+use itertools::Itertools;
+use rayon::prelude::*;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
 
 
 // use std::collections::LinkedList;
 // use std::rc::Rc;
 // use itertools::Itertools;
+// Digits of a grid by row and column, 0 for an empty cell
+pub type Grid = [[u8; 9]; 9];
+
 // Basic structure of a sudoku board
 #[derive(Clone)]
 pub struct Sudoku {
@@ -21,6 +32,42 @@ pub struct Sudoku {
     box_peers: HashMap<String, HashSet<String>>,
     peers: HashMap<String, HashSet<String>>,
     candidates: HashMap<String, HashSet<usize>>,
+    // Set while forcing chains propagate a premise, to record why each deduction followed
+    implications: Option<Vec<Implication>>,
+    // While implications are recorded, the depth past which propagation stops deducing
+    implication_limit: usize,
+    // Calls to assign and eliminate so far, propagation included (see SolveStats)
+    assigns: usize,
+    eliminates: usize,
+    // Set while a solve is observed: the placements (true) and eliminations (false) made since
+    // they were last reported (see report_changes)
+    changes: Option<Vec<(String, usize, bool)>>,
+    // Set while hint runs a technique: eliminate removes the candidate without propagating it, so
+    // that what the technique itself deduced is left to see
+    shallow: bool,
+}
+
+// A link of an implication tree: a placement or elimination (or the contradiction reached)
+// and the index of the link that forced it, None for the premise itself. depth counts the links
+// from the premise, which has depth 0.
+#[derive(Clone, Debug)]
+struct Implication {
+    cell: String,
+    digit: usize,
+    placed: bool,
+    contradiction: Option<String>,
+    cause: Option<usize>,
+    depth: usize,
+}
+
+impl std::fmt::Display for Implication {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match &self.contradiction {
+            Some(reason) => write!(f, "contradiction: {}", reason),
+            None if self.placed => write!(f, "{}={}", self.cell, self.digit),
+            None => write!(f, "{}<>{}", self.cell, self.digit),
+        }
+    }
 }
 
 impl Sudoku {
@@ -47,8 +94,8 @@ impl Sudoku {
                 unitlist.push(utils::cross(&[*r], &cols));
             }
             // Boxes
-            for rs in vec![&rows[0..3], &rows[3..6], &rows[6..9]] {
-                for cs in vec![&cols[0..3], &cols[3..6], &cols[6..9]] {
+            for rs in [&rows[0..3], &rows[3..6], &rows[6..9]] {
+                for cs in [&cols[0..3], &cols[3..6], &cols[6..9]] {
                     unitlist.push(utils::cross(rs, cs));
                 }
             }
@@ -122,6 +169,12 @@ impl Sudoku {
             box_peers,
             peers,
             candidates: HashMap::new(),
+            implications: None,
+            implication_limit: usize::MAX,
+            assigns: 0,
+            eliminates: 0,
+            changes: None,
+            shallow: false,
         };
     
         Ok(sudoku)
@@ -135,8 +188,8 @@ impl Sudoku {
     
         let mut grid: [[u8; 9]; 9] = [[0; 9]; 9]; // Initialise an empty 2D array
     
-        for row in 0..9 {
-            for col in 0..9 {
+        for (row, grid_row) in grid.iter_mut().enumerate() {
+            for (col, entry) in grid_row.iter_mut().enumerate() {
                 let c = s.chars().nth(9*row + col).unwrap();
                 let value = if c == '.' {
                     0
@@ -146,7 +199,7 @@ impl Sudoku {
                 if value > 9 {
                     return Err("Each digit must be from 0 to 9.");
                 }
-                grid[row][col] = value as u8;
+                *entry = value as u8;
             }
         }
     
@@ -195,14 +248,28 @@ impl Sudoku {
     
 
     fn assign(&mut self, cell: &str, digit: usize) -> bool {
+        self.assign_because(cell, digit, None)
+    }
+
+    fn eliminate(&mut self, cell: &str, digit: usize) -> bool {
+        self.eliminate_because(cell, digit, None)
+    }
+
+    // cause is the recorded implication that forced this assignment (see Implication)
+    fn assign_because(&mut self, cell: &str, digit: usize, cause: Option<usize>) -> bool {
+        self.assigns += 1;
+        if self.too_deep(cause) {
+            return true;
+        }
         // println!("Assigning {} to {}", digit, cell);
         // other_values is a set of digits that are not equal to the assigned digit
         let mut other_values: HashSet<usize> = self.candidates[cell].clone();
         other_values.remove(&digit);
+        let cause = if other_values.is_empty() { cause } else { self.imply(cell, digit, true, cause) };
 
         // We try to eliminate all other values from the cell
         for d2 in other_values {
-            if !self.eliminate(cell, d2) {
+            if !self.eliminate_because(cell, d2, cause) {
                 // If elimination of any value results in a contradiction, we return false
                 return false;
             }
@@ -210,48 +277,114 @@ impl Sudoku {
         true
     }
 
-    fn eliminate(&mut self, cell: &str, digit: usize) -> bool {
-        let mut tasks = vec![(cell.to_string(), digit)];
+    fn eliminate_because(&mut self, cell: &str, digit: usize, cause: Option<usize>) -> bool {
+        let mut tasks = vec![(cell.to_string(), digit, cause)];
         let mut processed = HashSet::new(); 
-        while let Some(task) = tasks.pop() {
-            let (cell, digit) = task.clone();
-            if processed.contains(&task) {
+        while let Some((cell, digit, cause)) = tasks.pop() {
+            // Each task stands for one eliminate call of the recursive formulation
+            self.eliminates += 1;
+            if self.too_deep(cause) {
                 continue;
             }
-            processed.insert(task);
-            if !self.candidates[&cell].contains(&digit) {
+            if !processed.insert((cell.clone(), digit)) {
                 continue;
             }
-            if self.candidates[&cell].len() > 1 {
-                self.candidates.get_mut(&cell).unwrap().remove(&digit);
+            if !self.candidates[&cell].contains(&digit) {
+                continue;
             }
-            if self.candidates[&cell].is_empty() {
-                println!("Contradiction: {} has no candidates left", cell);
+            if self.candidates[&cell].len() == 1 {
+                self.contradict(cause, format!("{} has no candidates left", cell));
                 return false;
             }
-            else if self.candidates[&cell].len() == 1 {
+            self.candidates.get_mut(&cell).unwrap().remove(&digit);
+            let link = self.imply(&cell, digit, false, cause);
+            self.change(&cell, digit, false);
+            if self.shallow || self.too_deep(link) {
+                continue;
+            }
+            if self.candidates[&cell].len() == 1 {
                 let d2 = *self.candidates[&cell].iter().next().unwrap();
+                self.change(&cell, d2, true);
+                // Unless this elimination came from assigning d2 itself
+                let assigned = self.implications.as_ref().zip(cause)
+                    .is_some_and(|(implications, c)| implications[c].placed && implications[c].cell == cell && implications[c].digit == d2);
+                let placed = if assigned { cause } else { self.imply(&cell, d2, true, link) };
                 let peers = self.peers[&cell].clone();
                 for s2 in peers.iter() {
-                    tasks.push((s2.clone(), d2));
+                    tasks.push((s2.clone(), d2, placed));
                 }
             }
-            let units = vec![self.row_peers[&cell].clone(), self.col_peers[&cell].clone(), self.box_peers[&cell].clone()];
-            for unit in units.iter() {
+            let units = [self.row_peers[&cell].clone(), self.col_peers[&cell].clone(), self.box_peers[&cell].clone()];
+            for (u, unit) in units.iter().enumerate() {
                 let d_places: Vec<_> = unit.iter().filter(|&s| self.candidates[s].contains(&digit)).cloned().collect();
                 if d_places.is_empty() {
-                    println!("Contradiction: {:?} has no place for {}", unit, digit);
+                    self.contradict(link, format!("no place for {} in {}", digit, Self::unit_name(&cell, u)));
                     return false;
                 } 
-                else if d_places.len() == 1 {
-                    if !self.assign(&d_places[0], digit) {
-                        return false;
-                    }
+                else if d_places.len() == 1 && !self.assign_because(&d_places[0], digit, link) {
+                    return false;
                 }
             }
         }
         true
     }
+
+    // Name of the row (unit 0), column (1) or box (2) of a cell
+    fn unit_name(cell: &str, unit: usize) -> String {
+        let (row, col) = utils::cell_to_coords(cell);
+        match unit {
+            0 => format!("row {}", &cell[..1]),
+            1 => format!("column {}", col + 1),
+            _ => format!("box {}", row / 3 * 3 + col / 3 + 1),
+        }
+    }
+
+    // Record an implication while a forcing chain premise is propagated, returning its index
+    fn imply(&mut self, cell: &str, digit: usize, placed: bool, cause: Option<usize>) -> Option<usize> {
+        let implications = self.implications.as_mut()?;
+        let depth = cause.map_or(0, |cause| implications[cause].depth + 1);
+        implications.push(Implication { cell: cell.to_string(), digit, placed, contradiction: None, cause, depth });
+        Some(implications.len() - 1)
+    }
+
+    // Whether a deduction forced by cause would lie past implication_limit
+    fn too_deep(&self, cause: Option<usize>) -> bool {
+        match (self.implications.as_ref(), cause) {
+            (Some(implications), Some(cause)) => implications[cause].depth >= self.implication_limit,
+            _ => false,
+        }
+    }
+
+    fn change(&mut self, cell: &str, digit: usize, placed: bool) {
+        if let Some(changes) = self.changes.as_mut() {
+            changes.push((cell.to_string(), digit, placed));
+        }
+    }
+
+    // Pass the changes recorded since the last call on to observer. The placement of guess, made
+    // by the solver rather than deduced, is reported first.
+    fn report_changes(&mut self, observer: &mut dyn SolverObserver, guess: Option<(&str, usize)>) {
+        let Some(changes) = self.changes.as_mut() else {
+            return;
+        };
+        if let Some((cell, digit)) = guess {
+            observer.on_assign(cell, digit, true);
+        }
+        for (cell, digit, placed) in changes.drain(..) {
+            if !placed {
+                observer.on_eliminate(&cell, digit);
+            } else if guess != Some((&cell, digit)) {
+                observer.on_assign(&cell, digit, false);
+            }
+        }
+    }
+
+    fn contradict(&mut self, cause: Option<usize>, reason: String) {
+        if let Some(implications) = self.implications.as_mut() {
+            let depth = cause.map_or(0, |cause| implications[cause].depth + 1);
+            implications.push(Implication { cell: String::new(), digit: 0, placed: false, contradiction: Some(reason), cause, depth });
+        }
+    }
     
 
     // Check if a given number is valid in a given cell
@@ -288,8 +421,8 @@ impl Sudoku {
 
     // Check the unique elements in a given array
     fn unique_elements(arr: [u8; 9]) -> i32 {
-        let unique_set: std::collections::HashSet<_> = arr.iter().filter(|&&x| x != 0).collect();
-        unique_set.len() as i32
+        let mask = arr.iter().filter(|&&x| x != 0).fold(0u16, |mask, &x| mask | 1 << x);
+        mask.count_ones() as i32
     }
 
     // Check if the board is solved
@@ -337,14 +470,41 @@ impl Sudoku {
                 let cell = utils::coords_to_cell(row, col);
                 let candidates = self.candidates.get(&cell).unwrap().clone();
                 if candidates.len() == 1 {
-                    self.board[row][col] = candidates.iter().next().unwrap().clone() as u8;
+                    self.board[row][col] = *candidates.iter().next().unwrap() as u8;
                 }
             }
         }
-        return self.board_correct();
+        self.board_correct()
+    }
+
+    // The outcome of a search that went through every possibility without finding a solution
+    fn no_solution(&self) -> SolveOutcome {
+        if self.board_correct() {
+            SolveOutcome::Unsolvable("the search found no way to fill the grid".to_string())
+        } else {
+            SolveOutcome::Unsolvable("the givens repeat a digit in a unit".to_string())
+        }
+    }
+
+    // Once every cell is down to one candidate: the grid, if those digits fit together
+    fn candidates_outcome(&mut self) -> SolveOutcome {
+        if self.candidates_correct() {
+            SolveOutcome::Solved(self.board)
+        } else {
+            SolveOutcome::Unsolvable("the remaining candidates repeat a digit in a unit".to_string())
+        }
     }
 
     fn print_candidates(&self) {
+        self.print_coloring(&[]);
+    }
+
+    // Print the candidates with some of them coloured, as (cell, digit, colour) triples.
+    // Coloured candidates are bracketed by colour: (d) [d] {d} <d>, and the cell takes the colour
+    // of its first coloured candidate.
+    pub fn print_coloring(&self, coloring: &[(String, usize, usize)]) {
+        const BRACKETS: [(char, char); 4] = [('(', ')'), ('[', ']'), ('{', '}'), ('<', '>')];
+        const COLOR_SPECS: [&str; 4] = ["cFB", "cFY", "cFC", "cFM"];
         let mut table = Table::new();
     
         // Print row index
@@ -361,11 +521,22 @@ impl Sudoku {
                 candidates.sort();
     
                 let mut candidates_string = String::new();
+                let mut color_spec = if candidates.len() == 1 { "cFG" } else { "cFR" };
+                let mut colored = false;
                 for candidate in candidates.iter() {
-                    candidates_string.push_str(&candidate.to_string());
+                    match coloring.iter().find(|(c, d, _)| *c == cell && d == candidate) {
+                        Some((_, _, color)) => {
+                            let (open, close) = BRACKETS[color % 4];
+                            candidates_string.push_str(&format!("{}{}{}", open, candidate, close));
+                            if !colored {
+                                color_spec = COLOR_SPECS[color % 4];
+                                colored = true;
+                            }
+                        }
+                        None => candidates_string.push_str(&candidate.to_string()),
+                    }
                 }
                 
-                let color_spec = if candidates.len() == 1 { "cFG" } else { "cFR" };
                 row_vec.push(Cell::new(&candidates_string).style_spec(color_spec));
     
                 // Add vertical separator every 3 columns
@@ -386,7 +557,7 @@ impl Sudoku {
         table.printstd();
     }
 
-    fn print_board(&self){
+    pub fn print_board(&self){
         // print self.board with the same format as print_candidates
         let mut table = Table::new();
         // Print row index
@@ -404,7 +575,7 @@ impl Sudoku {
                     digit_string.push_str(&digit.to_string());
                 }
                 else{
-                    digit_string.push_str(" ");
+                    digit_string.push(' ');
                 }
                 let color_spec = if digit != 0 { "cFG" } else { "cFR" };
                 row_vec.push(Cell::new(&digit_string).style_spec(color_spec));
@@ -427,1065 +598,8271 @@ impl Sudoku {
     }
 }
 
-pub trait Solver {
-    fn solve(&mut self, board: &mut Sudoku) -> bool;
-    fn name(&self) -> String;
-    fn initialize_candidates(&mut self, sudoku: &mut Sudoku);
-    fn is_correct(&self, board: &mut Sudoku) -> bool;
+// How a solve ended
+#[derive(Clone, Debug, PartialEq)]
+pub enum SolveOutcome {
+    // The completed grid
+    Solved(Grid),
+    // The puzzle has no solution, and why the solver knows
+    Unsolvable(String),
+    // The puzzle has more than one solution. Only the exact solvers asked to check for this report
+    // it (see BruteForceSolver::with_check_unique), leaving one of the solutions on the board.
+    MultipleSolutions,
+    // The solver gave up at one of its limits; its best attempt is left on the board
+    Stopped(Limit),
+    // Logic stalled before the grid was complete: the candidates left
+    PartiallySolved(HashMap<String, HashSet<usize>>),
 }
 
-pub struct BruteForceSolver;
-// Brute force solver.
-// This solver will try every possible candidate in every empty cell.
-// If it hits a dead end, it will backtrack and try a different candidate.
+impl SolveOutcome {
+    pub fn is_solved(&self) -> bool {
+        matches!(self, SolveOutcome::Solved(_))
+    }
+}
 
-impl Solver for BruteForceSolver {
-    fn solve(&mut self, board: &mut Sudoku) -> bool {
-        let mut min_candidates = 10;
-        let mut cell_to_fill = None;
-    
-        for row in 0..9 {
-            for col in 0..9 {
-                if board.board[row][col] == 0 {
-                    let candidates = &board.candidates[&utils::coords_to_cell(row, col)];
-                    let num_candidates = candidates.len();
-                    if num_candidates < min_candidates {
-                        min_candidates = num_candidates;
-                        cell_to_fill = Some((row, col));
-                    }
-                }
+impl std::fmt::Display for SolveOutcome {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            SolveOutcome::Solved(_) => write!(f, "Solved"),
+            SolveOutcome::Unsolvable(reason) => write!(f, "Unsolvable ({})", reason),
+            SolveOutcome::MultipleSolutions => write!(f, "MultipleSolutions"),
+            SolveOutcome::Stopped(limit) => write!(f, "Stopped ({})", limit),
+            SolveOutcome::PartiallySolved(candidates) => {
+                let open = candidates.values().filter(|candidates| candidates.len() > 1).count();
+                write!(f, "PartiallySolved ({} cells open)", open)
             }
         }
-    
-        match cell_to_fill {
-            None => {
-                // No empty cells left, solution found
-                return true;
-            },
-            Some((row, col)) => {
-                let cell = utils::coords_to_cell(row, col);
-                let candidates = board.candidates[&cell].clone(); // Clone the candidates for the first empty cell
+    }
+}
 
-                for &num in candidates.iter() {
-                    board.print_board();
-                    println!("Trying to fill {} with {}", cell, num);
-                    if board.is_valid(row, col, num) {
-                        println!("Valid");
-                        board.board[row][col] = num as u8; // Now it's only placed on the board after it's been verified to be valid
-                        if self.solve(board) {
-                            println!("Brute force solver finished.");
-                            return true;
-                        } else {
-                            board.board[row][col] = 0; // Undo the assignment only if the recursive call to solve failed
-                        }
-                    }
-                }                
-            }
+// The limit a solver ran into
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Limit {
+    Iterations(usize),
+    Generations(usize),
+    // Iterations without a better best cost
+    Stalled(usize),
+    // Search nodes allowed by the Budget
+    Nodes(usize),
+    // The Budget's deadline passed
+    Deadline,
+    // The Budget's cancellation flag was set
+    Cancelled,
+}
+
+impl std::fmt::Display for Limit {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Limit::Iterations(limit) => write!(f, "{} iterations", limit),
+            Limit::Generations(limit) => write!(f, "{} generations", limit),
+            Limit::Stalled(limit) => write!(f, "{} iterations without improvement", limit),
+            Limit::Nodes(limit) => write!(f, "{} nodes", limit),
+            Limit::Deadline => write!(f, "deadline"),
+            Limit::Cancelled => write!(f, "cancelled"),
         }
-        false // No solution found
     }
+}
 
-    fn name(&self) -> String {
-        "Brute Force Solver".to_string()
+// Counters gathered during a solve, to explain timing differences between solvers rather than
+// just measure them. A solver leaves the counters it has no use for at zero.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SolveStats {
+    // Search nodes visited: partial assignments the search (or branch-and-bound) looked at
+    pub nodes: usize,
+    // Guesses undone, or for clause learning the conflicts that forced a backjump
+    pub backtracks: usize,
+    // Values tried on a cell or variable that logic had not forced
+    pub guesses: usize,
+    pub max_depth: usize,
+    // Calls to Sudoku::assign and Sudoku::eliminate, propagation included
+    pub assigns: usize,
+    pub eliminates: usize,
+    // What each RuleBasedSolver technique achieved, by technique name
+    pub techniques: BTreeMap<String, TechniqueStats>,
+    // Local search: iterations (generations for the genetic solver), moves made and grid refills
+    pub iterations: usize,
+    pub accepted_moves: usize,
+    pub restarts: usize,
+    // IlpSolver: whether the LP relaxation at the root was already integral
+    pub relaxation_integral: Option<bool>,
+}
+
+// Placements are cells a technique left with a single candidate, eliminations the candidates
+// it removed (those removed by propagating its placements included)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TechniqueStats {
+    pub placements: usize,
+    pub eliminations: usize,
+}
+
+impl SolveStats {
+    // Start a solve of board: reset the counters and return the assign/eliminate counts to
+    // measure from (see propagation)
+    fn start(&mut self, board: &Sudoku) -> (usize, usize) {
+        *self = SolveStats::default();
+        (board.assigns, board.eliminates)
     }
 
-    fn initialize_candidates(&mut self, board: &mut Sudoku) {
-        board.initialize_candidates_heavy();
-        board.print_candidates();
+    // Record the assign/eliminate calls made on board since start
+    fn propagation(&mut self, board: &Sudoku, start: (usize, usize)) {
+        self.assigns += board.assigns - start.0;
+        self.eliminates += board.eliminates - start.1;
     }
 
-    fn is_correct(&self, board: &mut Sudoku) -> bool {
-        board.board_correct()
+    fn descend(&mut self, depth: usize) {
+        self.max_depth = self.max_depth.max(depth);
     }
-}
 
-impl BruteForceSolver {
-    pub fn new() -> BruteForceSolver {
-        BruteForceSolver
+    // Add the counters of a search run separately, e.g. by another thread or a fallback solver
+    fn add(&mut self, other: &SolveStats) {
+        self.nodes += other.nodes;
+        self.backtracks += other.backtracks;
+        self.guesses += other.guesses;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.assigns += other.assigns;
+        self.eliminates += other.eliminates;
+        for (technique, tally) in &other.techniques {
+            let entry = self.techniques.entry(technique.clone()).or_default();
+            entry.placements += tally.placements;
+            entry.eliminations += tally.eliminations;
+        }
+        self.iterations += other.iterations;
+        self.accepted_moves += other.accepted_moves;
+        self.restarts += other.restarts;
     }
 }
 
-// Constraint programming with forward propagation and backtracking.
-
-pub struct DeepDFSSolver {
-    queue: Vec<String>
+// How often the inner loops of the solvers look at the clock and the cancellation flag, in nodes
+// or iterations
+const BUDGET_POLL_INTERVAL: usize = 1024;
+
+// What a solve may spend before it gives up with SolveOutcome::Stopped, its statistics so far
+// still available from Solver::stats. Unlimited unless set.
+#[derive(Clone, Debug, Default)]
+pub struct Budget {
+    deadline: Option<Instant>,
+    // Search nodes, for the solvers that branch
+    nodes: Option<usize>,
+    // Iterations (generations for the genetic solver, colony iterations for ant colony), for
+    // the metaheuristics; on top of the limits in their own configs
+    iterations: Option<usize>,
+    // Set from another thread to stop the solve
+    cancel: Option<Arc<AtomicBool>>,
 }
 
-impl DeepDFSSolver {
-    // Constructor for CSPSolver
+impl Budget {
     pub fn new() -> Self {
-        DeepDFSSolver {
-            queue: Vec::new()
-        }
+        Self::default()
     }
 
-    fn solved(&self, board: &Sudoku) -> bool {
-        // Check if the board is solved by verifying that every cell has exactly one candidate
-        for cell in board.candidates.keys() {
-            if board.candidates.get(cell).unwrap().len() != 1 {
-                return false;
-            }
-        }
-        true
+    pub fn with_deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
     }
-}
 
+    // A deadline this long from now
+    pub fn with_time_limit(self, limit: Duration) -> Self {
+        self.with_deadline(Instant::now() + limit)
+    }
 
-impl Solver for DeepDFSSolver {
-    fn solve(&mut self, board: &mut Sudoku) -> bool {
-            
-        let mut depth = 1;
-        println!("Depth: {}", depth);
-        println!("Queue: {:?}", self.queue);
-        while !self.solved(board) {
-            let mut counter = 1;
-            let mut index = 0;
-            for cell in self.queue.clone().iter() {
-                println!("Cell: {}", cell);
-                println!("Cell Candidates: {:?}", board.candidates[cell]);
-                for digit in board.candidates[cell].clone().iter() {
-                    println!("Digit: {}", digit);
-                    while counter < depth {
-                        let candidates_copy: HashMap<String, HashSet<usize>> = board
-                            .candidates
-                            .iter()
-                            .map(|(key, value)| (key.clone(), value.clone()))
-                            .collect();  // Make a copy of the board
-                        // println!("board.candidates at start of loop: {:?}", board.candidates);
-                        // println!("candidates_copy at start of loop: {:?}", candidates_copy);
-                        if !board.assign(&self.queue[0], *digit){
-                            println!("CSPSOLVER: Assigning {} to {} failed", digit, cell);
-                            // println!("board.candidates before backtracking: {:?}", board.candidates);
-                            // println!("candidates_copy before backtracking: {:?}", candidates_copy);
-                            board.candidates = candidates_copy.clone();  // Revert the board
-                            // println!("board.candidates after backtracking: {:?}", board.candidates);
-                            if !board.eliminate(&self.queue[0], *digit) {
-                                // big problem...
-                                println!("CSPSOLVER: Eliminating {} from {} failed", digit, cell);
-                                board.candidates = candidates_copy;  // Revert the board
-                                return false;
-                            }
-                            break;
-                        }
-                        println!("CSPSOLVER: Assigning {} to {} succeeded", digit, cell);
-                        // digit is good, go one layer deeper.
-                        counter += 1;
-                        println!("Counter: {}", counter);
-                    }
-                }
-                if board.candidates[cell].len() == 1 {
-                    self.queue.remove(index);
-                    if index != 0 {
-                        index -= 1;
-                    }
-                    break;
-                }
-                else{
-                index += 1;
-                }
-            }
-            depth += 1;
-            println!("Depth: {}", depth);
-            println!("Queue: {:?}", self.queue);
-            board.print_candidates();
-        }
-        // solved
-        println!("DeepDFSsolver finished.");
-        return true;
+    pub fn with_node_limit(mut self, nodes: usize) -> Self {
+        self.nodes = Some(nodes);
+        self
     }
 
-    fn name(&self) -> String {
-        "Iterative Deepening DFS Solver".to_string()
+    pub fn with_iteration_limit(mut self, iterations: usize) -> Self {
+        self.iterations = Some(iterations);
+        self
     }
 
-    fn initialize_candidates(&mut self, board: &mut Sudoku) {
-        board.initialize_candidates_heavy();
+    // The solve stops soon after the flag is set
+    pub fn with_cancel_flag(mut self, cancel: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
 
-        // Priority queue for candidates
-        // cells must have more than 1 candidate
-        // and be sorted by the number of candidates
-        self.queue = board.cells.iter()
-            .filter(|cell| board.candidates[*cell].len() > 1)
-            .cloned()
-            .collect();
+    // The node or iteration limit reached, if any
+    fn spent(&self, nodes: usize, iterations: usize) -> Option<Limit> {
+        match (self.nodes, self.iterations) {
+            (Some(limit), _) if nodes >= limit => Some(Limit::Nodes(limit)),
+            (_, Some(limit)) if iterations >= limit => Some(Limit::Iterations(limit)),
+            _ => None,
+        }
+    }
 
-        // sort by number of candidates (value, ascending)
-        self.queue.sort_by_key(|cell| board.candidates[cell].len());
+    // Whether the deadline has passed or the solve was cancelled
+    fn expired(&self) -> Option<Limit> {
+        if self.cancel.as_ref().is_some_and(|cancel| cancel.load(Ordering::Relaxed)) {
+            return Some(Limit::Cancelled);
+        }
+        self.deadline.filter(|deadline| Instant::now() >= *deadline).map(|_| Limit::Deadline)
+    }
 
-        board.print_candidates();
+    // spent, and expired every BUDGET_POLL_INTERVAL nodes and iterations, for loops too fast
+    // to read the clock every time round
+    fn check(&self, nodes: usize, iterations: usize) -> Option<Limit> {
+        self.spent(nodes, iterations)
+            .or_else(|| (nodes + iterations).is_multiple_of(BUDGET_POLL_INTERVAL).then(|| self.expired()).flatten())
     }
 
-    fn is_correct(&self, board: &mut Sudoku) -> bool {
-        board.candidates_correct()
+    // spent and expired, for loops slow enough to read the clock every time round
+    fn exhausted(&self, nodes: usize, iterations: usize) -> Option<Limit> {
+        self.spent(nodes, iterations).or_else(|| self.expired())
     }
 }
 
-
-pub struct RuleBasedSolver{
-    cells_with_candidates: Vec<String>
+// Follows a solve as it happens, to show its progress, log it or record it (see
+// Solver::solve_observed). Every callback does nothing unless overridden. The solvers working on
+// Sudoku's candidates report what propagation did after each guess or rule, in the order it
+// happened. Observers must be Send, as ParallelSolver reports from its worker threads.
+pub trait SolverObserver: Send {
+    // A digit placed in a cell; guess is set when the solver tried it rather than deduced it
+    fn on_assign(&mut self, _cell: &str, _digit: usize, _guess: bool) {}
+    // A candidate removed from a cell
+    fn on_eliminate(&mut self, _cell: &str, _digit: usize) {}
+    // The guess of digit in cell was wrong and is undone, with everything that followed from it
+    fn on_backtrack(&mut self, _cell: &str, _digit: usize) {}
+    // A RuleBasedSolver technique made progress: its placements and eliminations have just been
+    // reported, and steps holds the deductions it recorded (none for the basic techniques)
+    fn on_technique_applied(&mut self, _technique: &str, _steps: &[Step]) {}
+    // A metaheuristic iteration (generation for the genetic solver): the current cost (cells left
+    // for ant colony) and, when annealing, the temperature
+    fn on_iteration(&mut self, _iteration: usize, _cost: i32, _temperature: Option<f64>) {}
+    // The candidates of board, for the solvers working on them: as the search starts, and for
+    // RuleBasedSolver after each round of rules
+    fn on_candidates(&mut self, _board: &Sudoku) {}
+    // The solve is over; summary says how much work it took
+    fn on_finish(&mut self, _summary: &str) {}
 }
-// Rule-based solver.
-// Note that a naked tuple is accompanied by a hidden pair. So this will implement up to naked/hidden tuples. But not quads.
 
-impl Solver for RuleBasedSolver {
-    fn solve(&mut self, board: &mut Sudoku) -> bool {
+// Observes nothing; what Solver::solve uses
+pub struct NoObserver;
 
-        // If board is solved, update it
-        if self.solved(board) {           
-            println!("Rule-based solver finished.");
-            return true;
-        }
+impl SolverObserver for NoObserver {}
 
+// Prints what happens, one line per event. Metaheuristics run for millions of iterations, so
+// only every iteration_interval-th is printed.
+pub struct ConsoleObserver {
+    iteration_interval: usize,
+}
 
-        self.cells_with_candidates = board.cells.iter()
-            .filter(|cell| board.candidates[*cell].len() > 1)
-            .cloned()
-            .collect();
+impl ConsoleObserver {
+    pub fn new(iteration_interval: usize) -> Self {
+        ConsoleObserver { iteration_interval: iteration_interval.max(1) }
+    }
+}
 
-        // Loop through rules
-        loop {
-            let boardcopy = self.cells_with_candidates.clone();
-            
-            let mut changes_made = false;
+impl Default for ConsoleObserver {
+    fn default() -> Self {
+        Self::new(10_000)
+    }
+}
 
-            // Try to apply each rule in turn.
-            if self.apply_basic_rules(board) {
-                changes_made = true; 
-            }
-            if self.apply_intermediate_rules(board) {
-                changes_made = true;  
-            }
-            // if self.apply_complex_rules(board) {
-            //     changes_made = true;  
-            // }
+impl SolverObserver for ConsoleObserver {
+    fn on_assign(&mut self, cell: &str, digit: usize, guess: bool) {
+        if guess {
+            println!("Trying to fill {} with {}", cell, digit);
+        } else {
+            println!("{}={}", cell, digit);
+        }
+    }
 
-            board.print_candidates();
+    fn on_eliminate(&mut self, cell: &str, digit: usize) {
+        println!("{}<>{}", cell, digit);
+    }
 
-            if !changes_made {
-                break;
-            }
+    fn on_backtrack(&mut self, cell: &str, digit: usize) {
+        println!("Backtracking: {} is not {}", cell, digit);
+    }
 
-            // if boardcopy is the same as the board then no changes were made and we can break
-            if boardcopy == self.cells_with_candidates {
-                break;
-            }
-        }
-    
-        // If board is solved, update it
-        if self.solved(board) {           
-            println!("Rule-based solver finished.");
-            return true;
+    fn on_technique_applied(&mut self, technique: &str, steps: &[Step]) {
+        if steps.is_empty() {
+            println!("{} applied", technique);
         }
-    
-        // If board is not solved, apply brute force solver
-        else {
-            let mut dfs_solver = DeepDFSSolver::new();
-
-            // Priority queue for candidates
-            dfs_solver.queue = board.cells.iter()
-                .filter(|cell| board.candidates[*cell].len() > 1)
-                .cloned()
-                .collect();
-            dfs_solver.queue.sort_by_key(|cell| board.candidates[cell].len());
-
-            return dfs_solver.solve(board);
+        for step in steps {
+            println!("{}", step);
+            for line in &step.proof {
+                println!("    {}", line);
+            }
         }
     }
 
-    fn name(&self) -> String {
-        "Rule Based Solver".to_string()
+    fn on_iteration(&mut self, iteration: usize, cost: i32, temperature: Option<f64>) {
+        if !iteration.is_multiple_of(self.iteration_interval) {
+            return;
+        }
+        match temperature {
+            Some(temperature) => println!("Iteration {}: cost {}, temperature {:.5}", iteration, cost, temperature),
+            None => println!("Iteration {}: cost {}", iteration, cost),
+        }
     }
 
-    fn initialize_candidates(&mut self, board: &mut Sudoku) {
-        board.initialize_candidates_lw();
+    fn on_candidates(&mut self, board: &Sudoku) {
         board.print_candidates();
     }
 
-    fn is_correct(&self, board: &mut Sudoku) -> bool {
-        board.candidates_correct()
+    fn on_finish(&mut self, summary: &str) {
+        println!("{}", summary);
     }
 }
 
-impl RuleBasedSolver {
-    pub fn new() -> RuleBasedSolver {
-        RuleBasedSolver{
-            cells_with_candidates: Vec::new()
-        }
-    }
-    
-    fn apply_basic_rules(&self, board: &mut Sudoku) -> bool {
-        // Apply basic rules here: Naked Single, Hidden Single, Naked Pair, Hidden Pair
-        // Returns true if a rule could be applied, false otherwise
-        // When any rule succeeds, call the solver again
+// What a TraceStep does to the board
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TraceAction {
+    Placement,
+    Elimination,
+    // A placement the solver tried rather than deduced
+    Guess,
+    // A guess proved wrong: the board goes back to how it was before it
+    Backtrack,
+}
 
-        let mut applied = false;
+// One step of a SolveTrace: the candidate digit of cell placed, eliminated, guessed or given up.
+// technique is the RuleBasedSolver technique that made it ("Guess", "Backtrack" or "Propagation"
+// for the rest), cells the other cells its deduction rests on, and reason why it holds.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TraceStep {
+    pub action: TraceAction,
+    pub technique: String,
+    pub cell: String,
+    pub digit: usize,
+    pub cells: Vec<String>,
+    pub reason: String,
+}
 
-        if self.naked_single(board) {
-            // println!("Naked single applied");
-            applied = true;
-        }
-        if self.hidden_single(board) {
-            // println!("Hidden single applied");
-            applied = true;
-        }
-        if self.naked_pair(board) {
-            // println!("Naked pair applied");
-            applied = true;
-        }
-        if self.hidden_pair(board) {
-            // println!("Hidden pair applied");
-            applied = true;
+impl std::fmt::Display for TraceStep {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let change = match self.action {
+            TraceAction::Placement => format!("{}={}", self.cell, self.digit),
+            TraceAction::Elimination => format!("{}<>{}", self.cell, self.digit),
+            TraceAction::Guess => format!("{}={}?", self.cell, self.digit),
+            TraceAction::Backtrack => format!("{}<>{}!", self.cell, self.digit),
+        };
+        write!(f, "{}: {} ({})", self.technique, change, self.reason)
+    }
+}
+
+// Every step of a solve in order, from the puzzle and the candidates it started with, so that any
+// intermediate state can be rebuilt to see where a deduction went wrong. Recorded by
+// TraceRecorder, e.g. through Solver::solve_traced.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SolveTrace {
+    // The givens, '.' for an empty cell
+    pub puzzle: String,
+    // Candidates of each cell when the solve started (none for the solvers that do not use them)
+    pub candidates: BTreeMap<String, Vec<usize>>,
+    pub steps: Vec<TraceStep>,
+    // Set if the steps stopped replaying as they were recorded: why the first one that failed did
+    #[serde(default)]
+    pub replay_error: Option<String>,
+}
+
+impl SolveTrace {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("a trace always serializes")
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|error| error.to_string())
+    }
+
+    // The board after the first count steps, starting from the puzzle and its candidates
+    pub fn state(&self, count: usize) -> Result<Sudoku, String> {
+        let mut board = Sudoku::new(Some(&self.puzzle)).map_err(|error| error.to_string())?;
+        board.candidates = self.candidates.iter()
+            .map(|(cell, digits)| (cell.clone(), digits.iter().cloned().collect()))
+            .collect();
+        self.replay(&mut board, count)?;
+        Ok(board)
+    }
+
+    // Apply the first count steps to board, which should be as the traced solve started.
+    // Err if a step is not a digit of a cell, or a backtrack does not undo an earlier guess.
+    pub fn replay(&self, board: &mut Sudoku, count: usize) -> Result<(), String> {
+        let mut replay = Replay::default();
+        for step in self.steps.iter().take(count) {
+            replay.apply(board, step)?;
         }
-        applied
+        Ok(())
     }
+}
 
-    fn apply_intermediate_rules(&self, board: &mut Sudoku) -> bool {
-        // Apply intermediate rules here: Locked Candidates Type 1 and Type 2
-        // Returns true if a rule could be applied, false otherwise
+// Applies trace steps to a board, keeping the board as it was before each guess still open
+#[derive(Default)]
+struct Replay {
+    guesses: Vec<Guess>,
+}
 
-        let mut applied = false;
+struct Guess {
+    cell: String,
+    digit: usize,
+    grid: Grid,
+    candidates: HashMap<String, HashSet<usize>>,
+}
 
-        if self.locked_candidates_type_1(board) {
-            // println!("Locked candidates type 1 applied");
-            applied = true;
+impl Replay {
+    fn apply(&mut self, board: &mut Sudoku, step: &TraceStep) -> Result<(), String> {
+        if !board.cells.contains(&step.cell) || !(1..=9).contains(&step.digit) {
+            return Err(format!("{} is not a digit of a cell", step));
         }
-        if self.locked_candidates_type_2(board) {
-            // println!("Locked candidates type 2 applied");
-            applied = true;
+        let (row, col) = utils::cell_to_coords(&step.cell);
+        match step.action {
+            TraceAction::Placement | TraceAction::Guess => {
+                if step.action == TraceAction::Guess {
+                    self.guesses.push(Guess { cell: step.cell.clone(), digit: step.digit, grid: board.board, candidates: board.candidates.clone() });
+                }
+                board.board[row][col] = step.digit as u8;
+                board.candidates.insert(step.cell.clone(), HashSet::from([step.digit]));
+            }
+            TraceAction::Elimination => {
+                if let Some(candidates) = board.candidates.get_mut(&step.cell) {
+                    candidates.remove(&step.digit);
+                }
+            }
+            TraceAction::Backtrack => {
+                // Guesses made after this one were undone with it
+                let position = self.guesses.iter()
+                    .rposition(|guess| guess.cell == step.cell && guess.digit == step.digit)
+                    .ok_or_else(|| format!("{} backtracks from a guess that was not made", step))?;
+                let guess = self.guesses.swap_remove(position);
+                self.guesses.truncate(position);
+                board.board = guess.grid;
+                board.candidates = guess.candidates;
+            }
         }
-        applied
+        Ok(())
     }
+}
 
-    // fn apply_complex_rules(&self, board: &mut Sudoku) -> bool {
-    //     // Apply complex rules here: X-Wing, Swordfish
-    //     // Returns true if a rule could be applied, false otherwise
+// Records a solve as a SolveTrace. The steps a RuleBasedSolver technique makes are credited to it
+// once it reports them, with the explanation of the deduction they come from; the rest are
+// explained from the board as the trace leaves it.
+pub struct TraceRecorder {
+    trace: SolveTrace,
+    board: Sudoku,
+    replay: Replay,
+    // Steps from here on are not credited to a technique yet
+    unattributed: usize,
+}
 
-    //     let mut applied = false;
+impl TraceRecorder {
+    // Record a solve starting from board
+    pub fn new(board: &Sudoku) -> Self {
+        let puzzle = board.board.iter().flatten()
+            .map(|&digit| if digit == 0 { '.' } else { (b'0' + digit) as char })
+            .collect();
+        let candidates = board.candidates.iter()
+            .map(|(cell, digits)| (cell.clone(), digits.iter().cloned().sorted().collect()))
+            .collect();
+        TraceRecorder {
+            trace: SolveTrace { puzzle, candidates, steps: Vec::new(), replay_error: None },
+            board: board.clone(),
+            replay: Replay::default(),
+            unattributed: 0,
+        }
+    }
 
-    //     if self.x_wing(board) {
-    //         applied = true;
-    //     }
-    //     if self.swordfish(board) {
-    //         applied = true;
-    //     }
-    //     applied
-    // }
+    pub fn trace(&self) -> &SolveTrace {
+        &self.trace
+    }
 
-    fn solved(&mut self, board: &Sudoku) -> bool {
-        for cell in self.cells_with_candidates.clone() {
-            if board.candidates[&cell].len() == 1 {
-                self.cells_with_candidates.pop();
-            }
-            else {
-                return false
-            }
+    pub fn into_trace(self) -> SolveTrace {
+        self.trace
+    }
+
+    fn record(&mut self, action: TraceAction, technique: &str, cell: &str, digit: usize, reason: String) {
+        let step = TraceStep { action, technique: technique.to_string(), cell: cell.to_string(), digit, cells: Vec::new(), reason };
+        // The trace is replayed as it is recorded, which keeps the board the reasons are taken from
+        // up to date, and shows at once if the solver reported a step it could not have made
+        if let Err(error) = self.replay.apply(&mut self.board, &step) {
+            self.trace.replay_error.get_or_insert(format!("step {}: {}", self.trace.steps.len() + 1, error));
+        }
+        self.trace.steps.push(step);
+        if matches!(action, TraceAction::Guess | TraceAction::Backtrack) {
+            self.unattributed = self.trace.steps.len();
         }
-        true
     }
 
-    // Basic rules: Naked Single, Hidden Single, Naked Pair, Hidden Pair
+    // Why digit can go in cell, judging from the board before it is placed
+    fn placement_reason(&self, cell: &str, digit: usize) -> String {
+        let board = &self.board;
+        let units = [&board.row_peers[cell], &board.col_peers[cell], &board.box_peers[cell]];
+        for (u, unit) in units.iter().enumerate() {
+            let elsewhere = unit.iter().any(|peer| {
+                let (row, col) = utils::cell_to_coords(peer);
+                board.board[row][col] as usize == digit
+                    || board.candidates.get(peer).is_none_or(|candidates| candidates.contains(&digit))
+            });
+            if !elsewhere {
+                return format!("only place for {} in {}", digit, Sudoku::unit_name(cell, u));
+            }
+        }
+        if board.candidates.get(cell).is_some_and(|candidates| candidates.len() == 1 && candidates.contains(&digit)) {
+            return format!("only candidate left in {}", cell);
+        }
+        "propagation".to_string()
+    }
 
-    fn naked_single(&self, board: &mut Sudoku) -> bool {
-        let mut found = false; // flag for finding a naked single
-        for cell in board.cells.clone().iter() {
-            if board.candidates[cell].len() > 1 {
-                continue;
+    // Why digit cannot go in cell, judging from the board before it is eliminated
+    fn elimination_reason(&self, cell: &str, digit: usize) -> String {
+        let board = &self.board;
+        let placed = |cell: &str| {
+            let (row, col) = utils::cell_to_coords(cell);
+            match board.board[row][col] {
+                0 => board.candidates.get(cell).filter(|candidates| candidates.len() == 1)
+                    .and_then(|candidates| candidates.iter().next().cloned()),
+                placed => Some(placed as usize),
             }
-            for digit in board.candidates[cell].clone() {
-                if !board.assign(cell, digit) {
-                    panic!("Contradiction encountered during naked single");
+        };
+        if let Some(other) = placed(cell).filter(|&other| other != digit) {
+            return format!("{} is {}", cell, other);
+        }
+        match board.peers[cell].iter().sorted().find(|peer| placed(peer) == Some(digit)) {
+            Some(peer) => format!("{} is {}", peer, digit),
+            None => "propagation".to_string(),
+        }
+    }
+}
+
+impl SolverObserver for TraceRecorder {
+    fn on_assign(&mut self, cell: &str, digit: usize, guess: bool) {
+        if guess {
+            self.record(TraceAction::Guess, "Guess", cell, digit, format!("trying {} in {}", digit, cell));
+        } else {
+            let reason = self.placement_reason(cell, digit);
+            // The other candidates of cell went first when it was assigned; since the last
+            // placement, those not explained otherwise went because of this one
+            for step in self.trace.steps.iter_mut().rev().take_while(|step| step.action == TraceAction::Elimination) {
+                if step.cell == cell && step.reason == "propagation" {
+                    step.reason = format!("{} is {}", cell, digit);
                 }
             }
-            found = true; // mark that a naked single has been found
+            self.record(TraceAction::Placement, "Propagation", cell, digit, reason);
         }
-        found
     }
 
-    fn hidden_single(&self, board: &mut Sudoku) -> bool {
-        let mut found = false;
-        // For each cell on the board that has more than one candidate
-        for cell in &self.cells_with_candidates {
-            // For each digit in candidates
-            for digit in board.candidates[cell].clone() {
+    fn on_eliminate(&mut self, cell: &str, digit: usize) {
+        let reason = self.elimination_reason(cell, digit);
+        self.record(TraceAction::Elimination, "Propagation", cell, digit, reason);
+    }
 
-                // Check the digit's occurrence in row peers
-                if self.not_in_peers(board, &board.row_peers[cell], digit) {
-                    if !board.assign(&cell, digit) {
-                        panic!("Contradiction encountered during hidden single");
-                    }
-                    found = true;
-                }
-                // Check the digit's occurrence in column peers
-                else if self.not_in_peers(board, &board.col_peers[cell], digit) {
-                    if !board.assign(&cell, digit) {
-                        panic!("Contradiction encountered during hidden single");
-                    }
-                    found = true;
-                }
-                // Check the digit's occurrence in box peers
-                else if self.not_in_peers(board, &board.box_peers[cell], digit) {
-                    if !board.assign(&cell, digit) {
-                        panic!("Contradiction encountered during hidden single");
-                    }
-                    found = true;
-                }
+    fn on_backtrack(&mut self, cell: &str, digit: usize) {
+        self.record(TraceAction::Backtrack, "Backtrack", cell, digit, format!("{}={} leads to a contradiction", cell, digit));
+    }
+
+    fn on_technique_applied(&mut self, technique: &str, steps: &[Step]) {
+        for trace_step in &mut self.trace.steps[self.unattributed..] {
+            trace_step.technique = technique.to_string();
+            let candidate = (trace_step.cell.clone(), trace_step.digit);
+            let deduction = steps.iter().find(|step| match trace_step.action {
+                TraceAction::Placement => step.placements.contains(&candidate),
+                _ => step.eliminations.contains(&candidate),
+            });
+            if let Some(step) = deduction {
+                trace_step.cells = step.cells.clone();
+                trace_step.reason = step.description.clone();
             }
         }
-        found
+        self.unattributed = self.trace.steps.len();
     }
-    
-    // Helper function to check if a digit isn't in peers
-    fn not_in_peers(&self, board:&Sudoku, peers: &HashSet<String>, digit: usize) -> bool {
-        let peers_with_digit: Vec<&String> = peers.iter()
-            .filter(|&peer| board.candidates[peer].contains(&digit))
-            .collect();
-        peers_with_digit.len() == 0
+}
+
+pub trait Solver {
+    // Solve, telling observer what happens along the way
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome;
+    fn solve(&mut self, board: &mut Sudoku, budget: &Budget) -> SolveOutcome {
+        self.solve_observed(board, budget, &mut NoObserver)
     }
+    // Solve, recording every step taken (see SolveTrace)
+    fn solve_traced(&mut self, board: &mut Sudoku, budget: &Budget) -> (SolveOutcome, SolveTrace) {
+        let mut recorder = TraceRecorder::new(board);
+        let outcome = self.solve_observed(board, budget, &mut recorder);
+        (outcome, recorder.into_trace())
+    }
+    // Counters of the last solve
+    fn stats(&self) -> &SolveStats;
+    fn name(&self) -> String;
+    fn initialize_candidates(&mut self, sudoku: &mut Sudoku);
+    fn is_correct(&self, board: &mut Sudoku) -> bool;
+}
 
-    fn naked_pair(&self, board: &mut Sudoku) -> bool {
-        let mut found = false;
-        for cell in &self.cells_with_candidates {
-            let candidates = board.candidates[cell].clone();
-            // If there are more than two candidates, we can't have a naked pair
-            if candidates.len() != 2 {
-                continue;
+pub struct BruteForceSolver {
+    stats: SolveStats,
+    // Look for a second solution after finding one
+    check_unique: bool,
+}
+// Brute force solver.
+// This solver will try every possible candidate in every empty cell.
+// If it hits a dead end, it will backtrack and try a different candidate.
+
+impl Solver for BruteForceSolver {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        self.stats = SolveStats::default();
+        if !board.board_correct() {
+            return board.no_solution();
+        }
+        observer.on_candidates(board);
+        let puzzle = self.check_unique.then(|| board.clone());
+        match self.search(board, 0, budget, observer) {
+            Ok(true) => {}
+            Ok(false) => return board.no_solution(),
+            Err(limit) => return SolveOutcome::Stopped(limit),
+        }
+        observer.on_finish(&format!("Brute force solver finished after {} nodes.", self.stats.nodes));
+        if puzzle.is_some_and(|puzzle| self.count_solutions(&puzzle, 2) > 1) {
+            return SolveOutcome::MultipleSolutions;
+        }
+        SolveOutcome::Solved(board.board)
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        "Brute Force Solver".to_string()
+    }
+
+    fn initialize_candidates(&mut self, board: &mut Sudoku) {
+        board.initialize_candidates_heavy();
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+impl Default for BruteForceSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BruteForceSolver {
+    pub fn new() -> BruteForceSolver {
+        BruteForceSolver { stats: SolveStats::default(), check_unique: false }
+    }
+
+    // Report MultipleSolutions instead of Solved when the puzzle has a second solution.
+    // This costs a second search.
+    pub fn with_check_unique(mut self, check_unique: bool) -> Self {
+        self.check_unique = check_unique;
+        self
+    }
+
+    // Count the solutions of the puzzle, up to limit, going through the same search tree as
+    // solve but quietly, and carrying on after each solution.
+    pub fn count_solutions(&mut self, board: &Sudoku, limit: usize) -> usize {
+        if limit == 0 || !board.board_correct() {
+            return 0;
+        }
+        let mut board = board.clone();
+        board.initialize_candidates_heavy();
+        self.count(&mut board, limit)
+    }
+
+    fn count(&mut self, board: &mut Sudoku, limit: usize) -> usize {
+        let (row, col) = match Self::cell_to_fill(board) {
+            Some(cell) => cell,
+            None => return 1,
+        };
+        let cell = utils::coords_to_cell(row, col);
+        let mut count = 0;
+        for num in board.candidates[&cell].clone().into_iter().sorted() {
+            if count == limit {
+                break;
             }
-            for unit in vec![&board.row_peers[cell].clone(), &board.col_peers[cell].clone(), &board.box_peers[cell].clone()] {
-                // Find other cells in the unit that have the same two candidates
-                let other_cells: Vec<_> = unit.iter()
-                    .filter(|&cell2| *cell2 != *cell && board.candidates[cell2] == candidates)
-                    .cloned()
-                    .collect();
-                // If there's exactly one cell with the same two candidates, we have a naked pair
-                if other_cells.len() != 1 {
-                    continue;
-                }
-                // Eliminate the two digits from all other cells in the unit
-                for cell_to_update in unit {
-                    if cell_to_update != cell && cell_to_update != &other_cells[0] {
-                        for digit in &candidates {
-                            if !board.eliminate(cell_to_update, *digit) {
-                                panic!("Contradiction encountered during naked pair");
-                            }
-                            else{
-                                found = true;
-                            }
-                        }
-                    }
-                }
+            if board.is_valid(row, col, num) {
+                board.board[row][col] = num as u8;
+                count += self.count(board, limit - count);
+                board.board[row][col] = 0;
             }
         }
-        found
+        count
     }
+
+    // The empty cell with the fewest candidates, None once the grid is full
+    fn cell_to_fill(board: &Sudoku) -> Option<(usize, usize)> {
+        let mut min_candidates = 10;
+        let mut cell_to_fill = None;
     
-    fn hidden_pair(&self, board: &mut Sudoku) -> bool {
-        let mut found = false;
-        for cell in &self.cells_with_candidates {
-            let candidates = &board.candidates[cell].clone();
-            for &digit1 in candidates {
-                for &digit2 in candidates {
-                    if digit1 >= digit2 {
-                        continue;
+        for row in 0..9 {
+            for col in 0..9 {
+                if board.board[row][col] == 0 {
+                    let candidates = &board.candidates[&utils::coords_to_cell(row, col)];
+                    let num_candidates = candidates.len();
+                    if num_candidates < min_candidates {
+                        min_candidates = num_candidates;
+                        cell_to_fill = Some((row, col));
                     }
-                    for unit in vec![&board.row_peers[cell].clone(), &board.col_peers[cell].clone(), &board.box_peers[cell].clone()] {
-                        // Find other cells in the unit that contain either digit1 or digit2
-                        let other_cells: Vec<_> = unit.iter()
-                            .filter(|&cell2| board.candidates[cell2].contains(&digit1) || board.candidates[cell2].contains(&digit2))
-                            .collect();
-                        // If there's exactly one other cell with either of the two digits, we have a hidden pair
-                        if other_cells.len() != 1 {
-                            continue;
-                        }
-                        let other_cell = &other_cells[0];
-                        // If the other cell contains both digits, eliminate all other digits from both cells
-                        if board.candidates[*other_cell].contains(&digit1) && board.candidates[*other_cell].contains(&digit2) {
-                            for digit in candidates.union(&board.candidates[*other_cell]).cloned().collect::<HashSet<_>>() {
-                                if digit != digit1 && digit != digit2 {
-                                    if !board.eliminate(cell, digit) || !board.eliminate(other_cell, digit) {
-                                        panic!("Contradiction encountered during hidden pair");
-                                    } else {
-                                        found = true;
-                                    }
-                                }
-                            }
+                }
+            }
+        }
+        cell_to_fill
+    }
+
+    // Fill the empty cells, backtracking out of dead ends. Returns false if there is no solution,
+    // or the limit of the budget reached, leaving the cells filled so far on the board.
+    // depth is the number of cells filled by the search so far.
+    fn search(&mut self, board: &mut Sudoku, depth: usize, budget: &Budget, observer: &mut dyn SolverObserver) -> Result<bool, Limit> {
+        if let Some(limit) = budget.check(self.stats.nodes, 0) {
+            return Err(limit);
+        }
+        self.stats.nodes += 1;
+        self.stats.descend(depth);
+        match Self::cell_to_fill(board) {
+            None => {
+                // No empty cells left, solution found
+                return Ok(true);
+            },
+            Some((row, col)) => {
+                let cell = utils::coords_to_cell(row, col);
+                let candidates = board.candidates[&cell].clone(); // Clone the candidates for the first empty cell
+
+                for &num in candidates.iter() {
+                    if board.is_valid(row, col, num) {
+                        board.board[row][col] = num as u8; // Now it's only placed on the board after it's been verified to be valid
+                        self.stats.guesses += 1;
+                        observer.on_assign(&cell, num, true);
+                        if self.search(board, depth + 1, budget, observer)? {
+                            return Ok(true);
+                        } else {
+                            board.board[row][col] = 0; // Undo the assignment only if the recursive call to solve failed
+                            self.stats.backtracks += 1;
+                            observer.on_backtrack(&cell, num);
                         }
                     }
-                }
+                }                
             }
         }
-        found
+        Ok(false) // No solution found
     }
+}
+
+// Constraint programming with forward propagation and backtracking.
+
+pub struct DeepDFSSolver {
+    queue: Vec<String>,
+    stats: SolveStats,
+}
+
+impl Default for DeepDFSSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DeepDFSSolver {
+    // Constructor for CSPSolver
+    pub fn new() -> Self {
+        DeepDFSSolver {
+            queue: Vec::new(),
+            stats: SolveStats::default(),
+        }
+    }
+
+    fn solved(&self, board: &Sudoku) -> bool {
+        // Check if the board is solved by verifying that every cell has exactly one candidate
+        for cell in board.candidates.keys() {
+            if board.candidates.get(cell).unwrap().len() != 1 {
+                return false;
+            }
+        }
+        true
+    }
+
+    // Deepen the search until every cell has a single candidate
+    fn deepen(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        observer.on_candidates(board);
+        let mut depth = 1;
+        self.stats.descend(depth);
+        // The candidates deduced without guessing, and the guesses still on the board on top of
+        // them: while there are any, a contradiction does not prove the puzzle has no solution
+        let mut deduced = board.candidates.clone();
+        let mut guesses: Vec<(String, usize)> = Vec::new();
+        while !self.solved(board) {
+            if self.queue.is_empty() {
+                // Nothing left to branch on, deepening would loop forever
+                return SolveOutcome::PartiallySolved(board.candidates.clone());
+            }
+            let mut counter = 1;
+            let mut index = 0;
+            for cell in self.queue.clone().iter() {
+                for digit in board.candidates[cell].clone().iter() {
+                    if let Some(limit) = budget.exhausted(self.stats.nodes, 0) {
+                        return SolveOutcome::Stopped(limit);
+                    }
+                    self.stats.nodes += 1;
+                    while counter < depth {
+                        self.stats.guesses += 1;
+                        let candidates_copy: HashMap<String, HashSet<usize>> = board
+                            .candidates
+                            .iter()
+                            .map(|(key, value)| (key.clone(), value.clone()))
+                            .collect();  // Make a copy of the board
+                        let guess = self.queue[0].clone();
+                        let assigned = board.assign(&guess, *digit);
+                        board.report_changes(observer, Some((&guess, *digit)));
+                        if !assigned {
+                            board.candidates = candidates_copy.clone();  // Revert the board
+                            self.stats.backtracks += 1;
+                            observer.on_backtrack(&guess, *digit);
+                            let eliminated = board.eliminate(&guess, *digit);
+                            board.report_changes(observer, None);
+                            if guesses.is_empty() {
+                                if !eliminated {
+                                    // guess can neither hold digit nor not
+                                    board.candidates = candidates_copy;  // Revert the board
+                                    return SolveOutcome::Unsolvable(format!("every candidate of {} leads to a contradiction", guess));
+                                }
+                                deduced = board.candidates.clone();
+                            } else if !eliminated {
+                                // One of the earlier guesses is wrong, but not which: undo them
+                                // all and give back what was deduced
+                                board.candidates = deduced.clone();
+                                for (cell, digit) in guesses.drain(..).rev() {
+                                    self.stats.backtracks += 1;
+                                    observer.on_backtrack(&cell, digit);
+                                }
+                                return SolveOutcome::PartiallySolved(deduced);
+                            }
+                            break;
+                        }
+                        // digit is good, go one layer deeper.
+                        guesses.push((guess, *digit));
+                        counter += 1;
+                    }
+                }
+                if board.candidates[cell].len() == 1 {
+                    self.queue.remove(index);
+                    break;
+                }
+                else{
+                index += 1;
+                }
+            }
+            depth += 1;
+            self.stats.descend(depth);
+        }
+        observer.on_finish("DeepDFSsolver finished.");
+        board.candidates_outcome()
+    }
+}
+
+
+impl Solver for DeepDFSSolver {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        let start = self.stats.start(board);
+        board.changes = Some(Vec::new());
+        let outcome = self.deepen(board, budget, observer);
+        board.changes = None;
+        self.stats.propagation(board, start);
+        outcome
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        "Iterative Deepening DFS Solver".to_string()
+    }
+
+    fn initialize_candidates(&mut self, board: &mut Sudoku) {
+        board.initialize_candidates_heavy();
+
+        // Priority queue for candidates
+        // cells must have more than 1 candidate
+        // and be sorted by the number of candidates
+        self.queue = board.cells.iter()
+            .filter(|cell| board.candidates[*cell].len() > 1)
+            .cloned()
+            .collect();
+
+        // sort by number of candidates (value, ascending)
+        self.queue.sort_by_key(|cell| board.candidates[cell].len());
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.candidates_correct()
+    }
+}
+
+
+
+// A deduction made by one of the rules, kept so that a solve can be followed afterwards.
+// cells holds the cells forming the pattern (for wings: the pivot first, then the pincers).
+// coloring holds (cell, digit, colour) for the coloring techniques, so the clusters can be drawn.
+// proof holds the implication trees of the forcing chains, one line per branch of the tree.
+#[derive(Clone, Debug, Default)]
+pub struct Step {
+    pub technique: String,
+    pub cells: Vec<String>,
+    pub placements: Vec<(String, usize)>,
+    pub eliminations: Vec<(String, usize)>,
+    pub description: String,
+    pub coloring: Vec<(String, usize, usize)>,
+    pub proof: Vec<String>,
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let results: Vec<String> = self.placements.iter()
+            .map(|(cell, digit)| format!("{}={}", cell, digit))
+            .chain(self.eliminations.iter().map(|(cell, digit)| format!("{}<>{}", cell, digit)))
+            .collect();
+        write!(f, "{}: {} => {}", self.technique, self.description, results.join(", "))
+    }
+}
+
+// A rule removed the last candidate of a cell: the candidates it started from have no solution
+#[derive(Debug)]
+struct Contradiction;
+
+// How much of a Hint to give away, from least to most
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Disclosure {
+    Region,
+    Technique,
+    Explanation,
+}
+
+// The next logical step of a puzzle, found by hint without being applied. region is the row,
+// column or box it happens in (the boxes it spans, if there is no such unit), cells those its
+// pattern is made of.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Hint {
+    pub technique: String,
+    pub region: String,
+    pub cells: Vec<String>,
+    pub placements: Vec<(String, usize)>,
+    pub eliminations: Vec<(String, usize)>,
+    // Why the step holds, with the implication trees for the forcing chains
+    pub explanation: String,
+}
+
+impl Hint {
+    fn new(board: &Sudoku, step: Step) -> Self {
+        let changed: Vec<&String> = step.placements.iter().chain(&step.eliminations).map(|(cell, _)| cell).collect();
+        let all: Vec<&String> = step.cells.iter().chain(changed.iter().cloned()).collect();
+        let region = [all, step.cells.iter().collect(), changed].iter()
+            .filter(|cells| !cells.is_empty())
+            .find_map(|cells| Self::common_unit(board, cells))
+            .unwrap_or_else(|| {
+                let boxes = step.cells.iter().chain(step.placements.iter().chain(&step.eliminations).map(|(cell, _)| cell))
+                    .map(|cell| Sudoku::unit_name(cell, 2)[4..].to_string())
+                    .sorted()
+                    .dedup()
+                    .join(", ");
+                format!("boxes {}", boxes)
+            });
+        let explanation = std::iter::once(step.description).chain(step.proof).join("\n");
+        Hint {
+            technique: step.technique,
+            region,
+            cells: step.cells,
+            placements: step.placements,
+            eliminations: step.eliminations,
+            explanation,
+        }
+    }
+
+    // The box, row or column holding all of cells
+    fn common_unit(board: &Sudoku, cells: &[&String]) -> Option<String> {
+        let first = cells[0];
+        let units = [&board.box_peers[first], &board.row_peers[first], &board.col_peers[first]];
+        units.iter().zip([2, 0, 1])
+            .find(|(peers, _)| cells.iter().all(|cell| *cell == first || peers.contains(*cell)))
+            .map(|(_, unit)| Sudoku::unit_name(first, unit))
+    }
+
+    // The hint, revealed up to the given level
+    pub fn disclose(&self, level: Disclosure) -> String {
+        match level {
+            Disclosure::Region => format!("Look at {}.", self.region),
+            Disclosure::Technique => format!("Look for a {} in {}.", self.technique, self.region),
+            Disclosure::Explanation => {
+                let results: Vec<String> = self.placements.iter()
+                    .map(|(cell, digit)| format!("{}={}", cell, digit))
+                    .chain(self.eliminations.iter().map(|(cell, digit)| format!("{}<>{}", cell, digit)))
+                    .collect();
+                format!("{}: {} => {}", self.technique, self.explanation, results.join(", "))
+            }
+        }
+    }
+}
+
+// The simplest step RuleBasedSolver's techniques find next on board, see RuleBasedSolver::hint
+pub fn hint(board: &Sudoku) -> Option<Hint> {
+    RuleBasedSolver::new().hint(board)
+}
+
+pub struct RuleBasedSolver{
+    cells_with_candidates: Vec<String>,
+    steps: Vec<Step>,
+    // Uniqueness techniques are only sound on puzzles known to have a single solution
+    assume_unique: bool,
+    // Maximum number of links in a chain (AIC, X-Chain, XY-Chain, Nice Loop)
+    max_chain_length: usize,
+    // Maximum number of implications from a forcing chain premise to its conclusion; propagating a
+    // premise stops there
+    max_forcing_depth: usize,
+    stats: SolveStats,
+}
+// Rule-based solver.
+// Note that a naked tuple is accompanied by a hidden pair. So this will implement up to naked/hidden tuples. But not quads.
+
+impl Solver for RuleBasedSolver {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        let start = self.stats.start(board);
+        board.changes = Some(Vec::new());
+        let outcome = self.deduce(board, budget, observer);
+        board.changes = None;
+        self.stats.propagation(board, start);
+        outcome
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        "Rule Based Solver".to_string()
+    }
+
+    fn initialize_candidates(&mut self, board: &mut Sudoku) {
+        board.initialize_candidates_lw();
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.candidates_correct()
+    }
+}
+
+impl RuleBasedSolver {
+    // Apply the rules until they stall, then finish with DeepDFSSolver if the grid is not solved.
+    // The deadline and cancellation flag of the budget are checked before each round of rules.
+    fn deduce(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        self.steps.clear();
+        observer.on_candidates(board);
+        self.update_cells_with_candidates(board);
+
+        // If board is solved, update it
+        if self.solved(board) {           
+            observer.on_finish("Rule-based solver finished.");
+            return board.candidates_outcome();
+        }
+
+        // Loop through rules
+        loop {
+            if let Some(limit) = budget.expired() {
+                return SolveOutcome::Stopped(limit);
+            }
+            self.update_cells_with_candidates(board);
+            let boardcopy = board.candidates.clone();
+            
+            let changes_made = match self.apply_rules(board, &boardcopy, observer) {
+                Ok(changes_made) => changes_made,
+                Err(reason) => return SolveOutcome::Unsolvable(reason),
+            };
+            // if self.apply_complex_rules(board, observer) {
+            //     changes_made = true;  
+            // }
+
+            observer.on_candidates(board);
+
+            if !changes_made {
+                break;
+            }
+
+            // if boardcopy is the same as the board then no changes were made and we can break
+            if boardcopy == board.candidates {
+                break;
+            }
+        }
     
+        // If board is solved, update it
+        if self.solved(board) {           
+            observer.on_finish("Rule-based solver finished.");
+            board.candidates_outcome()
+        }
     
+        // If board is not solved, apply brute force solver
+        else {
+            let mut dfs_solver = DeepDFSSolver::new();
+
+            // Priority queue for candidates
+            dfs_solver.queue = board.cells.iter()
+                .filter(|cell| board.candidates[*cell].len() > 1)
+                .cloned()
+                .collect();
+            dfs_solver.queue.sort_by_key(|cell| board.candidates[cell].len());
+
+            let outcome = dfs_solver.solve_observed(board, budget, observer);
+            // Its assign and eliminate calls are counted by solve already
+            self.stats.add(&SolveStats { assigns: 0, eliminates: 0, ..dfs_solver.stats().clone() });
+            outcome
+        }
+    }
+}
+
+impl Default for RuleBasedSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RuleBasedSolver {
+    pub fn new() -> RuleBasedSolver {
+        RuleBasedSolver{
+            cells_with_candidates: Vec::new(),
+            steps: Vec::new(),
+            assume_unique: false,
+            max_chain_length: 16,
+            max_forcing_depth: 24,
+            stats: SolveStats::default(),
+        }
+    }
+
+    // Enable the uniqueness techniques (Unique Rectangles, BUG+1).
+    // Only use this on puzzles that are known to have exactly one solution.
+    pub fn with_assume_unique(mut self, assume_unique: bool) -> Self {
+        self.assume_unique = assume_unique;
+        self
+    }
+
+    // Set the maximum number of links the chain techniques search for (16 by default).
+    pub fn with_max_chain_length(mut self, max_chain_length: usize) -> Self {
+        self.max_chain_length = max_chain_length;
+        self
+    }
+
+    // Set the maximum depth of the forcing chain implication trees (24 by default).
+    pub fn with_max_forcing_depth(mut self, max_forcing_depth: usize) -> Self {
+        self.max_forcing_depth = max_forcing_depth;
+        self
+    }
+
+    // The deductions recorded during the last solve, in the order they were made.
+    pub fn steps(&self) -> &[Step] {
+        &self.steps
+    }
+
+    // The next step on board, trying the techniques in the order a solve does, without applying
+    // it. Candidates are filled in from the digits first if board has none. The singles and
+    // pairs are looked for one at a time; the other techniques run on a copy of the board,
+    // without propagation, and the first deduction they record is the hint, or if they record
+    // none, everything they eliminated. None if no technique applies.
+    pub fn hint(&mut self, board: &Sudoku) -> Option<Hint> {
+        let mut board = board.clone();
+        board.changes = None;
+        if board.candidates.is_empty() {
+            board.initialize_candidates_lw();
+        }
+        // Leave the steps of the last solve as they were
+        let steps = std::mem::take(&mut self.steps);
+        let hint = self.find_hint(&board);
+        self.steps = steps;
+        hint
+    }
+
+    fn find_hint(&mut self, board: &Sudoku) -> Option<Hint> {
+        self.update_cells_with_candidates(board);
+        let finders = [Self::naked_single_hint, Self::hidden_single_hint, Self::naked_pair_hint, Self::hidden_pair_hint];
+        if let Some(step) = finders.iter().find_map(|find| find(self, board)) {
+            return Some(Hint::new(board, step));
+        }
+
+        type Rule = fn(&mut RuleBasedSolver, &mut Sudoku) -> Result<bool, Contradiction>;
+        let mut rules: Vec<(&str, Rule)> = vec![
+            ("Locked Candidates Type 1", |solver, board| solver.locked_candidates_type_1(board)),
+            ("Locked Candidates Type 2", |solver, board| solver.locked_candidates_type_2(board)),
+            ("Skyscraper", |solver, board| solver.skyscraper(board)),
+            ("2-String Kite", |solver, board| solver.two_string_kite(board)),
+            ("Turbot Fish", |solver, board| solver.turbot_fish(board)),
+            ("Empty Rectangle", |solver, board| solver.empty_rectangle(board)),
+            ("XY-Wing", |solver, board| solver.xy_wing(board)),
+            ("XYZ-Wing", |solver, board| solver.xyz_wing(board)),
+            ("W-Wing", |solver, board| solver.w_wing(board)),
+        ];
+        if self.assume_unique {
+            rules.extend([
+                ("Unique Rectangle", (|solver, board| solver.unique_rectangles(board)) as Rule),
+                ("Hidden Unique Rectangle", |solver, board| solver.hidden_unique_rectangles(board)),
+                ("BUG+1", |solver, board| solver.bug_plus_one(board)),
+            ]);
+        }
+        rules.extend([
+            ("Simple Coloring", (|solver, board| solver.simple_coloring(board)) as Rule),
+            ("Multi-Coloring", |solver, board| solver.multi_coloring(board)),
+            ("3D Medusa", |solver, board| solver.medusa(board)),
+            ("X-Chain", |solver, board| solver.chain(board, ChainKind::XChain)),
+            ("XY-Chain", |solver, board| solver.chain(board, ChainKind::XYChain)),
+            ("AIC", |solver, board| solver.chain(board, ChainKind::Aic)),
+            ("Sue de Coq", |solver, board| solver.sue_de_coq(board)),
+            ("ALS-XZ", |solver, board| solver.als_xz(board)),
+            ("ALS-XY-Wing", |solver, board| solver.als_xy_wing(board)),
+            ("Death Blossom", |solver, board| solver.death_blossom(board)),
+            ("Nishio", |solver, board| solver.nishio(board)),
+            ("Cell Forcing Chain", |solver, board| solver.cell_forcing_chains(board)),
+            ("Unit Forcing Chain", |solver, board| solver.unit_forcing_chains(board)),
+            ("Digit Forcing Chain", |solver, board| solver.digit_forcing_chains(board)),
+        ]);
+
+        for (technique, rule) in rules {
+            let mut copy = board.clone();
+            copy.shallow = true;
+            self.steps.clear();
+            if !matches!(rule(self, &mut copy), Ok(true)) {
+                continue;
+            }
+            let step = match self.steps.first() {
+                Some(step) => step.clone(),
+                None => Self::difference(technique, board, &copy),
+            };
+            return Some(Hint::new(board, step));
+        }
+        None
+    }
+
+    // A step for what a technique that records no deductions eliminated from before to after
+    fn difference(technique: &str, before: &Sudoku, after: &Sudoku) -> Step {
+        let mut step = Step {
+            technique: technique.to_string(),
+            description: format!("a {} pattern rules these candidates out", technique),
+            ..Step::default()
+        };
+        for cell in &before.cells {
+            let (old, new) = (&before.candidates[cell], &after.candidates[cell]);
+            if old == new {
+                continue;
+            }
+            step.cells.push(cell.clone());
+            step.eliminations.extend(old.difference(new).sorted().map(|&digit| (cell.clone(), digit)));
+            if old.len() > 1 && new.len() == 1 {
+                step.placements.push((cell.clone(), *new.iter().next().unwrap()));
+            }
+        }
+        step
+    }
+
+    // One round of rules, true if any of them applied. boardcopy holds the candidates from the start
+    // of the round. Err says why the puzzle has no solution if a rule ran into a contradiction.
+    fn apply_rules(&mut self, board: &mut Sudoku, boardcopy: &HashMap<String, HashSet<usize>>, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        let mut changes_made = false;
+
+        // Try to apply each rule in turn.
+        if self.apply_basic_rules(board, observer)? {
+            changes_made = true; 
+        }
+        if self.apply_intermediate_rules(board, observer)? {
+            changes_made = true;  
+        }
+        // Harder tiers only run once the easier ones have stalled
+        if *boardcopy == board.candidates && self.apply_single_digit_rules(board, observer)? {
+            changes_made = true;
+        }
+        if *boardcopy == board.candidates && self.apply_wing_rules(board, observer)? {
+            changes_made = true;
+        }
+        if self.assume_unique && *boardcopy == board.candidates && self.apply_uniqueness_rules(board, observer)? {
+            changes_made = true;
+        }
+        if *boardcopy == board.candidates && self.apply_coloring_rules(board, observer)? {
+            changes_made = true;
+        }
+        if *boardcopy == board.candidates && self.apply_chain_rules(board, observer)? {
+            changes_made = true;
+        }
+        if *boardcopy == board.candidates && self.apply_als_rules(board, observer)? {
+            changes_made = true;
+        }
+        // Last resort before guessing
+        if *boardcopy == board.candidates && self.apply_forcing_rules(board, observer)? {
+            changes_made = true;
+        }
+        Ok(changes_made)
+    }
+
+    // Apply a rule, crediting technique with the cells it leaves with a single candidate and the
+    // candidates it removes, and report what it changed to the observer.
+    // Err says why the puzzle has no solution if the rule ran into a contradiction.
+    fn tally(&mut self, technique: &str, board: &mut Sudoku, observer: &mut dyn SolverObserver, rule: impl FnOnce(&mut Self, &mut Sudoku) -> Result<bool, Contradiction>) -> Result<bool, String> {
+        let count = |board: &Sudoku| board.candidates.values()
+            .fold((0, 0), |(settled, candidates), digits| (settled + usize::from(digits.len() == 1), candidates + digits.len()));
+        let before = count(board);
+        let steps = self.steps.len();
+        let applied = rule(self, board);
+        board.report_changes(observer, None);
+        let applied = applied.map_err(|Contradiction| format!("{} removed the last candidate of a cell", technique))?;
+        if applied {
+            observer.on_technique_applied(technique, &self.steps[steps..]);
+            let after = count(board);
+            let tally = self.stats.techniques.entry(technique.to_string()).or_default();
+            tally.placements += after.0 - before.0;
+            tally.eliminations += before.1 - after.1;
+        }
+        Ok(applied)
+    }
     
-    
-    
-    
-    
-// Locked Candidates Type 1:
-fn locked_candidates_type_1(&self, board: &mut Sudoku) -> bool {
-    let mut found = false;
-    // For each cell on the board that has more than one candidate
-    for cell in &self.cells_with_candidates {
-        for unit in vec![&board.row_peers[cell].clone(), &board.col_peers[cell].clone(), &board.box_peers[cell].clone()] {
-            for digit in 1..=9 {
-                let candidate_cells: Vec<_> = unit.iter()
-                    .filter(|&cell| board.candidates[cell].contains(&digit))
-                    .collect();
+    fn apply_basic_rules(&mut self, board: &mut Sudoku, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        // Apply basic rules here: Naked Single, Hidden Single, Naked Pair, Hidden Pair
+        // Returns true if a rule could be applied, false otherwise, and Err if one found a contradiction
+        // When any rule succeeds, call the solver again
+
+        let mut applied = false;
+
+        if self.tally("Naked Single", board, observer, |solver, board| solver.naked_single(board))? {
+            // println!("Naked single applied");
+            applied = true;
+        }
+        if self.tally("Hidden Single", board, observer, |solver, board| solver.hidden_single(board))? {
+            // println!("Hidden single applied");
+            applied = true;
+        }
+        if self.tally("Naked Pair", board, observer, |solver, board| solver.naked_pair(board))? {
+            // println!("Naked pair applied");
+            applied = true;
+        }
+        if self.tally("Hidden Pair", board, observer, |solver, board| solver.hidden_pair(board))? {
+            // println!("Hidden pair applied");
+            applied = true;
+        }
+        Ok(applied)
+    }
+
+    fn apply_intermediate_rules(&mut self, board: &mut Sudoku, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        // Apply intermediate rules here: Locked Candidates Type 1 and Type 2
+        // Returns true if a rule could be applied, false otherwise, and Err if one found a contradiction
+
+        let mut applied = false;
+
+        if self.tally("Locked Candidates Type 1", board, observer, |solver, board| solver.locked_candidates_type_1(board))? {
+            // println!("Locked candidates type 1 applied");
+            applied = true;
+        }
+        if self.tally("Locked Candidates Type 2", board, observer, |solver, board| solver.locked_candidates_type_2(board))? {
+            // println!("Locked candidates type 2 applied");
+            applied = true;
+        }
+        Ok(applied)
+    }
+
+    fn apply_single_digit_rules(&mut self, board: &mut Sudoku, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        // Apply single-digit patterns here: Skyscraper, 2-String Kite, Turbot Fish, Empty Rectangle
+        // Returns true if a rule could be applied, false otherwise, and Err if one found a contradiction
+
+        let mut applied = false;
+
+        if self.tally("Skyscraper", board, observer, |solver, board| solver.skyscraper(board))? {
+            applied = true;
+        }
+        if self.tally("2-String Kite", board, observer, |solver, board| solver.two_string_kite(board))? {
+            applied = true;
+        }
+        if self.tally("Turbot Fish", board, observer, |solver, board| solver.turbot_fish(board))? {
+            applied = true;
+        }
+        if self.tally("Empty Rectangle", board, observer, |solver, board| solver.empty_rectangle(board))? {
+            applied = true;
+        }
+        Ok(applied)
+    }
+
+    fn apply_wing_rules(&mut self, board: &mut Sudoku, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        // Apply wing rules here: XY-Wing, XYZ-Wing, W-Wing
+        // Returns true if a rule could be applied, false otherwise, and Err if one found a contradiction
+
+        let mut applied = false;
+
+        if self.tally("XY-Wing", board, observer, |solver, board| solver.xy_wing(board))? {
+            applied = true;
+        }
+        if self.tally("XYZ-Wing", board, observer, |solver, board| solver.xyz_wing(board))? {
+            applied = true;
+        }
+        if self.tally("W-Wing", board, observer, |solver, board| solver.w_wing(board))? {
+            applied = true;
+        }
+        Ok(applied)
+    }
+
+    fn apply_uniqueness_rules(&mut self, board: &mut Sudoku, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        // Apply uniqueness rules here: Unique Rectangles, Hidden Unique Rectangles, BUG+1
+        // Only called when assume_unique is set
+        // Returns true if a rule could be applied, false otherwise, and Err if one found a contradiction
+
+        let mut applied = false;
+
+        if self.tally("Unique Rectangle", board, observer, |solver, board| solver.unique_rectangles(board))? {
+            applied = true;
+        }
+        if self.tally("Hidden Unique Rectangle", board, observer, |solver, board| solver.hidden_unique_rectangles(board))? {
+            applied = true;
+        }
+        if self.tally("BUG+1", board, observer, |solver, board| solver.bug_plus_one(board))? {
+            applied = true;
+        }
+        Ok(applied)
+    }
+
+    fn apply_coloring_rules(&mut self, board: &mut Sudoku, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        // Apply coloring rules here: Simple Coloring, Multi-Coloring, 3D Medusa
+        // Returns true if a rule could be applied, false otherwise, and Err if one found a contradiction
+
+        let mut applied = false;
+
+        if self.tally("Simple Coloring", board, observer, |solver, board| solver.simple_coloring(board))? {
+            applied = true;
+        }
+        if self.tally("Multi-Coloring", board, observer, |solver, board| solver.multi_coloring(board))? {
+            applied = true;
+        }
+        if self.tally("3D Medusa", board, observer, |solver, board| solver.medusa(board))? {
+            applied = true;
+        }
+        Ok(applied)
+    }
+
+    fn apply_chain_rules(&mut self, board: &mut Sudoku, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        // Apply chain rules here: X-Chain, XY-Chain, AIC (with grouped nodes and nice loops)
+        // Returns true if a rule could be applied, false otherwise, and Err if one found a contradiction
+
+        let mut applied = false;
+
+        if self.tally("X-Chain", board, observer, |solver, board| solver.chain(board, ChainKind::XChain))? {
+            applied = true;
+        }
+        if self.tally("XY-Chain", board, observer, |solver, board| solver.chain(board, ChainKind::XYChain))? {
+            applied = true;
+        }
+        if self.tally("AIC", board, observer, |solver, board| solver.chain(board, ChainKind::Aic))? {
+            applied = true;
+        }
+        Ok(applied)
+    }
+
+    fn apply_als_rules(&mut self, board: &mut Sudoku, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        // Apply Almost Locked Set rules here: Sue de Coq, ALS-XZ, ALS-XY-Wing, Death Blossom
+        // Returns true if a rule could be applied, false otherwise, and Err if one found a contradiction
+
+        let mut applied = false;
+
+        if self.tally("Sue de Coq", board, observer, |solver, board| solver.sue_de_coq(board))? {
+            applied = true;
+        }
+        if self.tally("ALS-XZ", board, observer, |solver, board| solver.als_xz(board))? {
+            applied = true;
+        }
+        if self.tally("ALS-XY-Wing", board, observer, |solver, board| solver.als_xy_wing(board))? {
+            applied = true;
+        }
+        if self.tally("Death Blossom", board, observer, |solver, board| solver.death_blossom(board))? {
+            applied = true;
+        }
+        Ok(applied)
+    }
+
+    fn apply_forcing_rules(&mut self, board: &mut Sudoku, observer: &mut dyn SolverObserver) -> Result<bool, String> {
+        // Apply forcing chains here: Nishio, Cell, Unit and Digit Forcing Chains
+        // Returns true if a rule could be applied, false otherwise, and Err if one found a contradiction
+        // Only the first deduction is applied, so the cheaper rules get another go first
+
+        Ok(self.tally("Nishio", board, observer, |solver, board| solver.nishio(board))?
+            || self.tally("Cell Forcing Chain", board, observer, |solver, board| solver.cell_forcing_chains(board))?
+            || self.tally("Unit Forcing Chain", board, observer, |solver, board| solver.unit_forcing_chains(board))?
+            || self.tally("Digit Forcing Chain", board, observer, |solver, board| solver.digit_forcing_chains(board))?)
+    }
+
+    // fn apply_complex_rules(&self, board: &mut Sudoku) -> bool {
+    //     // Apply complex rules here: X-Wing, Swordfish
+    //     // Returns true if a rule could be applied, false otherwise
+
+    //     let mut applied = false;
+
+    //     if self.x_wing(board) {
+    //         applied = true;
+    //     }
+    //     if self.swordfish(board) {
+    //         applied = true;
+    //     }
+    //     applied
+    // }
+
+    fn update_cells_with_candidates(&mut self, board: &Sudoku) {
+        self.cells_with_candidates = board.cells.iter()
+            .filter(|cell| board.candidates[*cell].len() > 1)
+            .cloned()
+            .collect();
+    }
+
+    fn solved(&mut self, board: &Sudoku) -> bool {
+        for cell in self.cells_with_candidates.clone() {
+            if board.candidates[&cell].len() == 1 {
+                self.cells_with_candidates.pop();
+            }
+            else {
+                return false
+            }
+        }
+        true
+    }
+
+    // Basic rules: Naked Single, Hidden Single, Naked Pair, Hidden Pair
+
+    fn naked_single(&self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut found = false; // flag for finding a naked single
+        for cell in board.cells.clone().iter() {
+            if board.candidates[cell].len() > 1 {
+                continue;
+            }
+            // Remove the placed digit from every peer that still has it
+            let digit = *board.candidates[cell].iter().next().unwrap();
+            for peer in board.peers[cell].clone() {
+                if board.candidates[&peer].contains(&digit) {
+                    if !board.eliminate(&peer, digit) {
+                        return Err(Contradiction);
+                    }
+                    found = true; // mark that a naked single has been found
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    fn hidden_single(&self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut found = false;
+        // For each cell on the board that has more than one candidate
+        for cell in &self.cells_with_candidates {
+            // For each digit in candidates
+            for digit in board.candidates[cell].clone() {
+
+                // Check the digit's occurrence in row, column and box peers
+                if self.not_in_peers(board, &board.row_peers[cell], digit)
+                    || self.not_in_peers(board, &board.col_peers[cell], digit)
+                    || self.not_in_peers(board, &board.box_peers[cell], digit) {
+                    if !board.assign(cell, digit) {
+                        return Err(Contradiction);
+                    }
+                    found = true;
+                }
+            }
+        }
+        Ok(found)
+    }
+    
+    // Helper function to check if a digit isn't in peers
+    fn not_in_peers(&self, board:&Sudoku, peers: &HashSet<String>, digit: usize) -> bool {
+        let peers_with_digit: Vec<&String> = peers.iter()
+            .filter(|&peer| board.candidates[peer].contains(&digit))
+            .collect();
+        peers_with_digit.is_empty()
+    }
+
+    fn naked_pair(&self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut found = false;
+        for cell in &self.cells_with_candidates {
+            let candidates = board.candidates[cell].clone();
+            // If there are more than two candidates, we can't have a naked pair
+            if candidates.len() != 2 {
+                continue;
+            }
+            for unit in [&board.row_peers[cell].clone(), &board.col_peers[cell].clone(), &board.box_peers[cell].clone()] {
+                // Find other cells in the unit that have the same two candidates
+                let other_cells: Vec<_> = unit.iter()
+                    .filter(|&cell2| *cell2 != *cell && board.candidates[cell2] == candidates)
+                    .cloned()
+                    .collect();
+                // If there's exactly one cell with the same two candidates, we have a naked pair
+                if other_cells.len() != 1 {
+                    continue;
+                }
+                // Eliminate the two digits from all other cells in the unit
+                for cell_to_update in unit {
+                    if cell_to_update != cell && cell_to_update != &other_cells[0] {
+                        for digit in &candidates {
+                            if !board.candidates[cell_to_update].contains(digit) {
+                                continue;
+                            }
+                            if !board.eliminate(cell_to_update, *digit) {
+                                return Err(Contradiction);
+                            }
+                            else{
+                                found = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+    
+    fn hidden_pair(&self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut found = false;
+        for cell in &self.cells_with_candidates {
+            let candidates = &board.candidates[cell].clone();
+            for &digit1 in candidates {
+                for &digit2 in candidates {
+                    if digit1 >= digit2 {
+                        continue;
+                    }
+                    for unit in [&board.row_peers[cell].clone(), &board.col_peers[cell].clone(), &board.box_peers[cell].clone()] {
+                        // Find other cells in the unit that contain either digit1 or digit2
+                        let other_cells: Vec<_> = unit.iter()
+                            .filter(|&cell2| board.candidates[cell2].contains(&digit1) || board.candidates[cell2].contains(&digit2))
+                            .collect();
+                        // If there's exactly one other cell with either of the two digits, we have a hidden pair
+                        if other_cells.len() != 1 {
+                            continue;
+                        }
+                        let other_cell = &other_cells[0];
+                        // If the other cell contains both digits, eliminate all other digits from both cells
+                        if board.candidates[*other_cell].contains(&digit1) && board.candidates[*other_cell].contains(&digit2) {
+                            for digit in candidates.union(&board.candidates[*other_cell]).cloned().collect::<HashSet<_>>() {
+                                if digit != digit1 && digit != digit2 {
+                                    if !board.eliminate(cell, digit) || !board.eliminate(other_cell, digit) {
+                                        return Err(Contradiction);
+                                    } else {
+                                        found = true;
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+    
+    
+    
+    
+    
+    
+    
+    // The singles and pairs for hint, one at a time: the first found going through the cells in
+    // order, with what it eliminates. Cells with a single candidate but no digit yet are singles
+    // to place, as long as a peer still holds that candidate.
+
+    fn units_of_cell<'a>(board: &'a Sudoku, cell: &str) -> [(usize, &'a HashSet<String>); 3] {
+        [(0, &board.row_peers[cell]), (1, &board.col_peers[cell]), (2, &board.box_peers[cell])]
+    }
+
+    fn naked_single_hint(&self, board: &Sudoku) -> Option<Step> {
+        board.cells.iter().find_map(|cell| {
+            let (row, col) = utils::cell_to_coords(cell);
+            if board.board[row][col] != 0 || board.candidates[cell].len() != 1 {
+                return None;
+            }
+            let digit = *board.candidates[cell].iter().next().unwrap();
+            let eliminations: Vec<(String, usize)> = board.peers[cell].iter().sorted()
+                .filter(|peer| board.candidates[*peer].contains(&digit))
+                .map(|peer| (peer.clone(), digit))
+                .collect();
+            // Placing the digit has to take it from a peer, or the grid already shows all it says
+            if eliminations.is_empty() {
+                return None;
+            }
+            Some(Step {
+                technique: "Naked Single".to_string(),
+                cells: vec![cell.clone()],
+                placements: vec![(cell.clone(), digit)],
+                eliminations,
+                description: format!("{} has no candidate left but {}", cell, digit),
+                ..Step::default()
+            })
+        })
+    }
+
+    fn hidden_single_hint(&self, board: &Sudoku) -> Option<Step> {
+        for cell in &self.cells_with_candidates {
+            for &digit in board.candidates[cell].iter().sorted() {
+                for (unit, peers) in Self::units_of_cell(board, cell) {
+                    if !self.not_in_peers(board, peers, digit) {
+                        continue;
+                    }
+                    return Some(Step {
+                        technique: "Hidden Single".to_string(),
+                        cells: peers.iter().chain([cell]).cloned().sorted().collect(),
+                        placements: vec![(cell.clone(), digit)],
+                        eliminations: board.candidates[cell].iter().sorted()
+                            .filter(|&&other| other != digit)
+                            .map(|&other| (cell.clone(), other))
+                            .collect(),
+                        description: format!("{} is the only cell of {} that can hold {}", cell, Sudoku::unit_name(cell, unit), digit),
+                        ..Step::default()
+                    });
+                }
+            }
+        }
+        None
+    }
+
+    fn naked_pair_hint(&self, board: &Sudoku) -> Option<Step> {
+        for cell in &self.cells_with_candidates {
+            let candidates = &board.candidates[cell];
+            if candidates.len() != 2 {
+                continue;
+            }
+            for (unit, peers) in Self::units_of_cell(board, cell) {
+                let others: Vec<&String> = peers.iter().filter(|&other| board.candidates[other] == *candidates).collect();
+                if others.len() != 1 {
+                    continue;
+                }
+                let eliminations: Vec<(String, usize)> = peers.iter().sorted()
+                    .filter(|&peer| peer != others[0])
+                    .flat_map(|peer| candidates.iter().sorted()
+                        .filter(|&digit| board.candidates[peer].contains(digit))
+                        .map(move |&digit| (peer.clone(), digit)))
+                    .collect();
+                if eliminations.is_empty() {
+                    continue;
+                }
+                let digits: Vec<&usize> = candidates.iter().sorted().collect();
+                return Some(Step {
+                    technique: "Naked Pair".to_string(),
+                    cells: vec![cell.clone(), others[0].clone()],
+                    eliminations,
+                    description: format!("{} and {} can only hold {} and {}, so no other cell of {} can",
+                        cell, others[0], digits[0], digits[1], Sudoku::unit_name(cell, unit)),
+                    ..Step::default()
+                });
+            }
+        }
+        None
+    }
+
+    fn hidden_pair_hint(&self, board: &Sudoku) -> Option<Step> {
+        for cell in &self.cells_with_candidates {
+            let candidates: Vec<usize> = board.candidates[cell].iter().cloned().sorted().collect();
+            for (k, &digit1) in candidates.iter().enumerate() {
+                for &digit2 in &candidates[k + 1..] {
+                    for (unit, peers) in Self::units_of_cell(board, cell) {
+                        let others: Vec<&String> = peers.iter()
+                            .filter(|&other| board.candidates[other].contains(&digit1) || board.candidates[other].contains(&digit2))
+                            .collect();
+                        if others.len() != 1 || !board.candidates[others[0]].contains(&digit1) || !board.candidates[others[0]].contains(&digit2) {
+                            continue;
+                        }
+                        let eliminations: Vec<(String, usize)> = [cell, others[0]].into_iter()
+                            .flat_map(|pair_cell| board.candidates[pair_cell].iter().sorted()
+                                .filter(|&&digit| digit != digit1 && digit != digit2)
+                                .map(move |&digit| (pair_cell.clone(), digit)))
+                            .collect();
+                        if eliminations.is_empty() {
+                            continue;
+                        }
+                        return Some(Step {
+                            technique: "Hidden Pair".to_string(),
+                            cells: vec![cell.clone(), others[0].clone()],
+                            eliminations,
+                            description: format!("{} and {} are the only cells of {} that can hold {} and {}, so they hold nothing else",
+                                cell, others[0], Sudoku::unit_name(cell, unit), digit1, digit2),
+                            ..Step::default()
+                        });
+                    }
+                }
+            }
+        }
+        None
+    }
+
+// Locked Candidates Type 1 (Pointing):
+// The candidates of a digit in a box all lie on one row or column, so whichever of them holds the
+// digit, no other cell of that line can.
+fn locked_candidates_type_1(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+    let mut found = false;
+    for unit in Self::units_of(board, &board.box_peers) {
+        for digit in 1..=9 {
+            let cells: Vec<&String> = unit.iter()
+                .filter(|&cell| board.candidates[cell].contains(&digit))
+                .collect();
+            if cells.len() < 2 || cells.iter().any(|&cell| board.candidates[cell].len() == 1) {
+                continue;
+            }
+            // The row or column they all lie on, if any
+            let Some(kind) = [0, 1].into_iter()
+                .find(|&kind| cells.iter().all(|&cell| Sudoku::unit_name(cell, kind) == Sudoku::unit_name(cells[0], kind))) else {
+                continue;
+            };
+            let line = Sudoku::unit_name(cells[0], kind);
+            let step = Step {
+                technique: "Locked Candidates Type 1".to_string(),
+                cells: unit.clone(),
+                eliminations: board.cells.iter()
+                    .filter(|&cell| !unit.contains(cell) && Sudoku::unit_name(cell, kind) == line)
+                    .map(|cell| (cell.clone(), digit))
+                    .collect(),
+                description: format!("{} can only go in {} of {} ({}), so not in the rest of {}",
+                    digit, line, Sudoku::unit_name(cells[0], 2), cells.iter().join(", "), line),
+                ..Step::default()
+            };
+            if self.apply_eliminations(board, step)? {
+                found = true;
+            }
+        }
+    }
+    Ok(found)
+}
+
+// Locked Candidates Type 2 (Claiming):
+// The candidates of a digit in a row or column all lie in one box, so whichever of them holds the
+// digit, no other cell of that box can.
+fn locked_candidates_type_2(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+    let mut found = false;
+    let lines = Self::units_of(board, &board.row_peers).into_iter().map(|unit| (0, unit))
+        .chain(Self::units_of(board, &board.col_peers).into_iter().map(|unit| (1, unit)));
+    for (kind, unit) in lines.collect::<Vec<_>>() {
+        for digit in 1..=9 {
+            let cells: Vec<&String> = unit.iter()
+                .filter(|&cell| board.candidates[cell].contains(&digit))
+                .collect();
+            if cells.len() < 2 || cells.iter().any(|&cell| board.candidates[cell].len() == 1) {
+                continue;
+            }
+            let box_ = Sudoku::unit_name(cells[0], 2);
+            if !cells.iter().all(|&cell| Sudoku::unit_name(cell, 2) == box_) {
+                continue;
+            }
+            let step = Step {
+                technique: "Locked Candidates Type 2".to_string(),
+                cells: unit.clone(),
+                eliminations: board.cells.iter()
+                    .filter(|&cell| !unit.contains(cell) && Sudoku::unit_name(cell, 2) == box_)
+                    .map(|cell| (cell.clone(), digit))
+                    .collect(),
+                description: format!("{} can only go in {} of {} ({}), so not in the rest of {}",
+                    digit, box_, Sudoku::unit_name(cells[0], kind), cells.iter().join(", "), box_),
+                ..Step::default()
+            };
+            if self.apply_eliminations(board, step)? {
+                found = true;
+            }
+        }
+    }
+    Ok(found)
+}
+
+
+
+
+    
+
+    // Single-digit patterns: Skyscraper, 2-String Kite, Turbot Fish, Empty Rectangle
+    // All four are built from strong links: a unit in which a digit has exactly two candidate cells,
+    // so one of the two must hold the digit.
+
+    // Rebuild the 9 units of one kind (rows, columns or boxes) from a peer map, as sorted lists of cells.
+    fn units_of(board: &Sudoku, peers: &HashMap<String, HashSet<String>>) -> Vec<Vec<String>> {
+        let mut units: Vec<Vec<String>> = Vec::new();
+        for cell in &board.cells {
+            let mut unit: Vec<String> = peers[cell].iter().cloned().collect();
+            unit.push(cell.clone());
+            unit.sort();
+            if !units.contains(&unit) {
+                units.push(unit);
+            }
+        }
+        units
+    }
+
+    // Find the strong links on a digit in the given units, as pairs of cells.
+    fn strong_links(board: &Sudoku, units: &[Vec<String>], digit: usize) -> Vec<(String, String)> {
+        let mut links = Vec::new();
+        for unit in units {
+            let cells: Vec<&String> = unit.iter()
+                .filter(|&cell| board.candidates[cell].contains(&digit))
+                .collect();
+            if cells.len() == 2 && cells.iter().all(|&cell| board.candidates[cell].len() > 1) {
+                links.push((cells[0].clone(), cells[1].clone()));
+            }
+        }
+        links
+    }
+
+    // Cells (other than the given ones) that see all of the given cells, in sorted order
+    fn common_peers(board: &Sudoku, cells: &[&String]) -> Vec<String> {
+        let mut common: Vec<String> = board.peers[cells[0]].iter()
+            .filter(|&peer| cells[1..].iter().all(|&cell| board.peers[cell].contains(peer)))
+            .filter(|&peer| !cells.contains(&peer))
+            .cloned()
+            .collect();
+        common.sort();
+        common
+    }
+
+    // Helper function to remove a digit from every cell that sees all of the given cells.
+    // Returns the cells the digit was removed from.
+    fn eliminate_from_common_peers(&self, board: &mut Sudoku, digit: usize, cells: &[&String]) -> Result<Vec<String>, Contradiction> {
+        let mut eliminated = Vec::new();
+        for cell in Self::common_peers(board, cells) {
+            if board.candidates[&cell].contains(&digit) {
+                if !board.eliminate(&cell, digit) {
+                    return Err(Contradiction);
+                }
+                eliminated.push(cell);
+            }
+        }
+        Ok(eliminated)
+    }
+
+    // Two strong links whose base ends are joined by a weak link (`connected`).
+    // Either base end is false, so at least one of the two roof ends holds the digit, and the digit
+    // can be eliminated from every cell that sees both roofs.
+    fn two_strong_links(&mut self, board: &mut Sudoku, technique: &str, first: &[Vec<String>], second: &[Vec<String>], connected: impl Fn(&Sudoku, &String, &String) -> bool) -> Result<bool, Contradiction> {
+        let mut found = false;
+        for digit in 1..=9 {
+            let first_links = Self::strong_links(board, first, digit);
+            let second_links = Self::strong_links(board, second, digit);
+            for (a1, b1) in &first_links {
+                for (a2, b2) in &second_links {
+                    // The four cells must be distinct
+                    if a1 == a2 || a1 == b2 || b1 == a2 || b1 == b2 {
+                        continue;
+                    }
+                    for (base1, roof1) in [(a1, b1), (b1, a1)] {
+                        for (base2, roof2) in [(a2, b2), (b2, a2)] {
+                            if !connected(board, base1, base2) {
+                                continue;
+                            }
+                            let eliminated = self.eliminate_from_common_peers(board, digit, &[roof1, roof2])?;
+                            if !eliminated.is_empty() {
+                                self.steps.push(Step {
+                                    technique: technique.to_string(),
+                                    cells: vec![base1.clone(), roof1.clone(), base2.clone(), roof2.clone()],
+                                    eliminations: eliminated.into_iter().map(|cell| (cell, digit)).collect(),
+                                    description: format!("{} on strong links {}={} and {}={}, bases {} and {} see each other",
+                                        digit, base1, roof1, base2, roof2, base1, base2),
+                                    ..Default::default()
+                                });
+                                found = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // Skyscraper:
+    // Two strong links in parallel rows (or columns) whose base ends share a column (or row).
+    fn skyscraper(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let rows = Self::units_of(board, &board.row_peers);
+        let cols = Self::units_of(board, &board.col_peers);
+        let mut found = false;
+        if self.two_strong_links(board, "Skyscraper", &rows, &rows, |board, a, b| board.col_peers[a].contains(b))? {
+            found = true;
+        }
+        if self.two_strong_links(board, "Skyscraper", &cols, &cols, |board, a, b| board.row_peers[a].contains(b))? {
+            found = true;
+        }
+        Ok(found)
+    }
+
+    // 2-String Kite:
+    // A strong link in a row and one in a column whose base ends share a box.
+    fn two_string_kite(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let rows = Self::units_of(board, &board.row_peers);
+        let cols = Self::units_of(board, &board.col_peers);
+        self.two_strong_links(board, "2-String Kite", &rows, &cols, |board, a, b| board.box_peers[a].contains(b))
+    }
+
+    // Turbot Fish:
+    // Any two strong links, in any kind of unit, whose base ends see each other.
+    // Skyscraper and 2-String Kite are special cases; this catches the remaining ones (e.g. box links).
+    fn turbot_fish(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut units = Self::units_of(board, &board.row_peers);
+        units.extend(Self::units_of(board, &board.col_peers));
+        units.extend(Self::units_of(board, &board.box_peers));
+        self.two_strong_links(board, "Turbot Fish", &units, &units, |board, a, b| board.peers[a].contains(b))
+    }
+
+    // Empty Rectangle:
+    // The candidates of a digit in a box all lie on one row and one column of the box (the hinge),
+    // with at least one candidate off the hinge cell on each line. A strong link outside the box
+    // with one end on the hinge row then forces the digit out of the cell where its other end's row
+    // crosses the hinge column (and the same with rows and columns swapped).
+    fn empty_rectangle(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let rows = Self::units_of(board, &board.row_peers);
+        let cols = Self::units_of(board, &board.col_peers);
+        let boxes = Self::units_of(board, &board.box_peers);
+        let row_of = |cell: &String| cell.chars().next().unwrap();
+        let col_of = |cell: &String| cell.chars().nth(1).unwrap();
+        let mut found = false;
+
+        for digit in 1..=9 {
+            for unit in &boxes {
+                let cells: Vec<&String> = unit.iter()
+                    .filter(|&cell| board.candidates[cell].contains(&digit))
+                    .collect();
+                if cells.len() < 2 || cells.iter().any(|&cell| board.candidates[cell].len() == 1) {
+                    continue;
+                }
+                let box_rows: HashSet<char> = unit.iter().map(row_of).collect();
+                let box_cols: HashSet<char> = unit.iter().map(col_of).collect();
+
+                let mut targets = Vec::new();
+                for &r in &box_rows {
+                    for &c in &box_cols {
+                        if !cells.iter().all(|&cell| row_of(cell) == r || col_of(cell) == c) {
+                            continue;
+                        }
+                        let off_row = cells.iter().any(|&cell| row_of(cell) == r && col_of(cell) != c);
+                        let off_col = cells.iter().any(|&cell| col_of(cell) == c && row_of(cell) != r);
+                        if !off_row || !off_col {
+                            continue;
+                        }
+                        // Strong link in a column outside the box, one end on the hinge row
+                        for (p, q) in Self::strong_links(board, &cols, digit) {
+                            for (end, other) in [(&p, &q), (&q, &p)] {
+                                if row_of(end) == r && !unit.contains(end) && !box_rows.contains(&row_of(other)) {
+                                    targets.push((format!("{}{}", row_of(other), c), p.clone(), q.clone(), r, c));
+                                }
+                            }
+                        }
+                        // Strong link in a row outside the box, one end on the hinge column
+                        for (p, q) in Self::strong_links(board, &rows, digit) {
+                            for (end, other) in [(&p, &q), (&q, &p)] {
+                                if col_of(end) == c && !unit.contains(end) && !box_cols.contains(&col_of(other)) {
+                                    targets.push((format!("{}{}", r, col_of(other)), p.clone(), q.clone(), r, c));
+                                }
+                            }
+                        }
+                    }
+                }
+
+                for (target, p, q, r, c) in targets {
+                    let step = Step {
+                        technique: "Empty Rectangle".to_string(),
+                        cells: unit.iter().chain([&p, &q]).cloned().collect(),
+                        eliminations: vec![(target.clone(), digit)],
+                        description: format!("{} in {} lies on row {} and column {}, strong link {}={}, so not in {}",
+                            digit, Sudoku::unit_name(&unit[0], 2), r, c, p, q, target),
+                        ..Default::default()
+                    };
+                    if self.apply_eliminations(board, step)? {
+                        found = true;
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // Wings: XY-Wing, XYZ-Wing, W-Wing
+    // Patterns built around bivalue cells (cells with exactly two candidates).
+
+    // Cells with exactly the given number of candidates, in board order
+    fn cells_with_count(board: &Sudoku, count: usize) -> Vec<String> {
+        board.cells.iter()
+            .filter(|&cell| board.candidates[cell].len() == count)
+            .cloned()
+            .collect()
+    }
+
+    // Helper function to format a cell with its candidates, e.g. "D5 {3,7}"
+    fn describe_cell(board: &Sudoku, cell: &str) -> String {
+        let mut digits: Vec<usize> = board.candidates[cell].iter().cloned().collect();
+        digits.sort();
+        let digits: Vec<String> = digits.iter().map(|d| d.to_string()).collect();
+        format!("{} {{{}}}", cell, digits.join(","))
+    }
+
+    // XY-Wing:
+    // A bivalue pivot {x,y} sees two bivalue pincers {x,z} and {y,z}. Whichever value the pivot
+    // takes, one of the pincers is z, so z can be eliminated from every cell that sees both pincers.
+    fn xy_wing(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut found = false;
+        let bivalue = Self::cells_with_count(board, 2);
+        for pivot in &bivalue {
+            if board.candidates[pivot].len() != 2 {
+                continue;
+            }
+            let pivot_digits = board.candidates[pivot].clone();
+            let pincers: Vec<&String> = bivalue.iter()
+                .filter(|&cell| board.peers[pivot].contains(cell) && board.candidates[cell].len() == 2)
+                .filter(|&cell| board.candidates[cell].intersection(&pivot_digits).count() == 1)
+                .collect();
+            for (i, &pincer1) in pincers.iter().enumerate() {
+                for &pincer2 in &pincers[i + 1..] {
+                    let digits1 = &board.candidates[pincer1];
+                    let digits2 = &board.candidates[pincer2];
+                    // The pincers share z, and each shares a different digit with the pivot
+                    let common: Vec<usize> = digits1.intersection(digits2).cloned().collect();
+                    if common.len() != 1 || pivot_digits.contains(&common[0]) {
+                        continue;
+                    }
+                    let z = common[0];
+                    let description = format!("pivot {}, pincers {}, {}",
+                        Self::describe_cell(board, pivot), Self::describe_cell(board, pincer1), Self::describe_cell(board, pincer2));
+                    let eliminated = self.eliminate_from_common_peers(board, z, &[pincer1, pincer2])?;
+                    if !eliminated.is_empty() {
+                        self.steps.push(Step {
+                            technique: "XY-Wing".to_string(),
+                            cells: vec![pivot.clone(), pincer1.clone(), pincer2.clone()],
+                            eliminations: eliminated.into_iter().map(|cell| (cell, z)).collect(),
+                            description,
+                            ..Default::default()
+                        });
+                        found = true;
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // XYZ-Wing:
+    // A trivalue pivot {x,y,z} sees two bivalue pincers {x,z} and {y,z}. One of the three cells
+    // must be z, so z can be eliminated from every cell that sees the pivot and both pincers.
+    fn xyz_wing(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut found = false;
+        let bivalue = Self::cells_with_count(board, 2);
+        for pivot in Self::cells_with_count(board, 3) {
+            if board.candidates[&pivot].len() != 3 {
+                continue;
+            }
+            let pivot_digits = board.candidates[&pivot].clone();
+            let pincers: Vec<&String> = bivalue.iter()
+                .filter(|&cell| board.peers[&pivot].contains(cell) && board.candidates[cell].len() == 2)
+                .filter(|&cell| board.candidates[cell].is_subset(&pivot_digits))
+                .collect();
+            for (i, &pincer1) in pincers.iter().enumerate() {
+                for &pincer2 in &pincers[i + 1..] {
+                    let digits1 = &board.candidates[pincer1];
+                    let digits2 = &board.candidates[pincer2];
+                    // The pincers must be different and together cover the pivot
+                    let common: Vec<usize> = digits1.intersection(digits2).cloned().collect();
+                    if common.len() != 1 {
+                        continue;
+                    }
+                    let z = common[0];
+                    let description = format!("pivot {}, pincers {}, {}",
+                        Self::describe_cell(board, &pivot), Self::describe_cell(board, pincer1), Self::describe_cell(board, pincer2));
+                    let eliminated = self.eliminate_from_common_peers(board, z, &[&pivot, pincer1, pincer2])?;
+                    if !eliminated.is_empty() {
+                        self.steps.push(Step {
+                            technique: "XYZ-Wing".to_string(),
+                            cells: vec![pivot.clone(), pincer1.clone(), pincer2.clone()],
+                            eliminations: eliminated.into_iter().map(|cell| (cell, z)).collect(),
+                            description,
+                            ..Default::default()
+                        });
+                        found = true;
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // W-Wing:
+    // Two bivalue cells with the same candidates {x,y} that do not see each other, connected by a
+    // strong link on x (one end seeing each cell). If neither cell were y, both would be x and the
+    // strong link would have no place for x, so y can be eliminated from every cell that sees both.
+    fn w_wing(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut units = Self::units_of(board, &board.row_peers);
+        units.extend(Self::units_of(board, &board.col_peers));
+        units.extend(Self::units_of(board, &board.box_peers));
+
+        let mut found = false;
+        let bivalue = Self::cells_with_count(board, 2);
+        for (i, wing1) in bivalue.iter().enumerate() {
+            for wing2 in &bivalue[i + 1..] {
+                if board.candidates[wing1].len() != 2
+                    || board.candidates[wing1] != board.candidates[wing2]
+                    || board.peers[wing1].contains(wing2) {
+                    continue;
+                }
+                let mut digits: Vec<usize> = board.candidates[wing1].iter().cloned().collect();
+                digits.sort();
+                for (x, y) in [(digits[0], digits[1]), (digits[1], digits[0])] {
+                    for (a, b) in Self::strong_links(board, &units, x) {
+                        if [&a, &b].contains(&wing1) || [&a, &b].contains(&wing2) {
+                            continue;
+                        }
+                        for (end1, end2) in [(&a, &b), (&b, &a)] {
+                            if !board.peers[wing1].contains(end1) || !board.peers[wing2].contains(end2) {
+                                continue;
+                            }
+                            let description = format!("pivots {}, {}, strong link on {} between {} and {}",
+                                Self::describe_cell(board, wing1), Self::describe_cell(board, wing2), x, end1, end2);
+                            let eliminated = self.eliminate_from_common_peers(board, y, &[wing1, wing2])?;
+                            if !eliminated.is_empty() {
+                                self.steps.push(Step {
+                                    technique: "W-Wing".to_string(),
+                                    cells: vec![wing1.clone(), wing2.clone(), end1.clone(), end2.clone()],
+                                    eliminations: eliminated.into_iter().map(|cell| (cell, y)).collect(),
+                                    description,
+                                    ..Default::default()
+                                });
+                                found = true;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // Helper function to apply the eliminations of a step found by a rule, and record the step.
+    // Candidates that are already gone are dropped from the step. Returns true if anything was eliminated.
+    fn apply_eliminations(&mut self, board: &mut Sudoku, mut step: Step) -> Result<bool, Contradiction> {
+        let mut applied: Vec<(String, usize)> = Vec::new();
+        for (cell, digit) in std::mem::take(&mut step.eliminations) {
+            if !board.candidates[&cell].contains(&digit) || applied.contains(&(cell.clone(), digit)) {
+                continue;
+            }
+            if !board.eliminate(&cell, digit) {
+                return Err(Contradiction);
+            }
+            applied.push((cell, digit));
+        }
+        if applied.is_empty() {
+            return Ok(false);
+        }
+        step.eliminations = applied;
+        self.steps.push(step);
+        Ok(true)
+    }
+
+    // Uniqueness: Unique Rectangles (types 1-6), Hidden Unique Rectangles, BUG+1
+    // A puzzle with a single solution cannot contain a deadly pattern: four cells on two rows,
+    // two columns and two boxes holding only the same two digits, since the digits could be swapped.
+    // These rules remove the candidates that would leave such a pattern behind.
+
+    // Find the possible rectangles: corners in the order (r1,c1), (r1,c2), (r2,c1), (r2,c2),
+    // spanning exactly two boxes, all unsolved, with two digits a < b common to every corner.
+    // Corner i shares a row with corner i^1, a column with corner i^2, and is diagonal to corner 3-i.
+    fn rectangles(board: &Sudoku) -> Vec<([String; 4], usize, usize)> {
+        let mut rectangles = Vec::new();
+        for r1 in 0..9 {
+            for r2 in r1 + 1..9 {
+                for c1 in 0..9 {
+                    for c2 in c1 + 1..9 {
+                        // Two boxes: either the rows share a band or the columns share a stack, not both
+                        if (r1 / 3 == r2 / 3) == (c1 / 3 == c2 / 3) {
+                            continue;
+                        }
+                        let corners = [
+                            utils::coords_to_cell(r1, c1), utils::coords_to_cell(r1, c2),
+                            utils::coords_to_cell(r2, c1), utils::coords_to_cell(r2, c2),
+                        ];
+                        if corners.iter().any(|cell| board.candidates[cell].len() < 2) {
+                            continue;
+                        }
+                        let mut common: Vec<usize> = board.candidates[&corners[0]].iter()
+                            .filter(|&digit| corners[1..].iter().all(|cell| board.candidates[cell].contains(digit)))
+                            .cloned()
+                            .collect();
+                        common.sort();
+                        for (i, &a) in common.iter().enumerate() {
+                            for &b in &common[i + 1..] {
+                                rectangles.push((corners.clone(), a, b));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        rectangles
+    }
+
+    // The units (row, column and/or box) shared by two cells, as lists of cells
+    fn shared_units(board: &Sudoku, cell1: &String, cell2: &String) -> Vec<Vec<String>> {
+        let mut units = Vec::new();
+        for peers in [&board.row_peers, &board.col_peers, &board.box_peers] {
+            if peers[cell1].contains(cell2) {
+                let mut unit: Vec<String> = peers[cell1].iter().cloned().collect();
+                unit.push(cell1.clone());
+                unit.sort();
+                units.push(unit);
+            }
+        }
+        units
+    }
+
+    fn unique_rectangles(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut found = false;
+        for (corners, a, b) in Self::rectangles(board) {
+            let pair: HashSet<usize> = [a, b].into_iter().collect();
+            // Earlier eliminations may have broken the rectangle
+            if corners.iter().any(|cell| board.candidates[cell].len() < 2 || !pair.is_subset(&board.candidates[cell])) {
+                continue;
+            }
+            let floor: Vec<&String> = corners.iter().filter(|&cell| board.candidates[cell] == pair).collect();
+            let roof: Vec<&String> = corners.iter().filter(|&cell| board.candidates[cell] != pair).collect();
+            let extras: HashSet<usize> = roof.iter()
+                .flat_map(|&cell| board.candidates[cell].difference(&pair).cloned())
+                .collect();
+
+            let mut results: Vec<(&str, Vec<(String, usize)>)> = Vec::new();
+            if floor.len() == 3 {
+                // Type 1: the fourth corner cannot be a or b
+                results.push(("Unique Rectangle Type 1", vec![(roof[0].clone(), a), (roof[0].clone(), b)]));
+            }
+            if (floor.len() == 2 || floor.len() == 1) && extras.len() == 1
+                && roof.iter().all(|&cell| board.candidates[cell].len() == 3) {
+                // Type 2 (roof in one unit) / Type 5 (diagonal or three roof cells):
+                // every roof cell is {a,b,c}, so one of them is c
+                let c = *extras.iter().next().unwrap();
+                let technique = if roof.len() == 2 && board.peers[roof[0]].contains(roof[1]) {
+                    "Unique Rectangle Type 2"
+                } else {
+                    "Unique Rectangle Type 5"
+                };
+                let eliminations = Self::common_peers(board, &roof).into_iter().map(|cell| (cell, c)).collect();
+                results.push((technique, eliminations));
+            }
+            if floor.len() == 2 && board.peers[roof[0]].contains(roof[1]) {
+                for unit in Self::shared_units(board, roof[0], roof[1]) {
+                    // Type 3: the roof acts as one cell holding one of the extra digits, and can
+                    // form a naked subset with other cells of a shared unit
+                    let others: Vec<&String> = unit.iter()
+                        .filter(|&cell| !roof.contains(&cell) && board.candidates[cell].len() > 1)
+                        .collect();
+                    for size in 1..=3 {
+                        for subset in others.iter().combinations(size) {
+                            let mut digits = extras.clone();
+                            for &&cell in &subset {
+                                digits.extend(board.candidates[cell].iter().cloned());
+                            }
+                            if digits.len() != size + 1 {
+                                continue;
+                            }
+                            let mut eliminations = Vec::new();
+                            for &cell in &others {
+                                if subset.contains(&&cell) {
+                                    continue;
+                                }
+                                for &digit in &digits {
+                                    eliminations.push((cell.clone(), digit));
+                                }
+                            }
+                            results.push(("Unique Rectangle Type 3", eliminations));
+                        }
+                    }
+                    // Type 4: if one of the pair is confined to the roof in a shared unit,
+                    // the other digit of the pair cannot appear in the roof
+                    for (x, y) in [(a, b), (b, a)] {
+                        let cells_with_x: Vec<&String> = unit.iter()
+                            .filter(|&cell| board.candidates[cell].contains(&x))
+                            .collect();
+                        if cells_with_x.len() == 2 && roof.iter().all(|cell| cells_with_x.contains(cell)) {
+                            results.push(("Unique Rectangle Type 4", vec![(roof[0].clone(), y), (roof[1].clone(), y)]));
+                        }
+                    }
+                }
+            }
+            if floor.len() == 2 && !board.peers[roof[0]].contains(roof[1]) {
+                // Type 6: floor and roof on the diagonals. If a digit of the pair is confined to the
+                // rectangle in both rows (or both columns), it must sit on the floor.
+                for x in [a, b] {
+                    for peers in [&board.row_peers, &board.col_peers] {
+                        let confined = [&corners[0], &corners[3]].iter().all(|&corner| {
+                            peers[corner].iter()
+                                .filter(|&cell| board.candidates[cell].contains(&x))
+                                .all(|cell| corners.contains(cell))
+                        });
+                        if confined {
+                            results.push(("Unique Rectangle Type 6", vec![(roof[0].clone(), x), (roof[1].clone(), x)]));
+                        }
+                    }
+                }
+            }
+
+            let digits = format!("{{{},{}}}", a, b);
+            for (technique, eliminations) in results {
+                let step = Step {
+                    technique: technique.to_string(),
+                    cells: corners.to_vec(),
+                    eliminations,
+                    description: format!("{} on {}", digits, corners.join(", ")),
+                    ..Default::default()
+                };
+                if self.apply_eliminations(board, step)? {
+                    found = true;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // Hidden Unique Rectangle:
+    // Take a corner holding only {a,b} and the corner X diagonal to it. If a appears in the row and
+    // the column of X only within the rectangle, X cannot be b: otherwise the two other corners would
+    // both be a, the bivalue corner b, and the four corners a deadly pattern.
+    fn hidden_unique_rectangles(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut found = false;
+        for (corners, a, b) in Self::rectangles(board) {
+            let pair: HashSet<usize> = [a, b].into_iter().collect();
+            if corners.iter().any(|cell| board.candidates[cell].len() < 2 || !pair.is_subset(&board.candidates[cell])) {
+                continue;
+            }
+            for i in 0..4 {
+                let opposite = &corners[3 - i];
+                if board.candidates[&corners[i]] != pair || board.candidates[opposite] == pair {
+                    continue;
+                }
+                for (x, y) in [(a, b), (b, a)] {
+                    let confined = [&board.row_peers[opposite], &board.col_peers[opposite]].iter().all(|peers| {
+                        peers.iter()
+                            .filter(|&cell| board.candidates[cell].contains(&x))
+                            .all(|cell| corners.contains(cell))
+                    });
+                    if !confined {
+                        continue;
+                    }
+                    let step = Step {
+                        technique: "Hidden Unique Rectangle".to_string(),
+                        cells: corners.to_vec(),
+                        eliminations: vec![(opposite.clone(), y)],
+                        description: format!("{{{},{}}} on {}, bivalue corner {}, strong links on {} from {}",
+                            a, b, corners.join(", "), corners[i], x, opposite),
+                        ..Default::default()
+                    };
+                    if self.apply_eliminations(board, step)? {
+                        found = true;
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // BUG+1 (Bivalue Universal Grave):
+    // If every unsolved cell has two candidates except one cell with three, then without that third
+    // candidate the grid would have two solutions. The cell must take the digit that appears three
+    // times in its row.
+    fn bug_plus_one(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let unsolved: Vec<&String> = board.cells.iter().filter(|&cell| board.candidates[cell].len() > 1).collect();
+        if unsolved.iter().any(|&cell| board.candidates[cell].len() > 3) {
+            return Ok(false);
+        }
+        let trivalue: Vec<&String> = unsolved.iter().filter(|&&cell| board.candidates[cell].len() == 3).cloned().collect();
+        if trivalue.len() != 1 {
+            return Ok(false);
+        }
+        let cell = trivalue[0].clone();
+        let mut digits: Vec<usize> = board.candidates[&cell].iter().cloned().collect();
+        digits.sort();
+        for digit in digits {
+            let count = board.row_peers[&cell].iter()
+                .filter(|&peer| board.candidates[peer].len() > 1 && board.candidates[peer].contains(&digit))
+                .count() + 1;
+            if count != 3 {
+                continue;
+            }
+            let description = format!("all unsolved cells bivalue except {}", Self::describe_cell(board, &cell));
+            if !board.assign(&cell, digit) {
+                return Err(Contradiction);
+            }
+            self.steps.push(Step {
+                technique: "BUG+1".to_string(),
+                cells: vec![cell.clone()],
+                placements: vec![(cell, digit)],
+                description,
+                ..Default::default()
+            });
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    // Coloring: Simple Coloring, Multi-Coloring, 3D Medusa
+    // Strong links say that exactly one of their two ends is true, so a chain of them can be coloured
+    // with two alternating colours, one of which is entirely true and the other entirely false.
+    // Color wrap: a colour that contradicts itself is false. Color trap: a candidate that sees both
+    // colours is false.
+
+    // Split a graph of strong links into connected clusters, each coloured with 0 and 1.
+    fn color_clusters<T: Clone + Ord + std::hash::Hash>(links: &[(T, T)]) -> Vec<HashMap<T, usize>> {
+        let mut neighbours: HashMap<T, Vec<T>> = HashMap::new();
+        for (a, b) in links {
+            neighbours.entry(a.clone()).or_default().push(b.clone());
+            neighbours.entry(b.clone()).or_default().push(a.clone());
+        }
+        let mut nodes: Vec<T> = neighbours.keys().cloned().collect();
+        nodes.sort();
+
+        let mut clusters: Vec<HashMap<T, usize>> = Vec::new();
+        let mut seen: HashSet<T> = HashSet::new();
+        for start in nodes {
+            if seen.contains(&start) {
+                continue;
+            }
+            let mut cluster = HashMap::new();
+            let mut queue = vec![(start.clone(), 0)];
+            seen.insert(start);
+            while let Some((node, color)) = queue.pop() {
+                for next in &neighbours[&node] {
+                    if seen.insert(next.clone()) {
+                        queue.push((next.clone(), 1 - color));
+                    }
+                }
+                cluster.insert(node, color);
+            }
+            clusters.push(cluster);
+        }
+        clusters
+    }
+
+    // Cells of a single-digit cluster having the given colour, in sorted order
+    fn cells_of_color(cluster: &HashMap<String, usize>, color: usize) -> Vec<String> {
+        let mut cells: Vec<String> = cluster.iter()
+            .filter(|(_, &c)| c == color)
+            .map(|(cell, _)| cell.clone())
+            .collect();
+        cells.sort();
+        cells
+    }
+
+    // Unsolved cells holding the digit that are not part of any of the given clusters
+    fn uncolored_cells(board: &Sudoku, digit: usize, clusters: &[&HashMap<String, usize>]) -> Vec<String> {
+        board.cells.iter()
+            .filter(|&cell| board.candidates[cell].len() > 1 && board.candidates[cell].contains(&digit))
+            .filter(|&cell| clusters.iter().all(|cluster| !cluster.contains_key(cell)))
+            .cloned()
+            .collect()
+    }
+
+    // Simple Coloring:
+    // Colour the strong links of one digit.
+    // Color wrap: two cells of the same colour see each other, so that colour is false everywhere.
+    // Color trap: a cell outside the cluster that sees both colours cannot hold the digit.
+    fn simple_coloring(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut units = Self::units_of(board, &board.row_peers);
+        units.extend(Self::units_of(board, &board.col_peers));
+        units.extend(Self::units_of(board, &board.box_peers));
+
+        let mut found = false;
+        for digit in 1..=9 {
+            let links = Self::strong_links(board, &units, digit);
+            for cluster in Self::color_clusters(&links) {
+                let colors = [Self::cells_of_color(&cluster, 0), Self::cells_of_color(&cluster, 1)];
+                let mut coloring: Vec<(String, usize, usize)> = cluster.iter()
+                    .map(|(cell, &color)| (cell.clone(), digit, color))
+                    .collect();
+                coloring.sort();
+
+                let mut eliminations = Vec::new();
+                let mut description = String::new();
+                for (color, cells) in colors.iter().enumerate() {
+                    let wrapped = cells.iter().enumerate()
+                        .any(|(i, a)| cells[i + 1..].iter().any(|b| board.peers[a].contains(b)));
+                    if wrapped {
+                        eliminations = cells.iter().map(|cell| (cell.clone(), digit)).collect();
+                        description = format!("color wrap on {}, colour {} sees itself", digit, color);
+                        break;
+                    }
+                }
+                if eliminations.is_empty() {
+                    for cell in Self::uncolored_cells(board, digit, &[&cluster]) {
+                        let sees = |cells: &Vec<String>| cells.iter().any(|c| board.peers[&cell].contains(c));
+                        if sees(&colors[0]) && sees(&colors[1]) {
+                            eliminations.push((cell, digit));
+                        }
+                    }
+                    description = format!("color trap on {}", digit);
+                }
+
+                let step = Step {
+                    technique: "Simple Coloring".to_string(),
+                    cells: colors.concat(),
+                    eliminations,
+                    description,
+                    coloring,
+                    ..Default::default()
+                };
+                if self.apply_eliminations(board, step)? {
+                    found = true;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // Multi-Coloring:
+    // Two clusters of the same digit, coloured A/A' and B/B'. If a cell coloured A sees one coloured B,
+    // A and B cannot both be true, so A' or B' is true and every cell seeing both A' and B' loses the digit.
+    // If A sees both B and B', A is false.
+    fn multi_coloring(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut units = Self::units_of(board, &board.row_peers);
+        units.extend(Self::units_of(board, &board.col_peers));
+        units.extend(Self::units_of(board, &board.box_peers));
+
+        let mut found = false;
+        for digit in 1..=9 {
+            let links = Self::strong_links(board, &units, digit);
+            let clusters = Self::color_clusters(&links);
+            for (i, cluster1) in clusters.iter().enumerate() {
+                for cluster2 in &clusters[i + 1..] {
+                    let colors1 = [Self::cells_of_color(cluster1, 0), Self::cells_of_color(cluster1, 1)];
+                    let colors2 = [Self::cells_of_color(cluster2, 0), Self::cells_of_color(cluster2, 1)];
+                    let sees = |a: &Vec<String>, b: &Vec<String>| a.iter().any(|x| b.iter().any(|y| board.peers[x].contains(y)));
+                    let mut coloring: Vec<(String, usize, usize)> = cluster1.iter()
+                        .map(|(cell, &color)| (cell.clone(), digit, color))
+                        .chain(cluster2.iter().map(|(cell, &color)| (cell.clone(), digit, color + 2)))
+                        .collect();
+                    coloring.sort();
+
+                    let mut results: Vec<(String, Vec<(String, usize)>)> = Vec::new();
+                    for a in 0..2 {
+                        for b in 0..2 {
+                            if !sees(&colors1[a], &colors2[b]) {
+                                continue;
+                            }
+                            // A' or B' is true
+                            let mut eliminations = Vec::new();
+                            for cell in Self::uncolored_cells(board, digit, &[cluster1, cluster2]) {
+                                let sees_cell = |cells: &Vec<String>| cells.iter().any(|c| board.peers[&cell].contains(c));
+                                if sees_cell(&colors1[1 - a]) && sees_cell(&colors2[1 - b]) {
+                                    eliminations.push((cell, digit));
+                                }
+                            }
+                            results.push((format!("colour {} sees colour {}", a, b + 2), eliminations));
+                        }
+                        // A sees both colours of the other cluster
+                        if sees(&colors1[a], &colors2[0]) && sees(&colors1[a], &colors2[1]) {
+                            let eliminations = colors1[a].iter().map(|cell| (cell.clone(), digit)).collect();
+                            results.push((format!("colour {} sees colours 2 and 3", a), eliminations));
+                        }
+                    }
+                    for (b, colors) in colors2.iter().enumerate() {
+                        if sees(colors, &colors1[0]) && sees(colors, &colors1[1]) {
+                            let eliminations = colors.iter().map(|cell| (cell.clone(), digit)).collect();
+                            results.push((format!("colour {} sees colours 0 and 1", b + 2), eliminations));
+                        }
+                    }
+
+                    for (description, eliminations) in results {
+                        let step = Step {
+                            technique: "Multi-Coloring".to_string(),
+                            cells: [colors1.concat(), colors2.concat()].concat(),
+                            eliminations,
+                            description: format!("{} on {}", description, digit),
+                            coloring: coloring.clone(),
+                            ..Default::default()
+                        };
+                        if self.apply_eliminations(board, step)? {
+                            found = true;
+                        }
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // 3D Medusa:
+    // Colour candidates across digits, linking the two candidates of bivalue cells as well as
+    // strong links within units.
+    // Color wrap: a colour is false if it appears twice in a cell, twice for a digit in a unit,
+    // or if it sees every candidate of some uncoloured cell.
+    // Color trap: an uncoloured candidate is false if its cell holds both colours, if it sees the digit
+    // in both colours, or if it sees the digit in one colour while its cell holds the other colour.
+    fn medusa(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let mut units = Self::units_of(board, &board.row_peers);
+        units.extend(Self::units_of(board, &board.col_peers));
+        units.extend(Self::units_of(board, &board.box_peers));
+
+        let mut links: Vec<((String, usize), (String, usize))> = Vec::new();
+        for cell in Self::cells_with_count(board, 2) {
+            let mut digits: Vec<usize> = board.candidates[&cell].iter().cloned().collect();
+            digits.sort();
+            links.push(((cell.clone(), digits[0]), (cell, digits[1])));
+        }
+        for digit in 1..=9 {
+            for (a, b) in Self::strong_links(board, &units, digit) {
+                links.push(((a, digit), (b, digit)));
+            }
+        }
+
+        let mut found = false;
+        for cluster in Self::color_clusters(&links) {
+            // A node of a colour that sees (digit, cell)
+            let sees_color = |cell: &String, digit: usize, color: usize| {
+                cluster.iter().any(|((c, d), &k)| k == color && *d == digit && board.peers[cell].contains(c))
+            };
+            let color_in_cell = |cell: &String, color: usize| {
+                cluster.iter().any(|((c, _), &k)| k == color && c == cell)
+            };
+
+            // Color wrap
+            let mut false_color = None;
+            for color in 0..2 {
+                let nodes: Vec<&(String, usize)> = cluster.iter().filter(|(_, &k)| k == color).map(|(node, _)| node).collect();
+                let twice_in_cell = nodes.iter().enumerate()
+                    .any(|(i, a)| nodes[i + 1..].iter().any(|b| a.0 == b.0));
+                let twice_in_unit = nodes.iter().enumerate()
+                    .any(|(i, a)| nodes[i + 1..].iter().any(|b| a.1 == b.1 && board.peers[&a.0].contains(&b.0)));
+                let empties_cell = board.cells.iter()
+                    .filter(|&cell| board.candidates[cell].len() > 1 && !color_in_cell(cell, 0) && !color_in_cell(cell, 1))
+                    .any(|cell| board.candidates[cell].iter().all(|&digit| sees_color(cell, digit, color)));
+                if twice_in_cell || twice_in_unit || empties_cell {
+                    false_color = Some(color);
+                    break;
+                }
+            }
+
+            let mut eliminations: Vec<(String, usize)> = Vec::new();
+            let description;
+            match false_color {
+                Some(color) => {
+                    eliminations = cluster.iter()
+                        .filter(|(_, &k)| k == color)
+                        .map(|(node, _)| node.clone())
+                        .collect();
+                    eliminations.sort();
+                    description = format!("color wrap, colour {} is false", color);
+                }
+                None => {
+                    for cell in &board.cells {
+                        if board.candidates[cell].len() < 2 {
+                            continue;
+                        }
+                        let mut digits: Vec<usize> = board.candidates[cell].iter().cloned().collect();
+                        digits.sort();
+                        for digit in digits {
+                            if cluster.contains_key(&(cell.clone(), digit)) {
+                                continue;
+                            }
+                            let both_in_cell = color_in_cell(cell, 0) && color_in_cell(cell, 1);
+                            let sees_both = sees_color(cell, digit, 0) && sees_color(cell, digit, 1);
+                            let cell_and_unit = (0..2).any(|color| color_in_cell(cell, color) && sees_color(cell, digit, 1 - color));
+                            if both_in_cell || sees_both || cell_and_unit {
+                                eliminations.push((cell.clone(), digit));
+                            }
+                        }
+                    }
+                    description = "color trap".to_string();
+                }
+            }
+
+            let mut coloring: Vec<(String, usize, usize)> = cluster.iter()
+                .map(|((cell, digit), &color)| (cell.clone(), *digit, color))
+                .collect();
+            coloring.sort();
+            let mut cells: Vec<String> = coloring.iter().map(|(cell, _, _)| cell.clone()).collect();
+            cells.dedup();
+            let step = Step {
+                technique: "3D Medusa".to_string(),
+                cells,
+                eliminations,
+                description,
+                coloring,
+                ..Default::default()
+            };
+            if self.apply_eliminations(board, step)? {
+                found = true;
+            }
+        }
+        Ok(found)
+    }
+
+    // Chains: X-Chain, XY-Chain, Alternating Inference Chains and Nice Loops
+    // See ChainGraph below for the links. An AIC starts and ends with a strong link, so one of its
+    // two ends is true and any candidate weakly linked to both ends can be eliminated.
+
+    // Find the shortest chain of the given kind that makes progress, and apply it.
+    fn chain(&mut self, board: &mut Sudoku, kind: ChainKind) -> Result<bool, Contradiction> {
+        let graph = ChainGraph::new(board, kind);
+        for start in 0..graph.nodes.len() {
+            let Some((path, result)) = graph.search(start, self.max_chain_length) else {
+                continue;
+            };
+            let chain = graph.describe(&path);
+            let mut cells: Vec<String> = path.iter()
+                .flat_map(|&(node, _)| graph.nodes[node].1.iter().map(|&i| graph.names[i].clone()))
+                .collect();
+            cells.sort();
+            cells.dedup();
+            match result {
+                ChainResult::Place(cell, digit, _) => {
+                    let description = format!("{} is true: {}", graph.describe_node(start), chain);
+                    if !board.assign(&cell, digit) {
+                        return Err(Contradiction);
+                    }
+                    self.steps.push(Step {
+                        technique: "Discontinuous Nice Loop".to_string(),
+                        cells,
+                        placements: vec![(cell, digit)],
+                        description,
+                        ..Default::default()
+                    });
+                    return Ok(true);
+                }
+                ChainResult::Eliminate(is_loop, eliminations) => {
+                    let technique = if is_loop {
+                        "Continuous Nice Loop"
+                    } else {
+                        match kind {
+                            ChainKind::XChain => "X-Chain",
+                            ChainKind::XYChain => "XY-Chain",
+                            ChainKind::Aic if path.iter().any(|&(node, _)| graph.nodes[node].1.len() > 1) => "Grouped AIC",
+                            ChainKind::Aic => "AIC",
+                        }
+                    };
+                    let step = Step {
+                        technique: technique.to_string(),
+                        cells,
+                        eliminations,
+                        description: chain,
+                        ..Default::default()
+                    };
+                    if self.apply_eliminations(board, step)? {
+                        return Ok(true);
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // Almost Locked Sets: Sue de Coq, ALS-XZ, ALS-XY-Wing, Death Blossom
+    // An ALS is n unsolved cells of one unit holding n + 1 candidates: remove any one of its digits
+    // and the rest are locked in the set. A restricted common candidate (RCC) x of two ALS is a digit
+    // whose cells in one set all see its cells in the other, so at most one of the sets can hold x.
+
+    // Find every ALS, in every unit (cells shared by two units are only listed once)
+    fn almost_locked_sets(table: &CellTable) -> Vec<Als> {
+        let mut sets: Vec<Als> = Vec::new();
+        let mut seen: HashSet<Vec<usize>> = HashSet::new();
+        for unit in &table.units {
+            let open: Vec<usize> = unit.iter().cloned().filter(|&i| table.masks[i] != 0).collect();
+            for size in 1..open.len() {
+                for cells in open.iter().cloned().combinations(size) {
+                    let mask = cells.iter().fold(0, |mask, &i| mask | table.masks[i]);
+                    if mask.count_ones() as usize == size + 1 && seen.insert(cells.clone()) {
+                        sets.push(Als { cells, mask });
+                    }
+                }
+            }
+        }
+        sets
+    }
+
+    // The restricted common candidates of two ALS that do not overlap
+    fn restricted_commons(table: &CellTable, a: &Als, b: &Als) -> Vec<usize> {
+        if a.cells.iter().any(|i| b.cells.contains(i)) {
+            return Vec::new();
+        }
+        CellTable::digits(a.mask & b.mask).into_iter()
+            .filter(|&x| {
+                let b_cells = b.cells_with(table, x);
+                a.cells_with(table, x).iter().all(|&i| b_cells.iter().all(|&j| table.sees[i][j]))
+            })
+            .collect()
+    }
+
+    // Candidates of a digit that see all of its cells in the given ALS (outside those sets)
+    fn als_eliminations(table: &CellTable, digit: usize, sets: &[&Als]) -> Vec<(String, usize)> {
+        let cells: Vec<usize> = sets.iter().flat_map(|set| set.cells_with(table, digit)).collect();
+        let exclude: Vec<usize> = sets.iter().flat_map(|set| set.cells.iter().cloned()).collect();
+        table.seeing_all(digit, &cells, &exclude).into_iter()
+            .map(|i| (table.names[i].clone(), digit))
+            .collect()
+    }
+
+    // ALS-XZ:
+    // Two ALS A and B with an RCC x. Whichever set misses x is locked, so any other common digit z
+    // is in A or B, and z can be eliminated from cells that see all of its cells in both sets.
+    // Doubly linked (two RCCs): both sets are locked, each RCC is in exactly one of them, and every
+    // other digit is locked in its own set.
+    fn als_xz(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let table = CellTable::new(board);
+        let sets = Self::almost_locked_sets(&table);
+        let mut found = false;
+        for (i, a) in sets.iter().enumerate() {
+            for b in &sets[i + 1..] {
+                let rccs = Self::restricted_commons(&table, a, b);
+                if rccs.is_empty() {
+                    continue;
+                }
+                let doubly_linked = rccs.len() > 1;
+                let mut eliminations = Vec::new();
+                for z in CellTable::digits(a.mask & b.mask) {
+                    if !doubly_linked && z == rccs[0] {
+                        continue;
+                    }
+                    eliminations.extend(Self::als_eliminations(&table, z, &[a, b]));
+                }
+                if doubly_linked {
+                    for set in [a, b] {
+                        for digit in CellTable::digits(set.mask) {
+                            if !rccs.contains(&digit) {
+                                eliminations.extend(Self::als_eliminations(&table, digit, &[set]));
+                            }
+                        }
+                    }
+                }
+                if eliminations.is_empty() {
+                    continue;
+                }
+                let rccs: Vec<String> = rccs.iter().map(|x| x.to_string()).collect();
+                let step = Step {
+                    technique: if doubly_linked { "ALS-XZ (doubly linked)" } else { "ALS-XZ" }.to_string(),
+                    cells: [a.describe_cells(&table), b.describe_cells(&table)].concat(),
+                    eliminations,
+                    description: format!("A={}, B={}, RCC {}", a.describe(&table), b.describe(&table), rccs.join(",")),
+                    ..Default::default()
+                };
+                if self.apply_eliminations(board, step)? {
+                    found = true;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // ALS-XY-Wing:
+    // Three ALS A, B and C, where A and C share an RCC x and B and C share a different RCC y.
+    // If A misses x it is locked; otherwise C misses x, so C is locked and holds y, and B is locked.
+    // A digit z common to A and B (other than x and y) is therefore in A or B.
+    fn als_xy_wing(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let table = CellTable::new(board);
+        let sets = Self::almost_locked_sets(&table);
+        // RCCs of each ALS with every other one
+        let mut links: Vec<Vec<(usize, usize)>> = vec![Vec::new(); sets.len()];
+        for (i, a) in sets.iter().enumerate() {
+            for (j, b) in sets.iter().enumerate().skip(i + 1) {
+                for x in Self::restricted_commons(&table, a, b) {
+                    links[i].push((j, x));
+                    links[j].push((i, x));
+                }
+            }
+        }
+
+        let mut found = false;
+        for (c, pivot) in sets.iter().enumerate() {
+            for (k, &(a, x)) in links[c].iter().enumerate() {
+                for &(b, y) in &links[c][k + 1..] {
+                    if a == b || x == y || sets[a].cells.iter().any(|i| sets[b].cells.contains(i)) {
+                        continue;
+                    }
+                    let mut eliminations = Vec::new();
+                    for z in CellTable::digits(sets[a].mask & sets[b].mask) {
+                        if z != x && z != y {
+                            eliminations.extend(Self::als_eliminations(&table, z, &[&sets[a], &sets[b]]));
+                        }
+                    }
+                    if eliminations.is_empty() {
+                        continue;
+                    }
+                    let step = Step {
+                        technique: "ALS-XY-Wing".to_string(),
+                        cells: [pivot.describe_cells(&table), sets[a].describe_cells(&table), sets[b].describe_cells(&table)].concat(),
+                        eliminations,
+                        description: format!("C={}, A={} (RCC {}), B={} (RCC {})",
+                            pivot.describe(&table), sets[a].describe(&table), x, sets[b].describe(&table), y),
+                        ..Default::default()
+                    };
+                    if self.apply_eliminations(board, step)? {
+                        found = true;
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // Death Blossom:
+    // A stem cell whose every candidate d has a petal: an ALS holding d in cells that all see the stem.
+    // Whichever digit the stem takes, its petal loses that digit and becomes locked, so a digit z
+    // (not in the stem) common to all petals is in one of them.
+    fn death_blossom(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let table = CellTable::new(board);
+        let sets = Self::almost_locked_sets(&table);
+        let mut found = false;
+        for stem in 0..81 {
+            let stem_mask = table.masks[stem];
+            if stem_mask == 0 || stem_mask.count_ones() > 3 {
+                continue;
+            }
+            let digits = CellTable::digits(stem_mask);
+            let petals: Vec<Vec<usize>> = digits.iter()
+                .map(|&d| (0..sets.len())
+                    .filter(|&p| !sets[p].cells.contains(&stem) && sets[p].mask & !stem_mask != 0)
+                    .filter(|&p| {
+                        let cells = sets[p].cells_with(&table, d);
+                        !cells.is_empty() && cells.iter().all(|&i| table.sees[i][stem])
+                    })
+                    .collect())
+                .collect();
+            if petals.iter().any(|p| p.is_empty()) {
+                continue;
+            }
+
+            // Pick one petal per stem digit, keeping only choices that still share a digit z
+            let mut choices: Vec<(Vec<usize>, u16)> = vec![(Vec::new(), 0x3fe & !stem_mask)];
+            for options in &petals {
+                let mut next = Vec::new();
+                for (chosen, common) in &choices {
+                    for &p in options {
+                        let common = common & sets[p].mask;
+                        if common != 0 && next.len() < 10000 {
+                            next.push(([chosen.clone(), vec![p]].concat(), common));
+                        }
+                    }
+                }
+                choices = next;
+            }
+
+            for (chosen, common) in choices {
+                let blossom: Vec<&Als> = chosen.iter().map(|&p| &sets[p]).collect();
+                let mut eliminations = Vec::new();
+                for z in CellTable::digits(common) {
+                    eliminations.extend(Self::als_eliminations(&table, z, &blossom)
+                        .into_iter()
+                        .filter(|(cell, _)| *cell != table.names[stem]));
+                }
+                if eliminations.is_empty() {
+                    continue;
+                }
+                let petal_names: Vec<String> = digits.iter().zip(&blossom)
+                    .map(|(d, petal)| format!("{}: {}", d, petal.describe(&table)))
+                    .collect();
+                let mut cells = vec![table.names[stem].clone()];
+                for petal in &blossom {
+                    cells.extend(petal.describe_cells(&table));
+                }
+                let step = Step {
+                    technique: "Death Blossom".to_string(),
+                    cells,
+                    eliminations,
+                    description: format!("stem {}, petals {}", Self::describe_cell(board, &table.names[stem]), petal_names.join("; ")),
+                    ..Default::default()
+                };
+                if self.apply_eliminations(board, step)? {
+                    found = true;
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // Sue de Coq:
+    // Two or three cells I where a box meets a line, holding at least |I| + 2 candidates, together
+    // with cells L from the rest of the line and B from the rest of the box, where L and B share
+    // no candidate and the cells of I, L and B hold as many digits as there are cells. Each digit
+    // then appears exactly once in them: the digits of L are eliminated from the rest of the line,
+    // those of B from the rest of the box, and the remaining digits of I from both (cells of the
+    // intersection outside I belong to both).
+    fn sue_de_coq(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let table = CellTable::new(board);
+        let open = |cells: Vec<usize>| -> Vec<usize> { cells.into_iter().filter(|&i| table.masks[i] != 0).collect() };
+        let union = |cells: &[usize]| cells.iter().fold(0u16, |mask, &i| mask | table.masks[i]);
+        let mut found = false;
+
+        for box_unit in &table.units[18..27] {
+            for line in &table.units[0..18] {
+                let intersection = open(box_unit.iter().cloned().filter(|i| line.contains(i)).collect());
+                if intersection.len() < 2 {
+                    continue;
+                }
+                let line_rest = open(line.iter().cloned().filter(|i| !box_unit.contains(i)).collect());
+                let box_rest = open(box_unit.iter().cloned().filter(|i| !line.contains(i)).collect());
+                for size in 2..=intersection.len() {
+                    for core in intersection.iter().cloned().combinations(size) {
+                        let core_mask = union(&core);
+                        if (core_mask.count_ones() as usize) < size + 2 {
+                            continue;
+                        }
+                        // The rest of the intersection is in both the line and the box
+                        let beside: Vec<usize> = intersection.iter().cloned().filter(|i| !core.contains(i)).collect();
+                        for line_size in 1..=line_rest.len() {
+                            for line_cells in line_rest.iter().cloned().combinations(line_size) {
+                                let line_mask = union(&line_cells);
+                                for box_size in 1..=box_rest.len() {
+                                    for box_cells in box_rest.iter().cloned().combinations(box_size) {
+                                        let box_mask = union(&box_cells);
+                                        let all = core_mask | line_mask | box_mask;
+                                        if line_mask & box_mask != 0 || all.count_ones() as usize != size + line_size + box_size {
+                                            continue;
+                                        }
+                                        let mut eliminations = Vec::new();
+                                        for digit in CellTable::digits(all) {
+                                            let in_line = line_mask & 1 << digit != 0;
+                                            let in_box = box_mask & 1 << digit != 0;
+                                            if !in_box {
+                                                for &i in line_rest.iter().filter(|i| !line_cells.contains(i)).chain(&beside) {
+                                                    eliminations.push((table.names[i].clone(), digit));
+                                                }
+                                            }
+                                            if !in_line {
+                                                for &i in box_rest.iter().filter(|i| !box_cells.contains(i)).chain(&beside) {
+                                                    eliminations.push((table.names[i].clone(), digit));
+                                                }
+                                            }
+                                        }
+                                        let eliminations: Vec<(String, usize)> = eliminations.into_iter()
+                                            .filter(|(cell, digit)| board.candidates[cell].contains(digit))
+                                            .collect();
+                                        if eliminations.is_empty() {
+                                            continue;
+                                        }
+                                        let step = Step {
+                                            technique: "Sue de Coq".to_string(),
+                                            cells: [&core, &line_cells, &box_cells].iter()
+                                                .flat_map(|cells| cells.iter().map(|&i| table.names[i].clone()))
+                                                .collect(),
+                                            eliminations,
+                                            description: format!("intersection {}, line {}, box {}",
+                                                table.describe(&core), table.describe(&line_cells), table.describe(&box_cells)),
+                                            ..Default::default()
+                                        };
+                                        if self.apply_eliminations(board, step)? {
+                                            found = true;
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        Ok(found)
+    }
+
+    // Forcing chains: Nishio, Cell, Unit and Digit Forcing Chains
+    // A premise (a candidate placed or eliminated) is propagated with assign/eliminate on the
+    // candidates, recording the implication that forced each deduction. A premise leading to a
+    // contradiction is false; a deduction reached from every possible premise of a cell, of a digit
+    // in a unit, or of a candidate being true or false, holds. The implication trees are the proof.
+
+    // Propagate a premise no deeper than max_forcing_depth, then put the candidates back as they
+    // were
+    fn propagate(&self, board: &mut Sudoku, cell: &str, digit: usize, placed: bool) -> Branch {
+        let saved = board.candidates.clone();
+        let changes = board.changes.take();
+        // Premises are propagated in full even when hint has turned propagation off
+        let shallow = std::mem::replace(&mut board.shallow, false);
+        let limit = std::mem::replace(&mut board.implication_limit, self.max_forcing_depth);
+        board.implications = Some(Vec::new());
+        let holds = if placed { board.assign(cell, digit) } else { board.eliminate(cell, digit) };
+        let implications = board.implications.take().unwrap();
+        board.implication_limit = limit;
+        board.shallow = shallow;
+        board.changes = changes;
+        let candidates = std::mem::replace(&mut board.candidates, saved);
+        Branch {
+            premise: (cell.to_string(), digit, placed),
+            candidates: if holds { Some(candidates) } else { None },
+            implications,
+        }
+    }
+
+    // Draw a conclusion from branches covering every possibility, and apply it.
+    // Branches that end in a contradiction are false; if a single one remains its premise holds,
+    // otherwise whatever all the remaining branches deduce holds.
+    fn forcing_chain(&mut self, board: &mut Sudoku, technique: &str, cells: Vec<String>, description: String, branches: &[Branch]) -> Result<bool, Contradiction> {
+        let mut leaves: Vec<Vec<usize>> = vec![Vec::new(); branches.len()];
+        let mut open: Vec<usize> = Vec::new();
+        for (i, branch) in branches.iter().enumerate() {
+            match branch.candidates {
+                Some(_) => open.push(i),
+                None => leaves[i].push(branch.implications.len() - 1),
+            }
+        }
+
+        let mut placements: Vec<(String, usize)> = Vec::new();
+        let mut eliminations: Vec<(String, usize)> = Vec::new();
+        match open[..] {
+            // Every premise fails: the board has no solution
+            [] => return Err(Contradiction),
+            [i] => {
+                let (cell, digit, placed) = branches[i].premise.clone();
+                if placed {
+                    placements.push((cell, digit));
+                } else {
+                    eliminations.push((cell, digit));
+                }
+                leaves[i].push(0);
+            }
+            _ => {
+                for cell in board.cells.iter().filter(|cell| board.candidates[*cell].len() > 1) {
+                    let states: Vec<&HashSet<usize>> = open.iter()
+                        .map(|&i| &branches[i].candidates.as_ref().unwrap()[cell])
+                        .collect();
+                    let mut found: Vec<(usize, bool)> = Vec::new();
+                    if states.iter().all(|state| state.len() == 1 && *state == states[0]) {
+                        found.push((*states[0].iter().next().unwrap(), true));
+                    } else {
+                        for &digit in board.candidates[cell].iter().sorted() {
+                            if states.iter().all(|state| !state.contains(&digit)) {
+                                found.push((digit, false));
+                            }
+                        }
+                    }
+                    for (digit, placed) in found {
+                        let links: Vec<Option<usize>> = open.iter().map(|&i| branches[i].link(cell, digit, placed)).collect();
+                        if links.iter().any(|link| link.is_none()) {
+                            continue;
+                        }
+                        for (&i, link) in open.iter().zip(&links) {
+                            leaves[i].push(link.unwrap());
+                        }
+                        if placed {
+                            placements.push((cell.clone(), digit));
+                        } else {
+                            eliminations.push((cell.clone(), digit));
+                        }
+                    }
+                }
+            }
+        }
+        if placements.is_empty() && eliminations.is_empty() {
+            return Ok(false);
+        }
+
+        let proof: Vec<String> = branches.iter().zip(&leaves)
+            .flat_map(|(branch, leaves)| branch.tree(leaves))
+            .collect();
+        let step = Step {
+            technique: technique.to_string(),
+            cells,
+            placements,
+            eliminations,
+            description,
+            proof,
+            ..Default::default()
+        };
+        self.apply_deductions(board, step)
+    }
+
+    // Apply the placements and eliminations of a step, then record it
+    fn apply_deductions(&mut self, board: &mut Sudoku, mut step: Step) -> Result<bool, Contradiction> {
+        if step.placements.is_empty() {
+            return self.apply_eliminations(board, step);
+        }
+        for (cell, digit) in &step.placements {
+            if !board.assign(cell, *digit) {
+                return Err(Contradiction);
+            }
+        }
+        step.eliminations.retain(|(cell, digit)| board.candidates[cell].contains(digit));
+        for (cell, digit) in &step.eliminations {
+            if !board.eliminate(cell, *digit) {
+                return Err(Contradiction);
+            }
+        }
+        self.steps.push(step);
+        Ok(true)
+    }
+
+    // Nishio: assuming a candidate leads to a contradiction, so it is eliminated
+    fn nishio(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        for cell in board.cells.clone() {
+            if board.candidates[&cell].len() < 2 {
+                continue;
+            }
+            for digit in board.candidates[&cell].clone().into_iter().sorted() {
+                let branch = self.propagate(board, &cell, digit, true);
+                if branch.candidates.is_some() {
+                    continue;
+                }
+                let contradiction = branch.implications.len() - 1;
+                let step = Step {
+                    technique: "Nishio".to_string(),
+                    cells: vec![cell.clone()],
+                    eliminations: vec![(cell.clone(), digit)],
+                    description: format!("assuming {}={} leads to a contradiction", cell, digit),
+                    proof: branch.tree(&[contradiction]),
+                    ..Default::default()
+                };
+                return self.apply_eliminations(board, step);
+            }
+        }
+        Ok(false)
+    }
+
+    // Cell Forcing Chains: whichever candidate a cell takes, the same deduction follows
+    fn cell_forcing_chains(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        for cell in board.cells.clone() {
+            if board.candidates[&cell].len() < 2 {
+                continue;
+            }
+            let digits: Vec<usize> = board.candidates[&cell].iter().cloned().sorted().collect();
+            let branches: Vec<Branch> = digits.iter()
+                .map(|&digit| self.propagate(board, &cell, digit, true))
+                .collect();
+            let description = format!("whichever of {} goes in {}", digits.iter().join(","), cell);
+            if self.forcing_chain(board, "Cell Forcing Chain", vec![cell.clone()], description, &branches)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    // Unit Forcing Chains: wherever a digit goes in a unit, the same deduction follows
+    fn unit_forcing_chains(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        let units: Vec<Vec<String>> = [&board.row_peers, &board.col_peers, &board.box_peers].iter()
+            .flat_map(|peers| Self::units_of(board, peers))
+            .collect();
+        for unit in &units {
+            for digit in 1..=9 {
+                let places: Vec<String> = unit.iter()
+                    .filter(|cell| board.candidates[*cell].contains(&digit))
+                    .cloned()
+                    .collect();
+                if places.len() < 2 || places.iter().any(|cell| board.candidates[cell].len() == 1) {
+                    continue;
+                }
+                let branches: Vec<Branch> = places.iter()
+                    .map(|cell| self.propagate(board, cell, digit, true))
+                    .collect();
+                let description = format!("wherever {} goes in {}", digit, places.join(","));
+                if self.forcing_chain(board, "Unit Forcing Chain", places.clone(), description, &branches)? {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // Digit Forcing Chains: whether a candidate is true or false, the same deduction follows
+    fn digit_forcing_chains(&mut self, board: &mut Sudoku) -> Result<bool, Contradiction> {
+        for cell in board.cells.clone() {
+            if board.candidates[&cell].len() < 2 {
+                continue;
+            }
+            for digit in board.candidates[&cell].clone().into_iter().sorted() {
+                let branches = [
+                    self.propagate(board, &cell, digit, true),
+                    self.propagate(board, &cell, digit, false),
+                ];
+                let description = format!("whether {} is {} or not", cell, digit);
+                if self.forcing_chain(board, "Digit Forcing Chain", vec![cell.clone()], description, &branches)? {
+                    return Ok(true);
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    // // Complex rules: X-Wing, Swordfish
+
+    // // X-Wing:
+    // // Look for two rows (the base sets) with two candidates of the same digit (the fish digit).
+    // // If you can find two columns, such that all candidates of the specific digit in both rows
+    // // are contained in the columns (the cover sets), all fish candidates in the columns that are not
+    // // part of the rows can be eliminated. The result is called an X-Wing in the rows.
+    // fn x_wing(&self, board: &mut Sudoku) -> bool {
+    //     for digit in 1..=9 {
+    //         // Check rows as base sets
+    //         if self.x_wing_base_sets(board, digit, |i| (0..9).map(|j| utils::coords_to_cell(i, j)).collect()) {
+    //             return true;
+    //         }
+    //         // Check columns as base sets
+    //         if self.x_wing_base_sets(board, digit, |j| (0..9).map(|i| utils::coords_to_cell(i, j)).collect()) {
+    //             return true;
+    //         }
+    //     }
+    //     false
+    // }
+    
+    // fn x_wing_base_sets(&self, board: &mut Sudoku, digit: usize, base_set_fn: impl Fn(usize) -> Vec<String>) -> bool {
+    //     for i in 0..9 {
+    //         let i_base_set = base_set_fn(i);
+    //         let i_candidate_indices: Vec<_> = i_base_set.iter().enumerate().filter(|(_, cell)| board.candidates[cell].contains(&digit)).map(|(index, _)| index).collect();
+    //         if i_candidate_indices.len() != 2 {
+    //             continue;
+    //         }
+    //         for j in i + 1..9 {
+    //             let j_base_set = base_set_fn(j);
+    //             let j_candidate_indices: Vec<_> = j_base_set.iter().enumerate().filter(|(_, &cell)| board.candidates[&cell].contains(&digit)).map(|(index, _)| index).collect();
+    //             if j_candidate_indices == i_candidate_indices {
+    //                 for &index in &i_candidate_indices {
+    //                     for k in 0..9 {
+    //                         if k != i && k != j {
+    //                             let cell = base_set_fn(k)[index].clone();
+    //                             if board.candidates[&cell].contains(&digit) {
+    //                                 board.eliminate(&cell, digit);
+    //                                 return true;
+    //                             }
+    //                         }
+    //                     }
+    //                 }
+    //             }
+    //         }
+    //     }
+    //     false
+    // }
+    
+
+    
+
+    // // Swordfish:
+    // // Look for three rows (the base sets) with two or three candidates of the same digit (the fish digit).
+    // // If you can find three columns, such that all candidates of the specific digit in the three rows
+    // // are contained in the columns (the cover sets), all fish candidates in the columns that are not
+    // // part of the rows can be eliminated. The result is called a Swordfish in the rows.
+    // fn swordfish(&self, board: &mut Sudoku) -> bool {
+    //     for digit in 1..=9 {
+    //         // Check rows as base sets
+    //         if self.swordfish_base_sets(board, digit, |i| (0..9).map(|j| utils::coords_to_cell(i, j)).collect()) {
+    //             return true;
+    //         }
+    //         // Check columns as base sets
+    //         if self.swordfish_base_sets(board, digit, |j| (0..9).map(|i| utils::coords_to_cell(i, j)).collect()) {
+    //             return true;
+    //         }
+    //     }
+    //     false
+    // }
+    
+    // fn swordfish_base_sets(&self, board: &mut Sudoku, digit: usize, base_set_fn: impl Fn(usize) -> Vec<String>) -> bool {
+    //     let base_sets_with_digit: Vec<_> = (0..9).filter(|&i| base_set_fn(i).iter().any(|cell| board.candidates[cell].contains(&digit))).collect();
+    //     if base_sets_with_digit.len() < 3 {
+    //         return false;
+    //     }
+    //     for combo in base_sets_with_digit.into_iter().combinations(3) {
+    //         let candidate_indices: Vec<_> = combo.iter().flat_map(|&i| {
+    //             let base_set = base_set_fn(i);
+    //             base_set.iter().enumerate().filter(move |(_, &cell)| board.candidates[&cell].contains(&digit)).map(|(index, _)| index).collect::<Vec<_>>()
+    //         }).collect();            
+    //         let unique_candidate_indices: HashSet<_> = candidate_indices.iter().cloned().collect();
+    //         if unique_candidate_indices.len() == 3 {
+    //             for &index in &unique_candidate_indices {
+    //                 for k in 0..9 {
+    //                     if !combo.contains(&k) {
+    //                         let cell = base_set_fn(k)[index].clone();
+    //                         if board.candidates[&cell].contains(&digit) {
+    //                             board.eliminate(&cell, digit);
+    //                             return true;
+    //                         }
+    //                     }
+    //                 }
+    //             }
+    //         }
+    //     }
+    //     false
+    // }
+    
+
+    
+}
+
+// Integer-indexed copy of the candidates and of the peer relation, for the techniques that compare
+// many sets of cells. Cell i is row i / 9, column i % 9.
+struct CellTable {
+    names: Vec<String>,
+    // Bit d is set when d is a candidate of an unsolved cell; solved cells have no bits
+    masks: Vec<u16>,
+    sees: Vec<[bool; 81]>,
+    // Rows 0-8, columns 9-17, boxes 18-26
+    units: Vec<Vec<usize>>,
+}
+
+impl CellTable {
+    fn new(board: &Sudoku) -> CellTable {
+        let names: Vec<String> = (0..81).map(|i| utils::coords_to_cell(i / 9, i % 9)).collect();
+        let masks = names.iter()
+            .map(|name| &board.candidates[name])
+            .map(|digits| if digits.len() > 1 { digits.iter().fold(0, |mask, d| mask | 1 << d) } else { 0 })
+            .collect();
+        let mut sees = vec![[false; 81]; 81];
+        for (i, name) in names.iter().enumerate() {
+            for peer in &board.peers[name] {
+                let (row, col) = utils::cell_to_coords(peer);
+                sees[i][row * 9 + col] = true;
+            }
+        }
+        let mut units: Vec<Vec<usize>> = Vec::new();
+        units.extend((0..9).map(|i| (0..9).map(|j| i * 9 + j).collect()));
+        units.extend((0..9).map(|i| (0..9).map(|j| j * 9 + i).collect()));
+        units.extend((0..9).map(|i| (0..9).map(|j| (i / 3 * 3 + j / 3) * 9 + i % 3 * 3 + j % 3).collect()));
+        CellTable { names, masks, sees, units }
+    }
+
+    // Digits of a mask, in increasing order
+    fn digits(mask: u16) -> Vec<usize> {
+        (1..=9).filter(|d| mask & 1 << d != 0).collect()
+    }
+
+    // Cells outside `exclude` holding the digit that see every one of `cells`
+    fn seeing_all(&self, digit: usize, cells: &[usize], exclude: &[usize]) -> Vec<usize> {
+        (0..81)
+            .filter(|&i| self.masks[i] & 1 << digit != 0 && !exclude.contains(&i))
+            .filter(|&i| cells.iter().all(|&j| self.sees[i][j]))
+            .collect()
+    }
+
+    fn describe(&self, cells: &[usize]) -> String {
+        let names: Vec<&str> = cells.iter().map(|&i| self.names[i].as_str()).collect();
+        names.join(",")
+    }
+}
+
+// The implications that followed from one forcing chain premise (cell, digit, placed or not).
+// candidates is None when the premise led to a contradiction, the last implication.
+struct Branch {
+    premise: (String, usize, bool),
+    candidates: Option<HashMap<String, HashSet<usize>>>,
+    implications: Vec<Implication>,
+}
+
+impl Branch {
+    // The implication placing or eliminating a digit in a cell
+    fn link(&self, cell: &str, digit: usize, placed: bool) -> Option<usize> {
+        self.implications.iter()
+            .position(|link| link.contradiction.is_none() && link.cell == cell && link.digit == digit && link.placed == placed)
+    }
+
+    // Draw the implication tree leading to the given links, one line per branch of the tree:
+    // a run of single implications reads "A1=5 -> A2<>5 -> A2=7", and each fork is indented below.
+    fn tree(&self, leaves: &[usize]) -> Vec<String> {
+        let mut kept = vec![false; self.implications.len()];
+        for &leaf in leaves {
+            let mut link = Some(leaf);
+            while let Some(l) = link {
+                if kept[l] {
+                    break;
+                }
+                kept[l] = true;
+                link = self.implications[l].cause;
+            }
+        }
+        let mut children: Vec<Vec<usize>> = vec![Vec::new(); self.implications.len()];
+        let mut roots = Vec::new();
+        for (l, implication) in self.implications.iter().enumerate().filter(|(l, _)| kept[*l]) {
+            match implication.cause {
+                Some(cause) => children[cause].push(l),
+                None => roots.push(l),
+            }
+        }
+
+        let mut lines = Vec::new();
+        let mut stack: Vec<(usize, usize)> = roots.into_iter().rev().map(|root| (root, 0)).collect();
+        while let Some((mut link, indent)) = stack.pop() {
+            let mut line = format!("{}{}", "  ".repeat(indent), self.implications[link]);
+            while children[link].len() == 1 {
+                link = children[link][0];
+                line += &format!(" -> {}", self.implications[link]);
+            }
+            lines.push(line);
+            for &child in children[link].iter().rev() {
+                stack.push((child, indent + 1));
+            }
+        }
+        lines
+    }
+}
+
+// An Almost Locked Set: n unsolved cells of one unit with n + 1 candidates between them
+struct Als {
+    cells: Vec<usize>,
+    mask: u16,
+}
+
+impl Als {
+    // Cells of the set holding the digit
+    fn cells_with(&self, table: &CellTable, digit: usize) -> Vec<usize> {
+        self.cells.iter().cloned().filter(|&i| table.masks[i] & 1 << digit != 0).collect()
+    }
+
+    fn describe_cells(&self, table: &CellTable) -> Vec<String> {
+        self.cells.iter().map(|&i| table.names[i].clone()).collect()
+    }
+
+    // e.g. "B1,B2 {1,5,7}"
+    fn describe(&self, table: &CellTable) -> String {
+        let digits: Vec<String> = CellTable::digits(self.mask).iter().map(|d| d.to_string()).collect();
+        format!("{} {{{}}}", table.describe(&self.cells), digits.join(","))
+    }
+}
+
+// Which links a chain may use.
+// X-Chain: one digit, strong links within units. XY-Chain: strong links within bivalue cells,
+// weak links between cells on the same digit. Aic: every link, including grouped nodes.
+#[derive(Clone, Copy, PartialEq)]
+enum ChainKind {
+    XChain,
+    XYChain,
+    Aic,
+}
+
+// What a chain proves: a candidate to place (cell, digit), with the node that closes the loop back
+// to it if the path stops short of it, or candidates to eliminate (and whether they come from a
+// continuous nice loop).
+enum ChainResult {
+    Place(String, usize, Option<usize>),
+    Eliminate(bool, Vec<(String, usize)>),
+}
+
+// Graph of strong and weak links between chain nodes, indexed by integers for speed.
+// A node is a digit in one cell, or a digit in a group of cells where a box meets a row or column;
+// a node is true when the digit is in (one of) its cell(s).
+// Strong link: at least one end is true. Weak link: at most one end is true.
+struct ChainGraph {
+    names: Vec<String>,
+    // (digit, cells) for each node; single-cell nodes come first
+    nodes: Vec<(usize, Vec<usize>)>,
+    strong: Vec<Vec<usize>>,
+    weak: Vec<Vec<usize>>,
+    // Single-cell nodes weakly linked to each node, sorted, whichever kind of chain is searched
+    targets: Vec<Vec<usize>>,
+}
+
+impl ChainGraph {
+    fn new(board: &Sudoku, kind: ChainKind) -> ChainGraph {
+        let CellTable { names, sees, units, .. } = CellTable::new(board);
+        let candidates: Vec<&HashSet<usize>> = names.iter().map(|name| &board.candidates[name]).collect();
+
+        let mut nodes: Vec<(usize, Vec<usize>)> = Vec::new();
+        for (i, digits) in candidates.iter().enumerate() {
+            if digits.len() > 1 {
+                let mut digits: Vec<usize> = digits.iter().cloned().collect();
+                digits.sort();
+                nodes.extend(digits.into_iter().map(|digit| (digit, vec![i])));
+            }
+        }
+        let singles = nodes.len();
+        if kind == ChainKind::Aic {
+            // Groups: two or three cells of a box on one row or column
+            for b in 0..9 {
+                let box_cells: Vec<usize> = (0..9).map(|j| (b / 3 * 3 + j / 3) * 9 + b % 3 * 3 + j % 3).collect();
+                for line in 0..6 {
+                    let segment: Vec<usize> = box_cells.iter().cloned()
+                        .filter(|&i| if line < 3 { i / 9 == b / 3 * 3 + line } else { i % 9 == b % 3 * 3 + line - 3 })
+                        .collect();
+                    for digit in 1..=9 {
+                        let group: Vec<usize> = segment.iter().cloned()
+                            .filter(|&i| candidates[i].len() > 1 && candidates[i].contains(&digit))
+                            .collect();
+                        if group.len() > 1 {
+                            nodes.push((digit, group));
+                        }
+                    }
+                }
+            }
+        }
+
+        let n = nodes.len();
+        let mut strong: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut weak: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let mut targets: Vec<Vec<usize>> = vec![Vec::new(); n];
+        let disjoint = |a: &Vec<usize>, b: &Vec<usize>| a.iter().all(|i| !b.contains(i));
+
+        for a in 0..n {
+            for b in a + 1..n {
+                let (digit_a, cells_a) = &nodes[a];
+                let (digit_b, cells_b) = &nodes[b];
+                let weakly_linked = if digit_a == digit_b {
+                    disjoint(cells_a, cells_b) && cells_a.iter().all(|&i| cells_b.iter().all(|&j| sees[i][j]))
+                } else {
+                    b < singles && cells_a == cells_b
+                };
+                if !weakly_linked {
+                    continue;
+                }
+                if b < singles {
+                    targets[a].push(b);
+                    targets[b].push(a);
+                } else if a < singles {
+                    targets[b].push(a);
+                }
+                let allowed = match kind {
+                    ChainKind::XChain => digit_a == digit_b,
+                    ChainKind::XYChain => digit_a == digit_b,
+                    ChainKind::Aic => true,
+                };
+                if allowed {
+                    weak[a].push(b);
+                    weak[b].push(a);
+                }
+            }
+        }
+
+        // Strong links within bivalue cells
+        if kind != ChainKind::XChain {
+            for a in 0..singles.saturating_sub(1) {
+                let cell = nodes[a].1[0];
+                if candidates[cell].len() == 2 && nodes[a + 1].1[0] == cell {
+                    strong[a].push(a + 1);
+                    strong[a + 1].push(a);
+                }
+            }
+        }
+        // Strong links within units: two disjoint nodes covering every place for the digit
+        if kind != ChainKind::XYChain {
+            for unit in &units {
+                for digit in 1..=9 {
+                    let places: Vec<usize> = unit.iter().cloned().filter(|&i| candidates[i].contains(&digit)).collect();
+                    if places.len() < 2 || places.iter().any(|&i| candidates[i].len() == 1) {
+                        continue;
+                    }
+                    let inside: Vec<usize> = (0..n)
+                        .filter(|&node| nodes[node].0 == digit && nodes[node].1.iter().all(|i| places.contains(i)))
+                        .collect();
+                    for (i, &a) in inside.iter().enumerate() {
+                        for &b in &inside[i + 1..] {
+                            if disjoint(&nodes[a].1, &nodes[b].1) && nodes[a].1.len() + nodes[b].1.len() == places.len() {
+                                strong[a].push(b);
+                                strong[b].push(a);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for links in strong.iter_mut().chain(weak.iter_mut()).chain(targets.iter_mut()) {
+            links.sort();
+            links.dedup();
+        }
+
+        ChainGraph { names, nodes, strong, weak, targets }
+    }
+
+    // Breadth-first search for chains starting from the assumption that `start` is false,
+    // alternating strong links (to a true node) and weak links (to a false node).
+    // Returns the path as (node, is_true) states and what the first productive chain proves.
+    fn search(&self, start: usize, max_length: usize) -> Option<(Vec<(usize, bool)>, ChainResult)> {
+        let state_index = |node: usize, value: bool| node * 2 + value as usize;
+        let mut parent: Vec<Option<usize>> = vec![None; self.nodes.len() * 2];
+        let mut visited = vec![false; self.nodes.len() * 2];
+        let mut queue = std::collections::VecDeque::new();
+        visited[state_index(start, false)] = true;
+        queue.push_back((start, false, 0));
+
+        while let Some((node, value, length)) = queue.pop_front() {
+            if length == max_length {
+                continue;
+            }
+            let links = if value { &self.weak[node] } else { &self.strong[node] };
+            for &next in links {
+                let next_state = state_index(next, !value);
+                if visited[next_state] {
+                    continue;
+                }
+                visited[next_state] = true;
+                parent[next_state] = Some(state_index(node, value));
+                if !value {
+                    // next is true whenever start is false: start or next is true
+                    let path = || {
+                        let mut path = vec![(next, true)];
+                        let mut state = next_state;
+                        while let Some(previous) = parent[state] {
+                            path.push((previous / 2, previous % 2 == 1));
+                            state = previous;
+                        }
+                        path.reverse();
+                        path
+                    };
+                    if let Some(result) = self.conclude(start, next, &path) {
+                        let mut path = path();
+                        if let ChainResult::Place(_, _, Some(closing)) = result {
+                            path.extend([(closing, false), (start, true)]);
+                        }
+                        return Some((path, result));
+                    }
+                }
+                queue.push_back((next, !value, length + 1));
+            }
+        }
+        None
+    }
+
+    // What follows from "start or end is true" for the chain built by `path`, if it makes progress.
+    fn conclude(&self, start: usize, end: usize, path: &dyn Fn() -> Vec<(usize, bool)>) -> Option<ChainResult> {
+        let candidate = |node: usize| (self.names[self.nodes[node].1[0]].clone(), self.nodes[node].0);
+        // Discontinuous nice loop: the chain leads back to its start, which must be true
+        if start == end {
+            return if self.nodes[start].1.len() == 1 {
+                let (cell, digit) = candidate(start);
+                Some(ChainResult::Place(cell, digit, None))
+            } else {
+                None
+            };
+        }
+        // Continuous nice loop: the ends are also weakly linked, so each weak link of the loop
+        // holds exactly one true node, and candidates weakly linked to both of its nodes are false
+        if self.weak[end].contains(&start) {
+            let path = path();
+            let mut eliminations = Vec::new();
+            for pair in path.windows(2).chain(std::iter::once(&[(end, true), (start, false)][..])) {
+                let ((a, _), (b, b_value)) = (pair[0], pair[1]);
+                if !b_value {
+                    eliminations.extend(self.common_targets(a, b).into_iter().map(candidate));
+                }
+            }
+            if !eliminations.is_empty() {
+                return Some(ChainResult::Eliminate(true, eliminations));
+            }
+        }
+        let targets = self.common_targets(start, end);
+        // Discontinuous nice loop closing through one of them: it is false, and strongly linked to
+        // start, so start is true. The loop is always found here before it reaches start itself.
+        if let Some(&closing) = targets.iter().find(|node| self.strong[start].contains(node)) {
+            if self.nodes[start].1.len() == 1 {
+                let (cell, digit) = candidate(start);
+                return Some(ChainResult::Place(cell, digit, Some(closing)));
+            }
+        }
+        let eliminations: Vec<(String, usize)> = targets.into_iter().map(candidate).collect();
+        if eliminations.is_empty() {
+            None
+        } else {
+            Some(ChainResult::Eliminate(false, eliminations))
+        }
+    }
+
+    // Single-cell nodes weakly linked to both nodes
+    fn common_targets(&self, a: usize, b: usize) -> Vec<usize> {
+        self.targets[a].iter().cloned()
+            .filter(|node| *node != a && *node != b && self.targets[b].binary_search(node).is_ok())
+            .collect()
+    }
+
+    // A node as (digit)cell, or (digit)cell|cell|cell for a group
+    fn describe_node(&self, node: usize) -> String {
+        let cells: Vec<&str> = self.nodes[node].1.iter().map(|&i| self.names[i].as_str()).collect();
+        format!("({}){}", self.nodes[node].0, cells.join("|"))
+    }
+
+    // A chain in Eureka-like notation: = for strong links, - for weak links
+    fn describe(&self, path: &[(usize, bool)]) -> String {
+        let mut chain = self.describe_node(path[0].0);
+        for &(node, value) in &path[1..] {
+            chain.push_str(if value { "=" } else { "-" });
+            chain.push_str(&self.describe_node(node));
+        }
+        chain
+    }
+}
+
+// Crook's algorithm.
+// A little tough :( sadge
+
+    
+// Local search.
+// Metaheuristics that walk from one complete grid to a neighbouring one. Every box is filled with
+// its missing digits and a move swaps two unfixed cells of a box, as in the annealer below, so
+// boxes always hold each digit once and a move only changes the cost of two rows and two columns.
+// A metaheuristic only decides which move to make next (LocalSearch); LocalSearchSolver keeps the
+// best grid, restarts, and stops once the grid is solved or the stopping criterion is met.
+
+// A move: swap the digits of two unfixed cells of the same box
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Swap {
+    pub a: (usize, usize),
+    pub b: (usize, usize),
+}
+
+// A complete grid whose boxes are permutations respecting the givens, with its cost: minus the
+// number of distinct digits in every row, column and box
+pub struct Neighborhood {
+    givens: [[u8; 9]; 9],
+    // The empty cells of each box, for the boxes with at least two of them
+    boxes: Vec<Vec<(usize, usize)>>,
+    grid: [[u8; 9]; 9],
+    cost: i32,
+}
+
+impl Neighborhood {
+    // Cost of a solved grid: every row, column and box holds 9 distinct digits
+    pub const SOLVED: i32 = -243;
+    // Why there is no grid to start from
+    const CLASHING_GIVENS: &'static str = "the givens repeat a digit within a box";
+
+    // The outcome of a search whose best grid, with its cost, is best and which ran into the
+    // limit stopped. A search only ends without either once no box has two cells to swap, and
+    // then the only way to fill the boxes clashes.
+    fn outcome(best: (i32, [[u8; 9]; 9]), stopped: Option<Limit>) -> SolveOutcome {
+        match stopped {
+            _ if best.0 == Self::SOLVED => SolveOutcome::Solved(best.1),
+            Some(limit) => SolveOutcome::Stopped(limit),
+            None => SolveOutcome::Unsolvable("the only way to fill the boxes repeats a digit in a row or column".to_string()),
+        }
+    }
+
+    // A random grid for the givens. None if the givens repeat a digit within a box.
+    pub fn new(givens: &[[u8; 9]; 9], rng: &mut StdRng) -> Option<Self> {
+        let grid = Self::fill_boxes(givens, rng)?;
+        Some(Neighborhood { givens: *givens, boxes: Self::unfixed_cells(givens), grid, cost: Self::score(&grid) })
+    }
+
+    pub fn grid(&self) -> &[[u8; 9]; 9] {
+        &self.grid
+    }
+
+    pub fn cost(&self) -> i32 {
+        self.cost
+    }
+
+    // False when no box has two empty cells to swap
+    pub fn has_moves(&self) -> bool {
+        !self.boxes.is_empty()
+    }
+
+    // Start over from a new random grid
+    pub fn restart(&mut self, rng: &mut StdRng) {
+        self.grid = Self::fill_boxes(&self.givens, rng).unwrap();
+        self.cost = Self::score(&self.grid);
+    }
+
+    // A move picked at random. Panics if there are none.
+    pub fn random_move(&self, rng: &mut StdRng) -> Swap {
+        let cells = &self.boxes[rng.gen_range(0..self.boxes.len())];
+        let picked: Vec<&(usize, usize)> = cells.choose_multiple(rng, 2).collect();
+        Swap { a: *picked[0], b: *picked[1] }
+    }
+
+    // Every move, box by box
+    pub fn moves(&self) -> impl Iterator<Item = Swap> + '_ {
+        self.boxes.iter().flat_map(|cells| cells.iter().tuple_combinations().map(|(&a, &b)| Swap { a, b }))
+    }
+
+    // Change in cost the move would make
+    pub fn delta(&self, swap: Swap) -> i32 {
+        Self::swap_delta(&self.grid, swap.a, swap.b)
+    }
+
+    // Make a move whose delta is known
+    pub fn apply(&mut self, swap: Swap, delta: i32) {
+        Self::swap(&mut self.grid, swap.a, swap.b);
+        self.cost += delta;
+    }
+
+    // The empty cells of each box, for the boxes with at least two of them
+    fn unfixed_cells(givens: &[[u8; 9]; 9]) -> Vec<Vec<(usize, usize)>> {
+        (0..9)
+            .map(|b| (0..9)
+                .map(|k| (b / 3 * 3 + k / 3, b % 3 * 3 + k % 3))
+                .filter(|&(row, col)| givens[row][col] == 0)
+                .collect::<Vec<_>>())
+            .filter(|cells| cells.len() >= 2)
+            .collect()
+    }
+
+    // Fill the empty cells of each box with the digits it is missing, in random order.
+    // None if the givens repeat a digit within a box.
+    fn fill_boxes(givens: &[[u8; 9]; 9], rng: &mut StdRng) -> Option<[[u8; 9]; 9]> {
+        let mut grid = *givens;
+        for b in 0..9 {
+            let cells: Vec<(usize, usize)> = (0..9).map(|k| (b / 3 * 3 + k / 3, b % 3 * 3 + k % 3)).collect();
+            let present: Vec<u8> = cells.iter().map(|&(row, col)| givens[row][col]).filter(|&d| d != 0).collect();
+            if present.iter().unique().count() != present.len() {
+                return None;
+            }
+            let mut missing: Vec<u8> = (1..=9).filter(|d| !present.contains(d)).collect();
+            missing.shuffle(rng);
+            for &(row, col) in cells.iter().filter(|&&(row, col)| givens[row][col] == 0) {
+                grid[row][col] = missing.pop().unwrap();
+            }
+        }
+        Some(grid)
+    }
+
+    // Minus the number of distinct digits in every row, column and box: -243 when solved
+    fn score(grid: &[[u8; 9]; 9]) -> i32 {
+        let mut score = 0;
+        for i in 0..9 {
+            score -= Self::row_distinct(grid, i) + Self::col_distinct(grid, i);
+            let box_values: [u8; 9] = std::array::from_fn(|k| grid[i / 3 * 3 + k / 3][i % 3 * 3 + k % 3]);
+            score -= Sudoku::unique_elements(box_values);
+        }
+        score
+    }
+
+    fn row_distinct(grid: &[[u8; 9]; 9], row: usize) -> i32 {
+        Sudoku::unique_elements(grid[row])
+    }
+
+    fn col_distinct(grid: &[[u8; 9]; 9], col: usize) -> i32 {
+        Sudoku::unique_elements(std::array::from_fn(|row| grid[row][col]))
+    }
+
+    fn swap(grid: &mut [[u8; 9]; 9], a: (usize, usize), b: (usize, usize)) {
+        let digit = grid[a.0][a.1];
+        grid[a.0][a.1] = grid[b.0][b.1];
+        grid[b.0][b.1] = digit;
+    }
+
+    // Change in score from swapping two cells of a box: only their rows and columns are affected
+    fn swap_delta(grid: &[[u8; 9]; 9], a: (usize, usize), b: (usize, usize)) -> i32 {
+        let rows: Vec<usize> = [a.0, b.0].into_iter().unique().collect();
+        let cols: Vec<usize> = [a.1, b.1].into_iter().unique().collect();
+        let distinct = |grid: &[[u8; 9]; 9]| -> i32 {
+            rows.iter().map(|&row| Self::row_distinct(grid, row)).sum::<i32>()
+                + cols.iter().map(|&col| Self::col_distinct(grid, col)).sum::<i32>()
+        };
+        let mut swapped = *grid;
+        Self::swap(&mut swapped, a, b);
+        distinct(grid) - distinct(&swapped)
+    }
+
+}
+
+// When a local search gives up without solving the grid
+#[derive(Clone, Debug)]
+pub enum StoppingCriterion {
+    // After this many iterations, restarts included
+    Iterations(usize),
+    // After this many iterations without a better best cost, restarts included
+    Stalled(usize),
+    // As soon as any of these is met
+    Any(Vec<StoppingCriterion>),
+}
+
+impl StoppingCriterion {
+    // The limit reached, if any
+    fn met(&self, iterations: usize, stalled: usize) -> Option<Limit> {
+        match self {
+            StoppingCriterion::Iterations(limit) => (iterations >= *limit).then_some(Limit::Iterations(*limit)),
+            StoppingCriterion::Stalled(limit) => (stalled >= *limit).then_some(Limit::Stalled(*limit)),
+            StoppingCriterion::Any(criteria) => criteria.iter().find_map(|criterion| criterion.met(iterations, stalled)),
+        }
+    }
+}
+
+// Settings for LocalSearchSolver
+#[derive(Clone, Debug)]
+pub struct LocalSearchConfig {
+    pub stopping: StoppingCriterion,
+    pub restart: RestartPolicy,
+    // Fixed seed for reproducible runs, otherwise seeded from entropy
+    pub seed: Option<u64>,
+}
+
+// A metaheuristic over the box-swap neighborhood
+pub trait LocalSearch {
+    // Called when a solve starts and after every restart
+    fn reset(&mut self, neighborhood: &Neighborhood);
+    // Pick a move and make it, or leave the grid as it is. best is the lowest cost seen so far.
+    // Returns whether a move was made.
+    fn step(&mut self, neighborhood: &mut Neighborhood, best: i32, rng: &mut StdRng) -> bool;
+    fn name(&self) -> String;
+}
+
+pub struct LocalSearchSolver<S: LocalSearch> {
+    search: S,
+    config: LocalSearchConfig,
+    stats: SolveStats,
+}
+
+impl<S: LocalSearch> Solver for LocalSearchSolver<S> {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        let mut rng = match self.config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut neighborhood = match Neighborhood::new(&board.board, &mut rng) {
+            Some(neighborhood) => neighborhood,
+            None => return SolveOutcome::Unsolvable(Neighborhood::CLASHING_GIVENS.to_string()),
+        };
+        self.search.reset(&neighborhood);
+        let mut best = (neighborhood.cost(), *neighborhood.grid());
+        // Iterations since the best cost last improved, and since it last improved or the grid was refilled
+        let (mut stalled, mut since_restart) = (0, 0);
+
+        self.stats = SolveStats::default();
+        let mut stopped = None;
+        while best.0 > Neighborhood::SOLVED && neighborhood.has_moves() {
+            stopped = self.config.stopping.met(self.stats.iterations, stalled)
+                .or_else(|| budget.check(0, self.stats.iterations));
+            if stopped.is_some() {
+                break;
+            }
+            self.stats.iterations += 1;
+            if self.search.step(&mut neighborhood, best.0, &mut rng) {
+                self.stats.accepted_moves += 1;
+            }
+            observer.on_iteration(self.stats.iterations, neighborhood.cost(), None);
+
+            if neighborhood.cost() < best.0 {
+                best = (neighborhood.cost(), *neighborhood.grid());
+                stalled = 0;
+                since_restart = 0;
+            } else {
+                stalled += 1;
+                since_restart += 1;
+            }
+            if let RestartPolicy::Stalled { iterations } = self.config.restart {
+                if since_restart >= iterations {
+                    neighborhood.restart(&mut rng);
+                    self.search.reset(&neighborhood);
+                    since_restart = 0;
+                    self.stats.restarts += 1;
+                }
+            }
+        }
+
+        board.board = best.1;
+        observer.on_finish(&format!("{} search finished after {} iterations.", self.search.name(), self.stats.iterations));
+        Neighborhood::outcome(best, stopped)
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        self.search.name()
+    }
+
+    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
+        // unneeded
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+impl<S: LocalSearch> LocalSearchSolver<S> {
+    pub fn new(search: S, config: LocalSearchConfig) -> Self {
+        LocalSearchSolver { search, config, stats: SolveStats::default() }
+    }
+
+    // Iterations used by the last solve
+    pub fn iterations(&self) -> usize {
+        self.stats.iterations
+    }
+}
+
+// Tabu search (Glover, 1986): every iteration makes the best move of the whole neighborhood, even
+// when it makes the grid worse, but a move made in the last `tenure` iterations is tabu, so the
+// search does not just swap the same two cells back. A tabu move is still allowed if it would beat
+// the best cost seen so far (aspiration). Ties are broken at random.
+pub struct TabuSearch {
+    tenure: usize,
+    // The last moves made, oldest first
+    tabu: VecDeque<Swap>,
+}
+
+impl TabuSearch {
+    pub fn new(tenure: usize) -> Self {
+        TabuSearch { tenure, tabu: VecDeque::with_capacity(tenure + 1) }
+    }
+}
+
+impl LocalSearch for TabuSearch {
+    fn reset(&mut self, _neighborhood: &Neighborhood) {
+        self.tabu.clear();
+    }
+
+    fn step(&mut self, neighborhood: &mut Neighborhood, best: i32, rng: &mut StdRng) -> bool {
+        let mut chosen = Vec::new();
+        let mut chosen_delta = i32::MAX;
+        for swap in neighborhood.moves() {
+            let delta = neighborhood.delta(swap);
+            if self.tabu.contains(&swap) && neighborhood.cost() + delta >= best {
+                continue;
+            }
+            if delta < chosen_delta {
+                chosen.clear();
+                chosen_delta = delta;
+            }
+            if delta == chosen_delta {
+                chosen.push(swap);
+            }
+        }
+
+        if let Some(&swap) = chosen.choose(rng) {
+            neighborhood.apply(swap, chosen_delta);
+            self.tabu.push_back(swap);
+            if self.tabu.len() > self.tenure {
+                self.tabu.pop_front();
+            }
+            return true;
+        }
+        false
+    }
+
+    fn name(&self) -> String {
+        format!("Tabu (tenure={})", self.tenure)
+    }
+}
+
+// Late acceptance hill climbing (Burke & Bykov, 2017): a random move is accepted if the grid would
+// be no worse than it is now, or than it was `length` iterations ago.
+pub struct LateAcceptance {
+    length: usize,
+    // The cost at each of the last `length` iterations, as a ring buffer
+    history: Vec<i32>,
+    iteration: usize,
+}
+
+impl LateAcceptance {
+    pub fn new(length: usize) -> Self {
+        LateAcceptance { length: length.max(1), history: Vec::new(), iteration: 0 }
+    }
+}
+
+impl LocalSearch for LateAcceptance {
+    fn reset(&mut self, neighborhood: &Neighborhood) {
+        self.history = vec![neighborhood.cost(); self.length];
+        self.iteration = 0;
+    }
+
+    fn step(&mut self, neighborhood: &mut Neighborhood, _best: i32, rng: &mut StdRng) -> bool {
+        let swap = neighborhood.random_move(rng);
+        let delta = neighborhood.delta(swap);
+        let slot = self.iteration % self.length;
+        let accepted = delta <= 0 || neighborhood.cost() + delta <= self.history[slot];
+        if accepted {
+            neighborhood.apply(swap, delta);
+        }
+        self.history[slot] = neighborhood.cost();
+        self.iteration += 1;
+        accepted
+    }
+
+    fn name(&self) -> String {
+        format!("Late acceptance (length={})", self.length)
+    }
+}
+
+// Stochastic search.
+// Simulated annealing over complete grids (Lewis, 2007): the givens are fixed, every box is filled
+// with its missing digits, and a move swaps two unfixed cells of a box. Boxes therefore always
+// hold each digit once, and the cost only counts the digits missing from rows and columns.
+// https://en.wikipedia.org/wiki/Simulated_annealing
+
+// When to raise the temperature again, once the best score has stopped improving
+#[derive(Clone, Debug)]
+pub enum ReheatPolicy {
+    Never,
+    // Reset the temperature to `temperature` every `iterations` iterations without improvement;
+    // 0 iterations never reheats
+    Stalled { iterations: usize, temperature: f64 },
+}
+
+// When to start over from a new random grid, once the best score has stopped improving
+#[derive(Clone, Debug)]
+pub enum RestartPolicy {
+    Never,
+    // Refill the boxes after `iterations` iterations without improvement (and, when annealing,
+    // reset the temperature)
+    Stalled { iterations: usize },
+}
+
+// How the temperature falls from one iteration to the next
+pub trait CoolingSchedule {
+    // Called when annealing starts, reheats or restarts, at the given temperature
+    fn reset(&mut self, temperature: f64);
+    // The temperature for the next iteration; accepted tells whether the last move was accepted
+    fn cool(&mut self, temperature: f64, accepted: bool) -> f64;
+    fn name(&self) -> String;
+}
+
+// Temperatures never drop below this, so exp(-delta / T) stays defined
+const MIN_TEMPERATURE: f64 = 1e-9;
+
+// T <- factor * T
+pub struct ExponentialCooling {
+    factor: f64,
+}
+
+impl ExponentialCooling {
+    pub fn new(factor: f64) -> Self {
+        ExponentialCooling { factor }
+    }
+}
+
+impl CoolingSchedule for ExponentialCooling {
+    fn reset(&mut self, _temperature: f64) {}
+
+    fn cool(&mut self, temperature: f64, _accepted: bool) -> f64 {
+        (temperature * self.factor).max(MIN_TEMPERATURE)
+    }
+
+    fn name(&self) -> String {
+        format!("exponential {}", self.factor)
+    }
+}
+
+// T <- T - step
+pub struct LinearCooling {
+    step: f64,
+}
+
+impl LinearCooling {
+    pub fn new(step: f64) -> Self {
+        LinearCooling { step }
+    }
+}
+
+impl CoolingSchedule for LinearCooling {
+    fn reset(&mut self, _temperature: f64) {}
+
+    fn cool(&mut self, temperature: f64, _accepted: bool) -> f64 {
+        (temperature - self.step).max(MIN_TEMPERATURE)
+    }
+
+    fn name(&self) -> String {
+        format!("linear {}", self.step)
+    }
+}
+
+// T_k = T_0 * ln 2 / ln(k + 2), k iterations after the last reset (Geman & Geman)
+pub struct LogarithmicCooling {
+    initial: f64,
+    iteration: usize,
+}
+
+impl Default for LogarithmicCooling {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LogarithmicCooling {
+    pub fn new() -> Self {
+        LogarithmicCooling { initial: 1.0, iteration: 0 }
+    }
+}
+
+impl CoolingSchedule for LogarithmicCooling {
+    fn reset(&mut self, temperature: f64) {
+        self.initial = temperature;
+        self.iteration = 0;
+    }
+
+    fn cool(&mut self, _temperature: f64, _accepted: bool) -> f64 {
+        self.iteration += 1;
+        (self.initial * 2f64.ln() / (self.iteration as f64 + 2.0).ln()).max(MIN_TEMPERATURE)
+    }
+
+    fn name(&self) -> String {
+        "logarithmic".to_string()
+    }
+}
+
+// T <- T / (1 + beta * T) (Lundy & Mees, 1986)
+pub struct LundyMeesCooling {
+    beta: f64,
+}
+
+impl LundyMeesCooling {
+    pub fn new(beta: f64) -> Self {
+        LundyMeesCooling { beta }
+    }
+}
+
+impl CoolingSchedule for LundyMeesCooling {
+    fn reset(&mut self, _temperature: f64) {}
+
+    fn cool(&mut self, temperature: f64, _accepted: bool) -> f64 {
+        (temperature / (1.0 + self.beta * temperature)).max(MIN_TEMPERATURE)
+    }
+
+    fn name(&self) -> String {
+        format!("Lundy-Mees {}", self.beta)
+    }
+}
+
+// Steers the temperature towards a target acceptance rate: after every window of iterations,
+// cool by factor if more moves than the target were accepted, and heat by 1 / factor otherwise.
+pub struct AdaptiveCooling {
+    target_acceptance: f64,
+    window: usize,
+    factor: f64,
+    iterations: usize,
+    accepted: usize,
+}
+
+impl AdaptiveCooling {
+    pub fn new(target_acceptance: f64, window: usize, factor: f64) -> Self {
+        AdaptiveCooling { target_acceptance, window, factor, iterations: 0, accepted: 0 }
+    }
+}
+
+impl CoolingSchedule for AdaptiveCooling {
+    fn reset(&mut self, _temperature: f64) {
+        self.iterations = 0;
+        self.accepted = 0;
+    }
+
+    fn cool(&mut self, temperature: f64, accepted: bool) -> f64 {
+        self.iterations += 1;
+        if accepted {
+            self.accepted += 1;
+        }
+        if self.iterations < self.window {
+            return temperature;
+        }
+        let rate = self.accepted as f64 / self.iterations as f64;
+        self.reset(temperature);
+        if rate > self.target_acceptance {
+            (temperature * self.factor).max(MIN_TEMPERATURE)
+        } else {
+            temperature / self.factor
+        }
+    }
+
+    fn name(&self) -> String {
+        format!("adaptive {}", self.target_acceptance)
+    }
+}
+
+// The cooling schedules that can be picked from AnnealingConfig
+#[derive(Clone, Debug)]
+pub enum Cooling {
+    Exponential { factor: f64 },
+    Linear { step: f64 },
+    Logarithmic,
+    LundyMees { beta: f64 },
+    Adaptive { target_acceptance: f64, window: usize, factor: f64 },
+}
+
+impl Cooling {
+    pub fn schedule(&self) -> Box<dyn CoolingSchedule> {
+        match *self {
+            Cooling::Exponential { factor } => Box::new(ExponentialCooling::new(factor)),
+            Cooling::Linear { step } => Box::new(LinearCooling::new(step)),
+            Cooling::Logarithmic => Box::new(LogarithmicCooling::new()),
+            Cooling::LundyMees { beta } => Box::new(LundyMeesCooling::new(beta)),
+            Cooling::Adaptive { target_acceptance, window, factor } => Box::new(AdaptiveCooling::new(target_acceptance, window, factor)),
+        }
+    }
+}
+
+// Settings for StochasticSolver
+#[derive(Clone, Debug)]
+pub struct AnnealingConfig {
+    pub initial_temperature: f64,
+    pub cooling: Cooling,
+    // Iterations allowed per solve, restarts included
+    pub max_iterations: usize,
+    pub reheat: ReheatPolicy,
+    pub restart: RestartPolicy,
+    // Fixed seed for reproducible runs, otherwise seeded from entropy
+    pub seed: Option<u64>,
+}
+
+impl Default for AnnealingConfig {
+    fn default() -> Self {
+        AnnealingConfig {
+            initial_temperature: 0.3,
+            cooling: Cooling::Exponential { factor: 0.99999 },
+            max_iterations: 2_000_000,
+            reheat: ReheatPolicy::Stalled { iterations: 20_000, temperature: 0.3 },
+            restart: RestartPolicy::Stalled { iterations: 200_000 },
+            seed: None,
+        }
+    }
+}
+
+pub struct StochasticSolver {
+    config: AnnealingConfig,
+    schedule: Box<dyn CoolingSchedule>,
+    stats: SolveStats,
+}
+
+impl Solver for StochasticSolver {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        let mut rng = match self.config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let mut neighborhood = match Neighborhood::new(&board.board, &mut rng) {
+            Some(neighborhood) => neighborhood,
+            None => return SolveOutcome::Unsolvable(Neighborhood::CLASHING_GIVENS.to_string()),
+        };
+        let mut best = (neighborhood.cost(), *neighborhood.grid());
+        let mut temperature = self.config.initial_temperature;
+        self.schedule.reset(temperature);
+        let mut stalled: usize = 0;
+
+        self.stats = SolveStats::default();
+        let mut stopped = None;
+        while best.0 > Neighborhood::SOLVED && self.stats.iterations < self.config.max_iterations && neighborhood.has_moves() {
+            stopped = budget.check(0, self.stats.iterations);
+            if stopped.is_some() {
+                break;
+            }
+            self.stats.iterations += 1;
+
+            let swap = neighborhood.random_move(&mut rng);
+            let delta = neighborhood.delta(swap);
+            let accepted = Self::accept(delta, temperature, &mut rng);
+            if accepted {
+                neighborhood.apply(swap, delta);
+                self.stats.accepted_moves += 1;
+            }
+            observer.on_iteration(self.stats.iterations, neighborhood.cost(), Some(temperature));
+
+            if neighborhood.cost() < best.0 {
+                best = (neighborhood.cost(), *neighborhood.grid());
+                stalled = 0;
+            } else {
+                stalled += 1;
+            }
+            temperature = self.schedule.cool(temperature, accepted);
+
+            if let ReheatPolicy::Stalled { iterations, temperature: reheated } = self.config.reheat {
+                // is_multiple_of(0) only holds for 0, so 0 iterations never reheats
+                if stalled > 0 && stalled.is_multiple_of(iterations) {
+                    temperature = reheated;
+                    self.schedule.reset(temperature);
+                }
+            }
+            if let RestartPolicy::Stalled { iterations } = self.config.restart {
+                if stalled >= iterations {
+                    neighborhood.restart(&mut rng);
+                    temperature = self.config.initial_temperature;
+                    self.schedule.reset(temperature);
+                    stalled = 0;
+                    self.stats.restarts += 1;
+                }
+            }
+        }
+
+        board.board = best.1;
+        observer.on_finish(&format!("Stochastic solver finished after {} iterations.", self.stats.iterations));
+        let stopped = stopped.or((self.stats.iterations >= self.config.max_iterations).then_some(Limit::Iterations(self.config.max_iterations)));
+        Neighborhood::outcome(best, stopped)
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        format!("Stochastic (T={}, cooling={})", self.config.initial_temperature, self.schedule.name())
+    }
+
+    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
+        // unneeded
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+} 
+
+impl Default for StochasticSolver {
+    fn default() -> Self {
+        Self::new(AnnealingConfig::default())
+    }
+}
+
+impl StochasticSolver {
+    pub fn new(config: AnnealingConfig) -> Self {
+        let schedule = config.cooling.schedule();
+        StochasticSolver { config, schedule, stats: SolveStats::default() }
+    }
+
+    // Use a cooling schedule of your own instead of the one picked in the config
+    pub fn with_cooling_schedule(mut self, schedule: Box<dyn CoolingSchedule>) -> Self {
+        self.schedule = schedule;
+        self
+    }
+
+    // Iterations used by the last solve
+    pub fn iterations(&self) -> usize {
+        self.stats.iterations
+    }
+
+    // Always accept a move that does not make the score worse, and a worse one with
+    // probability exp(-delta / T)
+    fn accept(delta: i32, temperature: f64, rng: &mut StdRng) -> bool {
+        if delta <= 0 {
+            true
+        } else {
+            let u: f64 = rng.gen();
+            u < (-delta as f64 / temperature).exp()
+        }
+    }
+}
+
+
+
+// Genetic algorithm.
+// Each chromosome is a grid whose boxes are permutations respecting the givens, as in the annealer,
+// and is scored with the same cost. Children mix whole boxes (or bands of three boxes) of two
+// parents chosen by tournament, then may have two unfixed cells of a box swapped. The best
+// individuals survive unchanged, and the population is reseeded when the best score stagnates.
+
+// How a child inherits from its two parents
+#[derive(Clone, Debug)]
+pub enum Crossover {
+    // Each band of three boxes (a row of boxes) from either parent
+    RowBlocks,
+    // Each box from either parent
+    BoxBlocks,
+}
+
+// Settings for GeneticSolver
+#[derive(Clone, Debug)]
+pub struct GeneticConfig {
+    pub population_size: usize,
+    pub max_generations: usize,
+    pub tournament_size: usize,
+    // Number of best individuals copied unchanged into the next generation
+    pub elitism: usize,
+    pub crossover: Crossover,
+    // Probability that a child is bred by crossover rather than copied from its first parent
+    pub crossover_rate: f64,
+    // Probability that a child gets a swap mutation
+    pub mutation_rate: f64,
+    // Generations without a better best score before the population is reseeded
+    pub restart_after: usize,
+    // Fixed seed for reproducible runs, otherwise seeded from entropy
+    pub seed: Option<u64>,
+}
+
+impl Default for GeneticConfig {
+    fn default() -> Self {
+        GeneticConfig {
+            population_size: 100,
+            max_generations: 30_000,
+            tournament_size: 3,
+            elitism: 2,
+            crossover: Crossover::BoxBlocks,
+            crossover_rate: 0.9,
+            mutation_rate: 1.0,
+            restart_after: 300,
+            seed: None,
+        }
+    }
+}
+
+pub struct GeneticSolver {
+    config: GeneticConfig,
+    // iterations counts generations
+    stats: SolveStats,
+}
+
+impl Solver for GeneticSolver {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        let mut rng = match self.config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let givens = board.board;
+        let boxes = Neighborhood::unfixed_cells(&givens);
+        let mut population = match self.seed_population(&givens, &mut rng) {
+            Some(population) => population,
+            None => return SolveOutcome::Unsolvable(Neighborhood::CLASHING_GIVENS.to_string()),
+        };
+
+        let mut best = population[0];
+        let mut stalled = 0;
+        self.stats = SolveStats::default();
+        let mut stopped = None;
+        while best.1 > Neighborhood::SOLVED && self.stats.iterations < self.config.max_generations {
+            stopped = budget.exhausted(0, self.stats.iterations);
+            if stopped.is_some() {
+                break;
+            }
+            self.stats.iterations += 1;
+            let mut next: Vec<([[u8; 9]; 9], i32)> = population[..self.config.elitism.min(population.len())].to_vec();
+            while next.len() < self.population_size() {
+                let first = self.tournament(&population, &mut rng);
+                let second = self.tournament(&population, &mut rng);
+                let mut child = if rng.gen::<f64>() < self.config.crossover_rate {
+                    self.crossover(&first.0, &second.0, &mut rng)
+                } else {
+                    first.0
+                };
+                if !boxes.is_empty() && rng.gen::<f64>() < self.config.mutation_rate {
+                    Self::mutate(&mut child, &givens, &boxes, &mut rng);
+                }
+                next.push((child, Neighborhood::score(&child)));
+            }
+            next.sort_by_key(|individual| individual.1);
+            population = next;
+            observer.on_iteration(self.stats.iterations, population[0].1, None);
+
+            if population[0].1 < best.1 {
+                best = population[0];
+                stalled = 0;
+            } else {
+                stalled += 1;
+            }
+            if stalled >= self.config.restart_after {
+                population = self.seed_population(&givens, &mut rng).unwrap();
+                stalled = 0;
+                self.stats.restarts += 1;
+            }
+        }
+
+        board.board = best.0;
+        observer.on_finish(&format!("Genetic solver finished after {} generations.", self.stats.iterations));
+        let stopped = stopped.or((self.stats.iterations >= self.config.max_generations).then_some(Limit::Generations(self.config.max_generations)));
+        Neighborhood::outcome((best.1, best.0), stopped)
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        format!("Genetic (population={}, crossover={:?})", self.config.population_size, self.config.crossover)
+    }
+
+    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
+        // unneeded
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+impl Default for GeneticSolver {
+    fn default() -> Self {
+        Self::new(GeneticConfig::default())
+    }
+}
+
+impl GeneticSolver {
+    const MUTATION_ATTEMPTS: usize = 10;
+
+    pub fn new(config: GeneticConfig) -> Self {
+        GeneticSolver { config, stats: SolveStats::default() }
+    }
+
+    // Generations used by the last solve
+    pub fn generations(&self) -> usize {
+        self.stats.iterations
+    }
+
+    // The configured population size, but at least one individual, so there is always a best one
+    fn population_size(&self) -> usize {
+        self.config.population_size.max(1)
+    }
+
+    // A random population with its scores, best first.
+    // None if the givens repeat a digit within a box.
+    fn seed_population(&self, givens: &[[u8; 9]; 9], rng: &mut StdRng) -> Option<Vec<([[u8; 9]; 9], i32)>> {
+        let mut population = Vec::with_capacity(self.population_size());
+        for _ in 0..self.population_size() {
+            let grid = Neighborhood::fill_boxes(givens, rng)?;
+            population.push((grid, Neighborhood::score(&grid)));
+        }
+        population.sort_by_key(|individual| individual.1);
+        Some(population)
+    }
+
+    // The best of a few individuals drawn at random
+    fn tournament(&self, population: &[([[u8; 9]; 9], i32)], rng: &mut StdRng) -> ([[u8; 9]; 9], i32) {
+        (0..self.config.tournament_size.max(1))
+            .map(|_| population[rng.gen_range(0..population.len())])
+            .min_by_key(|individual| individual.1)
+            .unwrap()
+    }
+
+    // Swap two unfixed cells of a random box, preferring a swap that does not move a digit
+    // into a row or column where it is already given
+    fn mutate(grid: &mut [[u8; 9]; 9], givens: &[[u8; 9]; 9], boxes: &[Vec<(usize, usize)>], rng: &mut StdRng) {
+        let given_elsewhere = |digit: u8, (row, col): (usize, usize)| {
+            (0..9).any(|k| givens[row][k] == digit || givens[k][col] == digit)
+        };
+        let mut swap = None;
+        for _ in 0..Self::MUTATION_ATTEMPTS {
+            let cells = &boxes[rng.gen_range(0..boxes.len())];
+            let picked: Vec<&(usize, usize)> = cells.choose_multiple(rng, 2).collect();
+            let (a, b) = (*picked[0], *picked[1]);
+            swap = Some((a, b));
+            if !given_elsewhere(grid[a.0][a.1], b) && !given_elsewhere(grid[b.0][b.1], a) {
+                break;
+            }
+        }
+        if let Some((a, b)) = swap {
+            Neighborhood::swap(grid, a, b);
+        }
+    }
+
+    // Copy whole boxes, or bands of boxes, from one parent or the other
+    fn crossover(&self, first: &[[u8; 9]; 9], second: &[[u8; 9]; 9], rng: &mut StdRng) -> [[u8; 9]; 9] {
+        let mut child = *first;
+        let mut from_second = [false; 9];
+        match self.config.crossover {
+            Crossover::RowBlocks => {
+                for band in 0..3 {
+                    let pick = rng.gen::<bool>();
+                    from_second[band * 3..band * 3 + 3].fill(pick);
+                }
+            }
+            Crossover::BoxBlocks => from_second.iter_mut().for_each(|pick| *pick = rng.gen()),
+        }
+        for (b, _) in from_second.iter().enumerate().filter(|(_, &pick)| pick) {
+            for k in 0..9 {
+                let (row, col) = (b / 3 * 3 + k / 3, b % 3 * 3 + k % 3);
+                child[row][col] = second[row][col];
+            }
+        }
+        child
+    }
+}
+
+// Ant colony optimisation.
+// Lloyd & Amos's ant colony system for sudoku (2019). Every ant starts from the propagated givens
+// at a random cell and visits the cells in order, picking a digit for each cell that is not fixed
+// yet: the one with the most pheromone with probability q0, otherwise by roulette wheel. Each pick
+// is propagated with assign/eliminate; a pick that leads to a contradiction is undone and the cell
+// left open. Picks wear their pheromone down towards its initial value (local update), so ants
+// spread out. The best fill so far, scored by its number of fixed cells, then lays pheromone on its
+// digits (global update), and the amount it lays evaporates every iteration (best-value evaporation)
+// so the colony can move away from it.
+// https://arxiv.org/abs/1805.03545
+
+// Settings for AntColonySolver
+#[derive(Clone, Debug)]
+pub struct AntColonyConfig {
+    pub ants: usize,
+    // Probability of picking the digit with the most pheromone rather than by roulette wheel (q0)
+    pub greediness: f64,
+    // Share of pheromone worn off a digit when an ant picks it (xi)
+    pub local_evaporation: f64,
+    // Share of pheromone replaced by the best fill's reward in the global update (rho)
+    pub global_evaporation: f64,
+    // Share of the best fill's reward that evaporates every iteration
+    pub best_evaporation: f64,
+    pub max_iterations: usize,
+    // Fixed seed for reproducible runs, otherwise seeded from entropy
+    pub seed: Option<u64>,
+}
+
+impl Default for AntColonyConfig {
+    fn default() -> Self {
+        AntColonyConfig {
+            ants: 10,
+            greediness: 0.9,
+            local_evaporation: 0.1,
+            global_evaporation: 0.9,
+            best_evaporation: 0.005,
+            max_iterations: 2_000,
+            seed: None,
+        }
+    }
+}
+
+pub struct AntColonySolver {
+    config: AntColonyConfig,
+    // Pheromone on each digit (index digit - 1) of each cell, cells in board.cells order
+    pheromone: Vec<[f64; 9]>,
+    // guesses counts the digits the ants pick, backtracks the picks that failed and were undone
+    stats: SolveStats,
+}
+
+impl Solver for AntColonySolver {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        let mut rng = match self.config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        let propagation = self.stats.start(board);
+        let start = board.candidates.clone();
+        let initial = 1.0 / board.cells.len() as f64;
+        self.pheromone = vec![[initial; 9]; board.cells.len()];
+
+        // The best fill so far: its candidates, its number of fixed cells, and the pheromone it lays
+        let mut best = (start.clone(), Self::fixed(board), 0.0);
+        let mut stopped = None;
+        while best.1 < board.cells.len() && self.stats.iterations < self.config.max_iterations {
+            stopped = budget.exhausted(0, self.stats.iterations);
+            if stopped.is_some() {
+                break;
+            }
+            self.stats.iterations += 1;
+
+            let mut iteration_best: Option<(HashMap<String, HashSet<usize>>, usize)> = None;
+            for _ in 0..self.config.ants.max(1) {
+                board.candidates = start.clone();
+                self.construct(board, initial, &mut rng);
+                let fixed = Self::fixed(board);
+                if iteration_best.as_ref().is_none_or(|(_, most)| fixed > *most) {
+                    iteration_best = Some((board.candidates.clone(), fixed));
+                }
+            }
+
+            let (candidates, fixed) = iteration_best.unwrap();
+            if fixed > best.1 {
+                let reward = board.cells.len() as f64 / (board.cells.len() - fixed).max(1) as f64;
+                best = (candidates, fixed, reward);
+            }
+
+            // Global update along the best fill, then best-value evaporation
+            let rho = self.config.global_evaporation;
+            for (i, cell) in board.cells.iter().enumerate() {
+                if best.0[cell].len() == 1 {
+                    let d = *best.0[cell].iter().next().unwrap();
+                    self.pheromone[i][d - 1] = (1.0 - rho) * self.pheromone[i][d - 1] + rho * best.2;
+                }
+            }
+            best.2 *= 1.0 - self.config.best_evaporation;
+            // The cost of an iteration is the number of cells the best fill leaves open
+            observer.on_iteration(self.stats.iterations, (board.cells.len() - best.1) as i32, None);
+        }
+
+        board.candidates = best.0;
+        for (i, cell) in board.cells.iter().enumerate() {
+            let candidates = &board.candidates[cell];
+            board.board[i / 9][i % 9] = if candidates.len() == 1 { *candidates.iter().next().unwrap() as u8 } else { 0 };
+        }
+        self.stats.propagation(board, propagation);
+        observer.on_finish(&format!("Ant colony solver finished after {} iterations.", self.stats.iterations));
+        if best.1 == board.cells.len() {
+            board.candidates_outcome()
+        } else {
+            SolveOutcome::Stopped(stopped.unwrap_or(Limit::Iterations(self.config.max_iterations)))
+        }
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        format!("Ant Colony (ants={}, q0={})", self.config.ants, self.config.greediness)
+    }
+
+    fn initialize_candidates(&mut self, board: &mut Sudoku) {
+        board.initialize_candidates_heavy();
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+impl Default for AntColonySolver {
+    fn default() -> Self {
+        Self::new(AntColonyConfig::default())
+    }
+}
+
+impl AntColonySolver {
+    pub fn new(config: AntColonyConfig) -> Self {
+        AntColonySolver { config, pheromone: Vec::new(), stats: SolveStats::default() }
+    }
+
+    // Iterations used by the last solve
+    pub fn iterations(&self) -> usize {
+        self.stats.iterations
+    }
+
+    fn fixed(board: &Sudoku) -> usize {
+        board.candidates.values().filter(|candidates| candidates.len() == 1).count()
+    }
+
+    // One ant's walk over the cells, starting at a random one. Sudoku's change log serves as the
+    // trail of what each pick removed, so a pick that fails is undone without copying the
+    // candidates.
+    fn construct(&mut self, board: &mut Sudoku, initial: f64, rng: &mut StdRng) {
+        let changes = board.changes.replace(Vec::new());
+        let first = rng.gen_range(0..board.cells.len());
+        for k in 0..board.cells.len() {
+            let i = (first + k) % board.cells.len();
+            let cell = board.cells[i].clone();
+            if board.candidates[&cell].len() == 1 {
+                continue;
+            }
+
+            let digits: Vec<usize> = board.candidates[&cell].iter().cloned().sorted().collect();
+            let pheromone = &self.pheromone[i];
+            let digit = if rng.gen::<f64>() < self.config.greediness {
+                *digits.iter().max_by(|a, b| pheromone[*a - 1].total_cmp(&pheromone[*b - 1])).unwrap()
+            } else {
+                *digits.choose_weighted(rng, |d| pheromone[d - 1]).unwrap()
+            };
+
+            self.stats.guesses += 1;
+            let assigned = board.assign(&cell, digit);
+            let trail = board.changes.as_mut().unwrap();
+            if !assigned {
+                for (cell, digit, placed) in trail.iter() {
+                    if !placed {
+                        board.candidates.get_mut(cell).unwrap().insert(*digit);
+                    }
+                }
+                self.stats.backtracks += 1;
+            }
+            trail.clear();
+            let xi = self.config.local_evaporation;
+            self.pheromone[i][digit - 1] = (1.0 - xi) * self.pheromone[i][digit - 1] + xi * initial;
+        }
+        board.changes = changes;
+    }
+}
+
+// Bitboard backtracking.
+// The production solver: no HashMaps and no printing. Each row, column and box keeps a mask of
+// the digits it already holds, so a cell's candidates are a couple of bitwise operations away.
+// Naked and hidden singles are propagated before branching on the cell with the fewest
+// candidates (MRV). The board is a small Copy struct, so backtracking just drops a copy and the
+// search does not allocate.
+
+pub struct FastSolver {
+    stats: SolveStats,
+    // Look for a second solution after finding one
+    check_unique: bool,
+}
+
+impl Solver for FastSolver {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        let givens = board.board;
+        match self.search(&mut board.board, budget, observer) {
+            Ok(true) if self.check_unique && Bitboard::new(&givens).is_some_and(|bitboard| bitboard.count(2) > 1) => {
+                SolveOutcome::MultipleSolutions
+            }
+            Ok(true) => SolveOutcome::Solved(board.board),
+            Ok(false) => board.no_solution(),
+            Err(limit) => SolveOutcome::Stopped(limit),
+        }
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        "Fast Solver (bitboard)".to_string()
+    }
+
+    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
+        // unneeded
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+impl Default for FastSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FastSolver {
+    pub fn new() -> Self {
+        FastSolver { stats: SolveStats::default(), check_unique: false }
+    }
+
+    // Report MultipleSolutions instead of Solved when the puzzle has a second solution.
+    // This costs a second search.
+    pub fn with_check_unique(mut self, check_unique: bool) -> Self {
+        self.check_unique = check_unique;
+        self
+    }
+
+    // Count the solutions of the puzzle, up to limit
+    pub fn count_solutions(&mut self, board: &Sudoku, limit: usize) -> usize {
+        match Bitboard::new(&board.board) {
+            Some(bitboard) if limit > 0 => bitboard.count(limit),
+            _ => 0,
+        }
+    }
+
+    // Solve a grid in place (0 for empty cells), without building a Sudoku.
+    // Returns false, leaving the grid untouched, if it has no solution.
+    pub fn solve_grid(&mut self, grid: &mut [[u8; 9]; 9]) -> bool {
+        self.search(grid, &Budget::default(), &mut NoObserver) == Ok(true)
+    }
+
+    // solve_grid within a budget
+    fn search(&mut self, grid: &mut [[u8; 9]; 9], budget: &Budget, observer: &mut dyn SolverObserver) -> Result<bool, Limit> {
+        self.stats = SolveStats::default();
+        let mut bitboard = match Bitboard::new(grid) {
+            Some(bitboard) => bitboard,
+            None => return Ok(false),
+        };
+        if !bitboard.search(&mut self.stats, 0, budget, observer, None)? {
+            return Ok(false);
+        }
+        for (i, &digit) in bitboard.grid.iter().enumerate() {
+            grid[i / 9][i % 9] = digit;
+        }
+        Ok(true)
+    }
+}
+
+// Every solution of a puzzle, lazily: the bitboard search of FastSolver with an explicit stack
+// that is kept between calls to next, so each call resumes where the last solution was found.
+// Solutions come in a fixed order, cells being branched on as in FastSolver and their digits
+// tried from 1 to 9.
+pub struct Solutions {
+    // Boards left to explore, the next one last
+    stack: Vec<Bitboard>,
+}
+
+impl Iterator for Solutions {
+    type Item = Grid;
+
+    fn next(&mut self) -> Option<Grid> {
+        while let Some(mut board) = self.stack.pop() {
+            if !board.propagate() {
+                continue;
+            }
+            let i = match board.branch_cell() {
+                Some(i) => i,
+                None => {
+                    let mut grid = [[0; 9]; 9];
+                    for (i, &digit) in board.grid.iter().enumerate() {
+                        grid[i / 9][i % 9] = digit;
+                    }
+                    return Some(grid);
+                }
+            };
+            // Pushed from 9 down to 1, so the smallest digit is explored first
+            let mut candidates = board.candidates(i);
+            while candidates != 0 {
+                let bit = 1 << (15 - candidates.leading_zeros());
+                candidates &= !bit;
+                let mut next = board;
+                next.place(i, bit);
+                self.stack.push(next);
+            }
+        }
+        None
+    }
+}
+
+// The solutions of the puzzle, none if its givens clash
+pub fn solutions(board: &Sudoku) -> Solutions {
+    Solutions { stack: Bitboard::new(&board.board).into_iter().collect() }
+}
+
+const ALL_DIGITS: u16 = 0x1ff;
+
+// Name of cell i (A1 to I9), built once so that reporting to an observer does not allocate
+fn cell_name(i: usize) -> &'static str {
+    static NAMES: OnceLock<Vec<String>> = OnceLock::new();
+    &NAMES.get_or_init(|| (0..81).map(|i| utils::coords_to_cell(i / 9, i % 9)).collect())[i]
+}
+
+// Cells of the rows, columns and boxes, in that order
+const UNITS: [[usize; 9]; 27] = {
+    let mut units = [[0; 9]; 27];
+    let mut i = 0;
+    while i < 9 {
+        let mut j = 0;
+        while j < 9 {
+            units[i][j] = i * 9 + j;
+            units[9 + i][j] = j * 9 + i;
+            units[18 + i][j] = (i / 3 * 3 + j / 3) * 9 + i % 3 * 3 + j % 3;
+            j += 1;
+        }
+        i += 1;
+    }
+    units
+};
+
+// A board for FastSolver: the digits placed, and the digits used in each row, column and box
+// (bit d - 1 for digit d)
+#[derive(Clone, Copy)]
+struct Bitboard {
+    grid: [u8; 81],
+    rows: [u16; 9],
+    cols: [u16; 9],
+    boxes: [u16; 9],
+    filled: usize,
+}
+
+impl Bitboard {
+    // None if the givens already clash
+    fn new(grid: &[[u8; 9]; 9]) -> Option<Self> {
+        let mut bitboard = Bitboard { grid: [0; 81], rows: [0; 9], cols: [0; 9], boxes: [0; 9], filled: 0 };
+        for (row, digits) in grid.iter().enumerate() {
+            for (col, &digit) in digits.iter().enumerate() {
+                if digit == 0 {
+                    continue;
+                }
+                let i = row * 9 + col;
+                let bit = 1 << (digit - 1);
+                if bitboard.candidates(i) & bit == 0 {
+                    return None;
+                }
+                bitboard.place(i, bit);
+            }
+        }
+        Some(bitboard)
+    }
+
+    fn box_of(i: usize) -> usize {
+        i / 27 * 3 + i % 9 / 3
+    }
+
+    fn candidates(&self, i: usize) -> u16 {
+        if self.grid[i] != 0 {
+            return 0;
+        }
+        !(self.rows[i / 9] | self.cols[i % 9] | self.boxes[Self::box_of(i)]) & ALL_DIGITS
+    }
+
+    fn place(&mut self, i: usize, bit: u16) {
+        self.grid[i] = bit.trailing_zeros() as u8 + 1;
+        self.rows[i / 9] |= bit;
+        self.cols[i % 9] |= bit;
+        self.boxes[Self::box_of(i)] |= bit;
+        self.filled += 1;
+    }
+
+    // Place naked and hidden singles until none are left; false on a contradiction
+    fn propagate(&mut self) -> bool {
+        loop {
+            let filled = self.filled;
+            // Naked singles: a cell with a single candidate
+            for i in 0..81 {
+                if self.grid[i] != 0 {
+                    continue;
+                }
+                let candidates = self.candidates(i);
+                if candidates == 0 {
+                    return false;
+                }
+                if candidates & (candidates - 1) == 0 {
+                    self.place(i, candidates);
+                }
+            }
+            // Hidden singles: in each unit, the digits seen in exactly one cell's candidates
+            for unit in &UNITS {
+                let (mut once, mut twice, mut used) = (0u16, 0u16, 0u16);
+                for &i in unit {
+                    let candidates = self.candidates(i);
+                    twice |= once & candidates;
+                    once |= candidates;
+                    if self.grid[i] != 0 {
+                        used |= 1 << (self.grid[i] - 1);
+                    }
+                }
+                if once | used != ALL_DIGITS {
+                    return false;
+                }
+                let hidden = once & !twice;
+                if hidden == 0 {
+                    continue;
+                }
+                for &i in unit {
+                    let single = self.candidates(i) & hidden;
+                    if single == 0 {
+                        continue;
+                    }
+                    if single & (single - 1) != 0 {
+                        return false;
+                    }
+                    self.place(i, single);
+                }
+            }
+            if self.filled == 81 || self.filled == filled {
+                return true;
+            }
+        }
+    }
+
+    // The empty cell with the fewest candidates, None once the board is full
+    fn branch_cell(&self) -> Option<usize> {
+        let mut best = None;
+        let mut fewest = 10;
+        for i in 0..81 {
+            if self.grid[i] != 0 {
+                continue;
+            }
+            let count = self.candidates(i).count_ones();
+            if count < fewest {
+                best = Some(i);
+                fewest = count;
+                if count == 2 {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    // Depth-first search, leaving the solution in self. depth is the number of branchings above.
+    // Err once the budget runs out. shared counts the nodes of every search spending the same
+    // budget, when there are several (see ParallelSolver); the budget is checked against it.
+    fn search(&mut self, stats: &mut SolveStats, depth: usize, budget: &Budget, observer: &mut dyn SolverObserver, shared: Option<&AtomicUsize>) -> Result<bool, Limit> {
+        let nodes = shared.map_or(stats.nodes, |shared| shared.fetch_add(1, Ordering::Relaxed));
+        if let Some(limit) = budget.check(nodes, 0) {
+            return Err(limit);
+        }
+        stats.nodes += 1;
+        stats.descend(depth);
+        let before = self.grid;
+        let consistent = self.propagate();
+        for (i, (&old, &new)) in before.iter().zip(self.grid.iter()).enumerate() {
+            if old != new {
+                observer.on_assign(cell_name(i), new as usize, false);
+            }
+        }
+        if !consistent {
+            return Ok(false);
+        }
+        let i = match self.branch_cell() {
+            Some(i) => i,
+            None => return Ok(true),
+        };
+        let mut candidates = self.candidates(i);
+        while candidates != 0 {
+            let bit = candidates & candidates.wrapping_neg();
+            candidates &= candidates - 1;
+            let mut next = *self;
+            next.place(i, bit);
+            stats.guesses += 1;
+            let digit = next.grid[i] as usize;
+            observer.on_assign(cell_name(i), digit, true);
+            if next.search(stats, depth + 1, budget, observer, shared)? {
+                *self = next;
+                return Ok(true);
+            }
+            stats.backtracks += 1;
+            observer.on_backtrack(cell_name(i), digit);
+        }
+        Ok(false)
+    }
+
+    // Count the solutions, stopping at limit
+    fn count(mut self, limit: usize) -> usize {
+        if !self.propagate() {
+            return 0;
+        }
+        let i = match self.branch_cell() {
+            Some(i) => i,
+            None => return 1,
+        };
+        let mut count = 0;
+        let mut candidates = self.candidates(i);
+        while candidates != 0 && count < limit {
+            let bit = candidates & candidates.wrapping_neg();
+            candidates &= candidates - 1;
+            let mut next = self;
+            next.place(i, bit);
+            count += next.count(limit - count);
+        }
+        count
+    }
+
+    // The subproblems found by branching breadth-first, depth levels deep.
+    // Dead ends are dropped; boards solved before that depth are kept as they are.
+    fn split(self, depth: usize) -> Vec<Bitboard> {
+        let mut frontier = vec![self];
+        for _ in 0..depth {
+            let mut next = Vec::new();
+            for mut board in frontier {
+                if !board.propagate() {
+                    continue;
+                }
+                let i = match board.branch_cell() {
+                    Some(i) => i,
+                    None => {
+                        next.push(board);
+                        continue;
+                    }
+                };
+                let mut candidates = board.candidates(i);
+                while candidates != 0 {
+                    let bit = candidates & candidates.wrapping_neg();
+                    candidates &= candidates - 1;
+                    let mut child = board;
+                    child.place(i, bit);
+                    next.push(child);
+                }
+            }
+            frontier = next;
+        }
+        frontier
+    }
+}
+
+// Parallel search.
+// The search tree of FastSolver is split at a shallow depth into independent subproblems, which
+// a thread pool then works through: either until one of them yields a solution, or until all of
+// them have been counted.
+
+pub struct ParallelSolver {
+    split_depth: usize,
+    pool: rayon::ThreadPool,
+    // Summed over the subproblems searched; the branching done by the split is not counted
+    stats: SolveStats,
+}
+
+impl Solver for ParallelSolver {
+    // The observer hears from the threads one event at a time, the events of different
+    // subproblems interleaved; the branching done by the split is not reported
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        self.stats = SolveStats::default();
+        let root = match Bitboard::new(&board.board) {
+            Some(root) => root,
+            None => return board.no_solution(),
+        };
+        let subproblems = root.split(self.split_depth);
+        let stats = Mutex::new(SolveStats::default());
+        let observer = Mutex::new(observer);
+        let split_depth = self.split_depth;
+        // The node limit of the budget is for all the threads together
+        let nodes = AtomicUsize::new(0);
+        // The first limit a thread ran into. The other threads carry on until they run into it
+        // too, in case one of them is about to find a solution.
+        let stopped = Mutex::new(None);
+        let solution = self.pool.install(|| {
+            subproblems.into_par_iter().find_map_any(|mut subproblem| {
+                let mut local = SolveStats::default();
+                let result = subproblem.search(&mut local, split_depth, budget, &mut SharedObserver(&observer), Some(&nodes));
+                stats.lock().unwrap().add(&local);
+                match result {
+                    Ok(true) => Some(subproblem),
+                    Ok(false) => None,
+                    Err(limit) => {
+                        stopped.lock().unwrap().get_or_insert(limit);
+                        None
+                    }
+                }
+            })
+        });
+        self.stats = stats.into_inner().unwrap();
+        match (solution, stopped.into_inner().unwrap()) {
+            (Some(solution), _) => {
+                for (i, &digit) in solution.grid.iter().enumerate() {
+                    board.board[i / 9][i % 9] = digit;
+                }
+                SolveOutcome::Solved(board.board)
+            }
+            (None, Some(limit)) => SolveOutcome::Stopped(limit),
+            (None, None) => board.no_solution(),
+        }
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        format!("Parallel Solver (threads={}, split depth={})", self.pool.current_num_threads(), self.split_depth)
+    }
+
+    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
+        // unneeded
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+// Passes the events of a thread on to the observer shared by all of them
+struct SharedObserver<'a, 'o>(&'a Mutex<&'o mut dyn SolverObserver>);
+
+impl SolverObserver for SharedObserver<'_, '_> {
+    fn on_assign(&mut self, cell: &str, digit: usize, guess: bool) {
+        self.0.lock().unwrap().on_assign(cell, digit, guess);
+    }
+
+    fn on_eliminate(&mut self, cell: &str, digit: usize) {
+        self.0.lock().unwrap().on_eliminate(cell, digit);
+    }
+
+    fn on_backtrack(&mut self, cell: &str, digit: usize) {
+        self.0.lock().unwrap().on_backtrack(cell, digit);
+    }
+
+    fn on_technique_applied(&mut self, technique: &str, steps: &[Step]) {
+        self.0.lock().unwrap().on_technique_applied(technique, steps);
+    }
+
+    fn on_iteration(&mut self, iteration: usize, cost: i32, temperature: Option<f64>) {
+        self.0.lock().unwrap().on_iteration(iteration, cost, temperature);
+    }
+
+    fn on_candidates(&mut self, board: &Sudoku) {
+        self.0.lock().unwrap().on_candidates(board);
+    }
+
+    fn on_finish(&mut self, summary: &str) {
+        self.0.lock().unwrap().on_finish(summary);
+    }
+}
+
+impl Default for ParallelSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ParallelSolver {
+    pub fn new() -> Self {
+        ParallelSolver {
+            split_depth: 3,
+            pool: Self::build_pool(0),
+            stats: SolveStats::default(),
+        }
+    }
+
+    // Number of worker threads (0, the default, uses one per CPU).
+    pub fn with_threads(mut self, threads: usize) -> Self {
+        self.pool = Self::build_pool(threads);
+        self
+    }
+
+    // Number of branching levels expanded before the subproblems are handed out (3 by default).
+    pub fn with_split_depth(mut self, split_depth: usize) -> Self {
+        self.split_depth = split_depth;
+        self
+    }
+
+    fn build_pool(threads: usize) -> rayon::ThreadPool {
+        rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .expect("Failed to build the thread pool")
+    }
+
+    // Count the solutions of the puzzle, up to limit, summing the counts of the subproblems.
+    // Each subproblem only counts up to what is left of the limit when it starts.
+    pub fn count_solutions(&mut self, board: &Sudoku, limit: usize) -> usize {
+        let root = match Bitboard::new(&board.board) {
+            Some(root) => root,
+            None => return 0,
+        };
+        let subproblems = root.split(self.split_depth);
+        let total = AtomicUsize::new(0);
+        self.pool.install(|| {
+            subproblems.into_par_iter().for_each(|subproblem| {
+                let remaining = limit.saturating_sub(total.load(Ordering::Relaxed));
+                if remaining > 0 {
+                    total.fetch_add(subproblem.count(remaining), Ordering::Relaxed);
+                }
+            })
+        });
+        total.into_inner().min(limit)
+    }
+}
+
+// SAT solving with conflict-driven clause learning (CDCL).
+// The puzzle is encoded as 729 boolean variables, one per (row, column, digit): each cell holds
+// exactly one digit, each unit holds each digit exactly once, and the givens are unit clauses.
+// https://en.wikipedia.org/wiki/Conflict-driven_clause_learning
+
+pub struct SatSolver {
+    // nodes and guesses count decisions, backtracks conflicts, max_depth the deepest decision level
+    stats: SolveStats,
+}
+
+impl Solver for SatSolver {
+    // Literals are reported as they reach the trail: a true literal as a placement, a false one as
+    // an elimination; literals of the givens are left out
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        let mut cdcl = Self::encode(board);
+        let satisfiable = cdcl.solve(budget, &mut |literal, event| {
+            let variable = literal / 2;
+            let (row, col, digit) = (variable / 81, variable / 9 % 9, variable % 9 + 1);
+            if board.board[row][col] != 0 {
+                return;
+            }
+            let cell = utils::coords_to_cell(row, col);
+            match (event, literal & 1 == 0) {
+                (LiteralEvent::Undone, true) => observer.on_backtrack(&cell, digit),
+                (LiteralEvent::Undone, false) => {}
+                (event, true) => observer.on_assign(&cell, digit, event == LiteralEvent::Decided),
+                (_, false) => observer.on_eliminate(&cell, digit),
+            }
+        });
+        self.record(&cdcl);
+        match satisfiable {
+            Ok(true) => {}
+            Ok(false) => return board.no_solution(),
+            Err(limit) => return SolveOutcome::Stopped(limit),
+        }
+        board.board = Self::decode(&cdcl);
+        observer.on_finish(&format!("SAT solver finished after {} decisions and {} conflicts.", self.decisions(), self.conflicts()));
+        SolveOutcome::Solved(board.board)
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        "SAT Solver (CDCL)".to_string()
+    }
+
+    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
+        // unneeded
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+impl Default for SatSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SatSolver {
+    pub fn new() -> Self {
+        SatSolver { stats: SolveStats::default() }
+    }
+
+    // Decisions and conflicts of the last run
+    pub fn decisions(&self) -> usize {
+        self.stats.guesses
+    }
+
+    pub fn conflicts(&self) -> usize {
+        self.stats.backtracks
+    }
+
+    fn record(&mut self, cdcl: &Cdcl) {
+        self.stats = SolveStats {
+            nodes: cdcl.decisions,
+            guesses: cdcl.decisions,
+            backtracks: cdcl.conflicts,
+            max_depth: cdcl.max_level,
+            restarts: cdcl.restarts,
+            ..SolveStats::default()
+        };
+    }
+
+    // Count the solutions of the puzzle, up to limit: after each solution a blocking clause
+    // forbidding its digits in the empty cells is added, and the search carries on.
+    pub fn count_solutions(&mut self, board: &Sudoku, limit: usize) -> usize {
+        let mut cdcl = Self::encode(board);
+        let mut count = 0;
+        while count < limit && cdcl.solve(&Budget::default(), &mut |_, _| {}) == Ok(true) {
+            count += 1;
+            let blocking: Vec<usize> = (0..81)
+                .filter(|&i| board.board[i / 9][i % 9] == 0)
+                .flat_map(|i| (1..=9).map(move |digit| Self::variable(i / 9, i % 9, digit)))
+                .filter(|&var| cdcl.values[var] == Some(true))
+                .map(|var| Cdcl::literal(var, false))
+                .collect();
+            cdcl.backtrack(0);
+            cdcl.add_clause(blocking);
+        }
+        self.record(&cdcl);
+        count
+    }
+
+    fn variable(row: usize, col: usize, digit: usize) -> usize {
+        row * 81 + col * 9 + digit - 1
+    }
+
+    fn encode(board: &Sudoku) -> Cdcl {
+        let mut cdcl = Cdcl::new(729);
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        for i in 0..9 {
+            for j in 0..9 {
+                // cell (i, j) holds a digit
+                groups.push((1..=9).map(|digit| Self::variable(i, j, digit)).collect());
+                let digit = j + 1;
+                // row i, column i and box i hold the digit
+                groups.push((0..9).map(|col| Self::variable(i, col, digit)).collect());
+                groups.push((0..9).map(|row| Self::variable(row, i, digit)).collect());
+                groups.push((0..9).map(|k| Self::variable(i / 3 * 3 + k / 3, i % 3 * 3 + k % 3, digit)).collect());
+            }
+        }
+        // Exactly one of each group: at least one, and no two together
+        for group in &groups {
+            cdcl.add_clause(group.iter().map(|&var| Cdcl::literal(var, true)).collect());
+            for (k, &a) in group.iter().enumerate() {
+                for &b in &group[k + 1..] {
+                    cdcl.add_clause(vec![Cdcl::literal(a, false), Cdcl::literal(b, false)]);
+                }
+            }
+        }
+        for row in 0..9 {
+            for col in 0..9 {
+                let digit = board.board[row][col] as usize;
+                if digit != 0 {
+                    cdcl.add_clause(vec![Cdcl::literal(Self::variable(row, col, digit), true)]);
+                }
+            }
+        }
+        cdcl
+    }
+
+    fn decode(cdcl: &Cdcl) -> [[u8; 9]; 9] {
+        let mut grid = [[0; 9]; 9];
+        for (row, grid_row) in grid.iter_mut().enumerate() {
+            for (col, entry) in grid_row.iter_mut().enumerate() {
+                if let Some(digit) = (1..=9).find(|&digit| cdcl.values[Self::variable(row, col, digit)] == Some(true)) {
+                    *entry = digit as u8;
+                }
+            }
+        }
+        grid
+    }
+}
+
+// What happened to a literal on the trail of a Cdcl
+#[derive(Clone, Copy, PartialEq)]
+enum LiteralEvent {
+    // Set by propagation, or asserted by a learnt clause
+    Implied,
+    Decided,
+    // A decision undone by backjumping or a restart
+    Undone,
+}
+
+// A CDCL SAT solver: two watched literals, first-UIP clause learning with non-chronological
+// backjumping, VSIDS branching with phase saving, and Luby restarts.
+// A literal is 2 * variable for the positive literal and 2 * variable + 1 for the negative one.
+struct Cdcl {
+    // The first two literals of a clause are the watched ones; a reason clause has its implied literal first
+    clauses: Vec<Vec<usize>>,
+    // Clauses watching each literal
+    watches: Vec<Vec<usize>>,
+    values: Vec<Option<bool>>,
+    levels: Vec<usize>,
+    reasons: Vec<Option<usize>>,
+    trail: Vec<usize>,
+    // Position in the trail where each decision level starts
+    trail_limits: Vec<usize>,
+    // Position in the trail up to which literals have been propagated
+    propagated: usize,
+    activity: Vec<f64>,
+    activity_increment: f64,
+    phases: Vec<bool>,
+    // Set when the clauses are unsatisfiable without any decision
+    unsatisfiable: bool,
+    conflicts: usize,
+    decisions: usize,
+    // Deepest decision level reached, and Luby restarts made
+    max_level: usize,
+    restarts: usize,
+}
+
+impl Cdcl {
+    const ACTIVITY_DECAY: f64 = 0.95;
+    const RESTART_INTERVAL: usize = 100;
+
+    fn new(variables: usize) -> Self {
+        Cdcl {
+            clauses: Vec::new(),
+            watches: vec![Vec::new(); 2 * variables],
+            values: vec![None; variables],
+            levels: vec![0; variables],
+            reasons: vec![None; variables],
+            trail: Vec::new(),
+            trail_limits: Vec::new(),
+            propagated: 0,
+            activity: vec![0.0; variables],
+            activity_increment: 1.0,
+            phases: vec![false; variables],
+            unsatisfiable: false,
+            conflicts: 0,
+            decisions: 0,
+            max_level: 0,
+            restarts: 0,
+        }
+    }
+
+    fn literal(variable: usize, positive: bool) -> usize {
+        2 * variable + if positive { 0 } else { 1 }
+    }
+
+    fn value(&self, literal: usize) -> Option<bool> {
+        self.values[literal / 2].map(|value| value == (literal & 1 == 0))
+    }
+
+    fn level(&self) -> usize {
+        self.trail_limits.len()
+    }
+
+    // Add a clause; only allowed at decision level 0
+    fn add_clause(&mut self, mut literals: Vec<usize>) {
+        if self.unsatisfiable {
+            return;
+        }
+        literals.sort();
+        literals.dedup();
+        if literals.iter().any(|&l| self.value(l) == Some(true) || literals.contains(&(l ^ 1))) {
+            return;
+        }
+        literals.retain(|&l| self.value(l).is_none());
+        match literals.len() {
+            0 => self.unsatisfiable = true,
+            1 => {
+                self.assign(literals[0], None);
+                if self.propagate().is_some() {
+                    self.unsatisfiable = true;
+                }
+            }
+            _ => {
+                self.watch(literals);
+            }
+        }
+    }
+
+    fn watch(&mut self, literals: Vec<usize>) -> usize {
+        let index = self.clauses.len();
+        self.watches[literals[0]].push(index);
+        self.watches[literals[1]].push(index);
+        self.clauses.push(literals);
+        index
+    }
+
+    fn assign(&mut self, literal: usize, reason: Option<usize>) {
+        let variable = literal / 2;
+        self.values[variable] = Some(literal & 1 == 0);
+        self.levels[variable] = self.level();
+        self.reasons[variable] = reason;
+        self.trail.push(literal);
+    }
+
+    // Unit propagation over the watched literals; returns the conflicting clause, if any
+    fn propagate(&mut self) -> Option<usize> {
+        while self.propagated < self.trail.len() {
+            let falsified = self.trail[self.propagated] ^ 1;
+            self.propagated += 1;
+            let watching = std::mem::take(&mut self.watches[falsified]);
+            let mut kept = Vec::with_capacity(watching.len());
+            let mut conflict = None;
+            for (k, &index) in watching.iter().enumerate() {
+                if conflict.is_some() {
+                    kept.extend_from_slice(&watching[k..]);
+                    break;
+                }
+                let clause = &mut self.clauses[index];
+                if clause[0] == falsified {
+                    clause.swap(0, 1);
+                }
+                let first = clause[0];
+                if self.values[first / 2].map(|value| value == (first & 1 == 0)) == Some(true) {
+                    kept.push(index);
+                    continue;
+                }
+                // Look for another literal to watch
+                let replacement = (2..clause.len())
+                    .find(|&j| self.values[clause[j] / 2].map(|value| value == (clause[j] & 1 == 0)) != Some(false));
+                if let Some(j) = replacement {
+                    clause.swap(1, j);
+                    let literal = clause[1];
+                    self.watches[literal].push(index);
+                    continue;
+                }
+                kept.push(index);
+                if self.value(first) == Some(false) {
+                    conflict = Some(index);
+                } else {
+                    self.assign(first, Some(index));
+                }
+            }
+            self.watches[falsified] = kept;
+            if conflict.is_some() {
+                return conflict;
+            }
+        }
+        None
+    }
+
+    // First-UIP conflict analysis: returns the learnt clause, asserting literal first and a literal
+    // of the backjump level second, along with that level
+    fn analyze(&mut self, conflict: usize) -> (Vec<usize>, usize) {
+        let mut seen = vec![false; self.values.len()];
+        let mut learnt = vec![0];
+        let mut pending = 0;
+        let mut clause = conflict;
+        let mut index = self.trail.len();
+        let mut literal = None;
+        loop {
+            let skip = if literal.is_some() { 1 } else { 0 };
+            for k in skip..self.clauses[clause].len() {
+                let other = self.clauses[clause][k];
+                let variable = other / 2;
+                if seen[variable] || self.levels[variable] == 0 {
+                    continue;
+                }
+                seen[variable] = true;
+                self.bump(variable);
+                if self.levels[variable] == self.level() {
+                    pending += 1;
+                } else {
+                    learnt.push(other);
+                }
+            }
+            // The next literal of the current level on the trail that took part in the conflict
+            loop {
+                index -= 1;
+                if seen[self.trail[index] / 2] {
+                    break;
+                }
+            }
+            let next = self.trail[index];
+            seen[next / 2] = false;
+            pending -= 1;
+            literal = Some(next);
+            if pending == 0 {
+                break;
+            }
+            clause = self.reasons[next / 2].unwrap();
+        }
+        learnt[0] = literal.unwrap() ^ 1;
+
+        let mut level = 0;
+        if learnt.len() > 1 {
+            let deepest = (1..learnt.len()).max_by_key(|&k| self.levels[learnt[k] / 2]).unwrap();
+            learnt.swap(1, deepest);
+            level = self.levels[learnt[1] / 2];
+        }
+        (learnt, level)
+    }
+
+    fn bump(&mut self, variable: usize) {
+        self.activity[variable] += self.activity_increment;
+        if self.activity[variable] > 1e100 {
+            for activity in &mut self.activity {
+                *activity *= 1e-100;
+            }
+            self.activity_increment *= 1e-100;
+        }
+    }
+
+    // Undo every assignment above the given decision level, saving the phases
+    fn backtrack(&mut self, level: usize) {
+        if self.level() <= level {
+            return;
+        }
+        let limit = self.trail_limits[level];
+        for &literal in &self.trail[limit..] {
+            let variable = literal / 2;
+            self.phases[variable] = literal & 1 == 0;
+            self.values[variable] = None;
+            self.reasons[variable] = None;
+        }
+        self.trail.truncate(limit);
+        self.trail_limits.truncate(level);
+        self.propagated = limit;
+    }
+
+    // The unassigned variable with the highest activity, in its saved phase
+    fn decide(&self) -> Option<usize> {
+        (0..self.values.len())
+            .filter(|&variable| self.values[variable].is_none())
+            .max_by(|&a, &b| self.activity[a].total_cmp(&self.activity[b]).then(b.cmp(&a)))
+            .map(|variable| Self::literal(variable, self.phases[variable]))
+    }
+
+    // The i-th term (from 1) of the Luby sequence 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, ...
+    fn luby(i: usize) -> usize {
+        let mut k = 1;
+        while (1 << k) - 1 < i {
+            k += 1;
+        }
+        if (1 << k) - 1 == i {
+            1 << (k - 1)
+        } else {
+            Self::luby(i - (1 << (k - 1)) + 1)
+        }
+    }
+
+    // Report the decisions above the given level, deepest first, as undone, then backtrack
+    fn undo(&mut self, level: usize, reported: &mut usize, report: &mut dyn FnMut(usize, LiteralEvent)) {
+        for &limit in self.trail_limits.iter().skip(level).rev() {
+            report(self.trail[limit], LiteralEvent::Undone);
+        }
+        self.backtrack(level);
+        *reported = (*reported).min(self.trail.len());
+    }
+
+    // Search for a satisfying assignment, left in values, reporting the literals that are set and
+    // the decisions that are undone along the way.
+    // Err once the budget runs out, decisions counting as its nodes.
+    fn solve(&mut self, budget: &Budget, report: &mut dyn FnMut(usize, LiteralEvent)) -> Result<bool, Limit> {
+        if self.unsatisfiable {
+            return Ok(false);
+        }
+        let mut restarts = 1;
+        let mut conflicts_since_restart = 0;
+        // Trail entries reported so far
+        let mut reported = 0;
+        loop {
+            let conflict = self.propagate();
+            for &literal in &self.trail[reported..] {
+                report(literal, LiteralEvent::Implied);
+            }
+            reported = self.trail.len();
+            if let Some(conflict) = conflict {
+                self.conflicts += 1;
+                conflicts_since_restart += 1;
+                if self.level() == 0 {
+                    self.unsatisfiable = true;
+                    return Ok(false);
+                }
+                let (learnt, level) = self.analyze(conflict);
+                self.undo(level, &mut reported, report);
+                if learnt.len() == 1 {
+                    self.assign(learnt[0], None);
+                } else {
+                    let asserting = learnt[0];
+                    let index = self.watch(learnt);
+                    self.assign(asserting, Some(index));
+                }
+                self.activity_increment /= Self::ACTIVITY_DECAY;
+            } else if conflicts_since_restart >= Self::luby(restarts) * Self::RESTART_INTERVAL {
+                restarts += 1;
+                self.restarts += 1;
+                conflicts_since_restart = 0;
+                self.undo(0, &mut reported, report);
+            } else {
+                match self.decide() {
+                    None => return Ok(true),
+                    Some(literal) => {
+                        if let Some(limit) = budget.check(self.decisions, 0) {
+                            return Err(limit);
+                        }
+                        self.decisions += 1;
+                        self.trail_limits.push(self.trail.len());
+                        self.max_level = self.max_level.max(self.level());
+                        self.assign(literal, None);
+                        report(literal, LiteralEvent::Decided);
+                        reported = self.trail.len();
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Constraint programming on the generic engine (see csp.rs).
+// The grid is a model of 81 variables, one per cell in row-major order, each over the digits 1 to
+// 9 (a given's variable only over the given), with an AllDifferent constraint on every row,
+// column and box. A variant adds its own constraints to this model, e.g. AllDifferent on the two
+// diagonals for X-Sudoku or Sum on the cages for Killer Sudoku, and is solved by the same engine.
+
+pub struct CspSolver {
+    filtering: AllDifferentFiltering,
+    // backtracks counts failed propagations
+    stats: SolveStats,
+}
+
+impl Solver for CspSolver {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        let model = Self::model(board);
+        let mut engine = Engine::new(FirstFail, self.filtering);
+        let mut monitor = CspMonitor { budget, stopped: None, observer };
+        let solution = engine.solve_monitored(&model, &mut monitor);
+        let stopped = monitor.stopped;
+        self.stats = SolveStats {
+            nodes: engine.nodes(),
+            backtracks: engine.failures(),
+            guesses: engine.guesses(),
+            max_depth: engine.max_depth(),
+            ..SolveStats::default()
+        };
+        let values = match solution {
+            Ok(Some(values)) => values,
+            Ok(None) => return board.no_solution(),
+            Err(_) => return SolveOutcome::Stopped(stopped.unwrap()),
+        };
+        for (i, value) in values.iter().enumerate() {
+            board.board[i / 9][i % 9] = *value as u8;
+        }
+        observer.on_finish(&format!("CSP solver finished after {} nodes and {} failures.", self.nodes(), self.failures()));
+        SolveOutcome::Solved(board.board)
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        format!("CSP Solver ({:?})", self.filtering)
+    }
+
+    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
+        // unneeded
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+impl Default for CspSolver {
+    fn default() -> Self {
+        Self::new(AllDifferentFiltering::Matching)
+    }
+}
+
+impl CspSolver {
+    pub fn new(filtering: AllDifferentFiltering) -> Self {
+        CspSolver { filtering, stats: SolveStats::default() }
+    }
+
+    // Search nodes and failed propagations of the last solve
+    pub fn nodes(&self) -> usize {
+        self.stats.nodes
+    }
+
+    pub fn failures(&self) -> usize {
+        self.stats.backtracks
+    }
+
+    // The puzzle as a model: variable row * 9 + col is the digit of that cell
+    pub fn model(board: &Sudoku) -> Model {
+        let mut model = Model::new();
+        for row in 0..9 {
+            for col in 0..9 {
+                match board.board[row][col] {
+                    0 => model.add_variable(1..=9),
+                    digit => model.add_variable([digit as u32]),
+                };
+            }
+        }
+        for i in 0..9 {
+            model.add_constraint(Constraint::AllDifferent((0..9).map(|col| i * 9 + col).collect()));
+            model.add_constraint(Constraint::AllDifferent((0..9).map(|row| row * 9 + i).collect()));
+            model.add_constraint(Constraint::AllDifferent((0..9).map(|k| (i / 3 * 3 + k / 3) * 9 + i % 3 * 3 + k % 3).collect()));
+        }
+        model
+    }
+}
+
+// Stops the engine once the budget runs out, and reports its search to an observer
+struct CspMonitor<'a> {
+    budget: &'a Budget,
+    stopped: Option<Limit>,
+    observer: &'a mut dyn SolverObserver,
+}
+
+impl CspMonitor<'_> {
+    fn cell(var: usize) -> String {
+        utils::coords_to_cell(var / 9, var % 9)
+    }
+}
+
+impl Monitor for CspMonitor<'_> {
+    fn interrupt(&mut self, nodes: usize) -> bool {
+        self.stopped = self.budget.check(nodes, 0);
+        self.stopped.is_some()
+    }
+
+    fn on_choice(&mut self, var: usize, value: u32) {
+        self.observer.on_assign(&Self::cell(var), value as usize, true);
+    }
+
+    fn on_propagate(&mut self, var: usize, before: Domain, after: Domain) {
+        let cell = Self::cell(var);
+        for value in before.iter().filter(|&value| !after.contains(value)) {
+            self.observer.on_eliminate(&cell, value as usize);
+        }
+        if let Some(value) = after.value() {
+            self.observer.on_assign(&cell, value as usize, false);
+        }
+    }
+
+    fn on_failure(&mut self, var: usize, value: u32) {
+        self.observer.on_backtrack(&Self::cell(var), value as usize);
+    }
+}
+
+// Integer linear programming.
+// The puzzle is a 0/1 program over 729 variables, one per (row, column, digit) as for the SAT
+// solver: each of the 324 groups (a cell's digits, a unit's cells for one digit) sums to exactly 1.
+// Since every variable lies in such a group, x <= 1 needs no row of its own. Fixing a variable to
+// 1 satisfies its four groups and fixes every variable sharing one with it to 0, so each node of
+// the branch-and-bound solves the LP relaxation of what is left with the simplex method (phase
+// one only: any feasible vertex will do), and branches on a fractional variable, 1 before 0.
+// https://en.wikipedia.org/wiki/Branch_and_bound
+
+// Entries of the simplex tableau smaller than this are rounding noise
+const ILP_EPSILON: f64 = 1e-9;
+// Smallest entry the simplex pivots on
+const ILP_PIVOT_TOLERANCE: f64 = 1e-5;
+// How far a value may be from 0 or 1, or the artificial variables from 0, and still count as such
+const ILP_TOLERANCE: f64 = 1e-6;
+
+pub struct IlpSolver {
+    // guesses counts the branches taken, backtracks the branches to 1 that proved infeasible
+    stats: SolveStats,
+    pivots: usize,
+}
+
+impl Solver for IlpSolver {
+    // A branch to 1 is reported as a guess and a branch to 0 as an elimination; the placements
+    // of an integral relaxation as deductions
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        self.stats = SolveStats::default();
+        self.pivots = 0;
+        let groups = Self::groups();
+        let mut fixed = vec![None; 729];
+        for row in 0..9 {
+            for col in 0..9 {
+                let digit = board.board[row][col] as usize;
+                if digit != 0 && !Self::fix(&groups, &mut fixed, Self::variable(row, col, digit)) {
+                    return board.no_solution();
+                }
+            }
+        }
+        let fixed = match self.branch(&groups, fixed, 0, budget, observer) {
+            Ok(Some(fixed)) => fixed,
+            Ok(None) => return board.no_solution(),
+            Err(limit) => return SolveOutcome::Stopped(limit),
+        };
+        for (var, value) in fixed.iter().enumerate() {
+            if *value == Some(true) {
+                board.board[var / 81][var / 9 % 9] = (var % 9 + 1) as u8;
+            }
+        }
+        observer.on_finish(&format!("ILP solver finished after {} nodes and {} pivots, LP relaxation {}integral.",
+            self.stats.nodes, self.pivots, if self.relaxation_integral() { "" } else { "not " }));
+        SolveOutcome::Solved(board.board)
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        "ILP Solver (simplex branch-and-bound)".to_string()
+    }
+
+    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
+        // unneeded
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+impl Default for IlpSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IlpSolver {
+    pub fn new() -> Self {
+        IlpSolver { stats: SolveStats::default(), pivots: 0 }
+    }
+
+    // Branch-and-bound nodes and simplex pivots of the last solve
+    pub fn nodes(&self) -> usize {
+        self.stats.nodes
+    }
+
+    pub fn pivots(&self) -> usize {
+        self.pivots
+    }
+
+    // Whether the LP relaxation at the root of the last solve was already integral
+    pub fn relaxation_integral(&self) -> bool {
+        self.stats.relaxation_integral == Some(true)
+    }
+
+    fn variable(row: usize, col: usize, digit: usize) -> usize {
+        row * 81 + col * 9 + digit - 1
+    }
+
+    // The 324 groups of variables summing to 1
+    fn groups() -> Vec<Vec<usize>> {
+        let mut groups = Vec::new();
+        for i in 0..9 {
+            for j in 0..9 {
+                let digit = j + 1;
+                groups.push((1..=9).map(|digit| Self::variable(i, j, digit)).collect());
+                groups.push((0..9).map(|col| Self::variable(i, col, digit)).collect());
+                groups.push((0..9).map(|row| Self::variable(row, i, digit)).collect());
+                groups.push((0..9).map(|k| Self::variable(i / 3 * 3 + k / 3, i % 3 * 3 + k % 3, digit)).collect());
+            }
+        }
+        groups
+    }
+
+    // Fix a variable to 1 and the variables sharing a group with it to 0, false if it was fixed to 0
+    fn fix(groups: &[Vec<usize>], fixed: &mut [Option<bool>], var: usize) -> bool {
+        if fixed[var] == Some(false) {
+            return false;
+        }
+        for group in groups.iter().filter(|group| group.contains(&var)) {
+            for &other in group {
+                if other != var {
+                    fixed[other] = Some(false);
+                }
+            }
+        }
+        fixed[var] = Some(true);
+        true
+    }
+
+    // Solve the relaxation of the variables not fixed yet and branch on a fractional one.
+    // depth is the number of branches above. Err once the budget runs out.
+    fn branch(&mut self, groups: &[Vec<usize>], fixed: Vec<Option<bool>>, depth: usize, budget: &Budget, observer: &mut dyn SolverObserver) -> Result<Option<Vec<Option<bool>>>, Limit> {
+        if let Some(limit) = budget.exhausted(self.stats.nodes, 0) {
+            return Err(limit);
+        }
+        self.stats.nodes += 1;
+        self.stats.descend(depth);
+        // Groups not satisfied yet, over the variables not fixed yet
+        let columns: Vec<usize> = (0..729).filter(|&var| fixed[var].is_none()).collect();
+        let mut rows = Vec::new();
+        for group in groups {
+            if group.iter().any(|&var| fixed[var] == Some(true)) {
+                continue;
+            }
+            let row: Vec<usize> = columns.iter().enumerate()
+                .filter(|(_, var)| group.contains(var))
+                .map(|(k, _)| k)
+                .collect();
+            if row.is_empty() {
+                return Ok(None);
+            }
+            rows.push(row);
+        }
+
+        let Some(values) = self.relaxation(&rows, columns.len()) else {
+            return Ok(None);
+        };
+        let fractional = values.iter().enumerate()
+            .filter(|(_, value)| **value > ILP_TOLERANCE && **value < 1.0 - ILP_TOLERANCE)
+            .max_by(|a, b| a.1.total_cmp(b.1))
+            .map(|(k, _)| columns[k]);
+        if self.stats.nodes == 1 {
+            self.stats.relaxation_integral = Some(fractional.is_none());
+        }
+
+        let Some(var) = fractional else {
+            let mut fixed = fixed;
+            for (k, value) in values.iter().enumerate() {
+                fixed[columns[k]] = Some(*value > 0.5);
+                if *value > 0.5 {
+                    let (cell, digit) = Self::cell_digit(columns[k]);
+                    observer.on_assign(&cell, digit, false);
+                }
+            }
+            return Ok(Some(fixed));
+        };
+        let (cell, digit) = Self::cell_digit(var);
+        let mut one = fixed.clone();
+        Self::fix(groups, &mut one, var);
+        self.stats.guesses += 1;
+        observer.on_assign(&cell, digit, true);
+        if let Some(solution) = self.branch(groups, one, depth + 1, budget, observer)? {
+            return Ok(Some(solution));
+        }
+        self.stats.backtracks += 1;
+        observer.on_backtrack(&cell, digit);
+        let mut zero = fixed;
+        zero[var] = Some(false);
+        self.stats.guesses += 1;
+        observer.on_eliminate(&cell, digit);
+        self.branch(groups, zero, depth + 1, budget, observer)
+    }
+
+    // The cell and digit of a variable
+    fn cell_digit(var: usize) -> (String, usize) {
+        (utils::coords_to_cell(var / 81, var / 9 % 9), var % 9 + 1)
+    }
+
+    // The values of the columns at a vertex where the variables of each row sum to 1, None if
+    // there is none
+    fn relaxation(&mut self, rows: &[Vec<usize>], columns: usize) -> Option<Vec<f64>> {
+        let mut simplex = Simplex::new(rows, columns);
+        let feasible = simplex.phase_one(rows);
+        self.pivots += simplex.pivots;
+        if !feasible {
+            return None;
+        }
+        let mut values = vec![0.0; columns];
+        for (i, &var) in simplex.basis.iter().enumerate() {
+            if var < columns {
+                values[var] = simplex.tableau[i][simplex.width - 1];
+            }
+        }
+        Some(values)
+    }
+}
+
+// Phase one of the tableau simplex method for: the variables of each row sum to 1, x >= 0.
+// One artificial variable per row makes the first basis; minimising their sum reaches a vertex of
+// the problem if it has any. Pricing is Dantzig's largest coefficient rule, switching to Bland's
+// rule (which cannot cycle) after a long run of degenerate pivots. Rounding errors build up from
+// pivot to pivot, so every so often the tableau is computed again from the rows and the basis.
+struct Simplex {
+    // One row per constraint: the columns, then the artificial variables, then the basic value
+    tableau: Vec<Vec<f64>>,
+    // Reduced costs of the phase one objective, then minus its value
+    cost: Vec<f64>,
+    // The basic variable of each row
+    basis: Vec<usize>,
+    columns: usize,
+    width: usize,
+    pivots: usize,
+}
+
+impl Simplex {
+    // Pivots between two computations of the tableau from scratch
+    const REINVERSION_INTERVAL: usize = 100;
+    // Degenerate pivots in a row before pricing switches to Bland's rule
+    const DEGENERATE_LIMIT: usize = 50;
+
+    fn new(rows: &[Vec<usize>], columns: usize) -> Self {
+        let m = rows.len();
+        let width = columns + m + 1;
+        let mut simplex = Simplex {
+            tableau: vec![vec![0.0; width]; m],
+            cost: vec![0.0; width],
+            basis: (columns..columns + m).collect(),
+            columns,
+            width,
+            pivots: 0,
+        };
+        simplex.reset(rows);
+        simplex
+    }
+
+    // The tableau of the artificial basis
+    fn reset(&mut self, rows: &[Vec<usize>]) {
+        let (columns, width) = (self.columns, self.width);
+        self.cost = vec![0.0; width];
+        for (i, row) in rows.iter().enumerate() {
+            self.tableau[i] = vec![0.0; width];
+            for &k in row {
+                self.tableau[i][k] = 1.0;
+                self.cost[k] -= 1.0;
+            }
+            self.tableau[i][columns + i] = 1.0;
+            self.tableau[i][width - 1] = 1.0;
+        }
+        self.cost[width - 1] = -(rows.len() as f64);
+    }
+
+    // Compute the tableau of the current basis again, from the artificial one: pivot each basic
+    // variable in, on the row where its entry is largest (partial pivoting)
+    fn reinvert(&mut self, rows: &[Vec<usize>]) {
+        let basis = std::mem::take(&mut self.basis);
+        self.reset(rows);
+        self.basis = (self.columns..self.columns + rows.len()).collect();
+        let mut placed = vec![false; rows.len()];
+        for var in basis {
+            let row = (0..rows.len())
+                .filter(|&i| !placed[i])
+                .max_by(|&a, &b| self.tableau[a][var].abs().total_cmp(&self.tableau[b][var].abs()))
+                .unwrap();
+            placed[row] = true;
+            if self.basis[row] != var {
+                self.pivot(row, var);
+            }
+        }
+    }
+
+    fn pivot(&mut self, leaving: usize, entering: usize) {
+        let pivot = self.tableau[leaving][entering];
+        for value in self.tableau[leaving].iter_mut() {
+            *value /= pivot;
+        }
+        let pivot_row = self.tableau[leaving].clone();
+        for (i, row) in self.tableau.iter_mut().enumerate() {
+            let factor = row[entering];
+            if i == leaving || factor == 0.0 {
+                continue;
+            }
+            for (value, p) in row.iter_mut().zip(&pivot_row) {
+                *value -= factor * p;
+                if value.abs() < ILP_EPSILON {
+                    *value = 0.0;
+                }
+            }
+        }
+        let factor = self.cost[entering];
+        for (value, p) in self.cost.iter_mut().zip(&pivot_row) {
+            *value -= factor * p;
+        }
+        self.basis[leaving] = entering;
+    }
+
+    // Drive the artificial variables to 0, false if they cannot all get there
+    fn phase_one(&mut self, rows: &[Vec<usize>]) -> bool {
+        let width = self.width;
+        let mut degenerate = 0;
+        while self.cost[width - 1] < -ILP_EPSILON {
+            let cost = &self.cost;
+            let entering = if degenerate < Self::DEGENERATE_LIMIT {
+                (0..width - 1).filter(|&j| cost[j] < -ILP_EPSILON).min_by(|&a, &b| cost[a].total_cmp(&cost[b]))
+            } else {
+                (0..width - 1).find(|&j| cost[j] < -ILP_EPSILON)
+            };
+            let Some(entering) = entering else {
+                break;
+            };
+            // Ratio test, ties broken by the smallest basic variable
+            let tableau = &self.tableau;
+            let ratio = |i: usize| tableau[i][width - 1] / tableau[i][entering];
+            let eligible: Vec<usize> = (0..tableau.len()).filter(|&i| tableau[i][entering] > ILP_PIVOT_TOLERANCE).collect();
+            let Some(smallest) = eligible.iter().map(|&i| ratio(i)).min_by(f64::total_cmp) else {
+                break;
+            };
+            let leaving = eligible.into_iter()
+                .filter(|&i| ratio(i) <= smallest + ILP_EPSILON)
+                .min_by_key(|&i| self.basis[i])
+                .unwrap();
+            if tableau[leaving][width - 1] < ILP_EPSILON {
+                degenerate += 1;
+            } else {
+                degenerate = 0;
+            }
+
+            self.pivot(leaving, entering);
+            // Basic values cannot be negative, anything below 0 is rounding error
+            for row in self.tableau.iter_mut() {
+                row[width - 1] = row[width - 1].max(0.0);
+            }
+            self.pivots += 1;
+            if self.pivots.is_multiple_of(Self::REINVERSION_INTERVAL) {
+                self.reinvert(rows);
+            }
+        }
+        self.reinvert(rows);
+        -self.cost[width - 1] <= ILP_TOLERANCE
+    }
+}
+
+
+
+// Knuth's Algorithm X, with dancing links.
+// The puzzle is an exact cover problem: each of the 729 rows places a digit in a cell, and covers
+// four of the 324 columns (the cell is filled, and the digit is in its row, its column and its
+// box). Every column must be covered exactly once. The links are indices into one list of nodes
+// instead of pointers, so a node can be unlinked and linked back without fighting the borrow
+// checker. The search always branches on the column with the fewest rows left.
+
+pub struct DlxSolver {
+    stats: SolveStats,
+    // Look for a second solution after finding one
+    check_unique: bool,
+}
+
+impl Solver for DlxSolver {
+    fn solve_observed(&mut self, board: &mut Sudoku, budget: &Budget, observer: &mut dyn SolverObserver) -> SolveOutcome {
+        self.stats = SolveStats::default();
+        let mut links = match DancingLinks::new(&board.board) {
+            Some(links) => links,
+            None => return board.no_solution(),
+        };
+        let mut rows = Vec::new();
+        match links.search(&mut rows, &mut self.stats, 0, budget, observer) {
+            Ok(true) => {}
+            Ok(false) => return board.no_solution(),
+            Err(limit) => return SolveOutcome::Stopped(limit),
+        }
+        let givens = board.board;
+        for row in rows {
+            board.board[row / 81][row / 9 % 9] = (row % 9 + 1) as u8;
+        }
+        if self.check_unique && DancingLinks::new(&givens).is_some_and(|mut links| links.count(2) > 1) {
+            return SolveOutcome::MultipleSolutions;
+        }
+        SolveOutcome::Solved(board.board)
+    }
+
+    fn stats(&self) -> &SolveStats {
+        &self.stats
+    }
+
+    fn name(&self) -> String {
+        "Dancing Links Solver".to_string()
+    }
+
+    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
+        // unneeded
+    }
+
+    fn is_correct(&self, board: &mut Sudoku) -> bool {
+        board.board_correct()
+    }
+}
+
+impl Default for DlxSolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DlxSolver {
+    pub fn new() -> Self {
+        DlxSolver { stats: SolveStats::default(), check_unique: false }
+    }
+
+    // Report MultipleSolutions instead of Solved when the puzzle has a second solution.
+    // This costs a second search.
+    pub fn with_check_unique(mut self, check_unique: bool) -> Self {
+        self.check_unique = check_unique;
+        self
+    }
+
+    // Count the solutions of the puzzle, up to limit
+    pub fn count_solutions(&mut self, board: &Sudoku, limit: usize) -> usize {
+        match DancingLinks::new(&board.board) {
+            Some(mut links) if limit > 0 => links.count(limit),
+            _ => 0,
+        }
+    }
+}
+
+// The exact cover matrix of DlxSolver. Node 0 is the root, nodes 1 to 324 head the columns, and
+// the four nodes of each row follow. Row r places digit r % 9 + 1 in cell r / 9.
+struct DancingLinks {
+    left: Vec<usize>,
+    right: Vec<usize>,
+    up: Vec<usize>,
+    down: Vec<usize>,
+    // The column header of each node, and the row of each node below the headers
+    column: Vec<usize>,
+    row: Vec<usize>,
+    // Rows left in each column, by header
+    size: Vec<usize>,
+}
+
+impl DancingLinks {
+    const COLUMNS: usize = 324;
+
+    // The matrix with the rows of the givens chosen. None if the givens already clash.
+    fn new(grid: &[[u8; 9]; 9]) -> Option<Self> {
+        let nodes = 1 + Self::COLUMNS + 729 * 4;
+        let mut links = DancingLinks {
+            left: Vec::with_capacity(nodes),
+            right: Vec::with_capacity(nodes),
+            up: Vec::with_capacity(nodes),
+            down: Vec::with_capacity(nodes),
+            column: Vec::with_capacity(nodes),
+            row: Vec::with_capacity(nodes),
+            size: vec![0; 1 + Self::COLUMNS],
+        };
+        for header in 0..=Self::COLUMNS {
+            links.left.push(if header == 0 { Self::COLUMNS } else { header - 1 });
+            links.right.push(if header == Self::COLUMNS { 0 } else { header + 1 });
+            links.up.push(header);
+            links.down.push(header);
+            links.column.push(header);
+            links.row.push(0);
+        }
+        for row in 0..729 {
+            let (cell, digit) = (row / 9, row % 9);
+            let box_ = cell / 27 * 3 + cell % 9 / 3;
+            let columns = [cell, 81 + cell / 9 * 9 + digit, 162 + cell % 9 * 9 + digit, 243 + box_ * 9 + digit];
+            let first = links.column.len();
+            for (k, &column) in columns.iter().enumerate() {
+                let (node, header) = (first + k, column + 1);
+                links.left.push(if k == 0 { first + 3 } else { node - 1 });
+                links.right.push(if k == 3 { first } else { node + 1 });
+                links.up.push(links.up[header]);
+                links.down.push(header);
+                let last = links.up[header];
+                links.down[last] = node;
+                links.up[header] = node;
+                links.column.push(header);
+                links.row.push(row);
+                links.size[header] += 1;
+            }
+        }
+
+        // Choose the rows of the givens; one of their columns is gone if it was covered before
+        let mut covered = [false; 1 + Self::COLUMNS];
+        for (i, &digit) in grid.iter().flatten().enumerate() {
+            if digit == 0 {
+                continue;
+            }
+            let node = 1 + Self::COLUMNS + (i * 9 + digit as usize - 1) * 4;
+            for k in 0..4 {
+                let header = links.column[node + k];
+                if covered[header] {
+                    return None;
+                }
+                covered[header] = true;
+                links.cover(header);
+            }
+        }
+        Some(links)
+    }
+
+    // Take a column out of the header list, and every row in it out of the other columns
+    fn cover(&mut self, header: usize) {
+        self.right[self.left[header]] = self.right[header];
+        self.left[self.right[header]] = self.left[header];
+        let mut i = self.down[header];
+        while i != header {
+            let mut j = self.right[i];
+            while j != i {
+                self.down[self.up[j]] = self.down[j];
+                self.up[self.down[j]] = self.up[j];
+                self.size[self.column[j]] -= 1;
+                j = self.right[j];
+            }
+            i = self.down[i];
+        }
+    }
+
+    // Undo cover, in the reverse order
+    fn uncover(&mut self, header: usize) {
+        let mut i = self.up[header];
+        while i != header {
+            let mut j = self.left[i];
+            while j != i {
+                self.size[self.column[j]] += 1;
+                self.down[self.up[j]] = j;
+                self.up[self.down[j]] = j;
+                j = self.left[j];
+            }
+            i = self.up[i];
+        }
+        self.right[self.left[header]] = header;
+        self.left[self.right[header]] = header;
+    }
+
+    // The uncovered column with the fewest rows, None once every column is covered
+    fn branch_column(&self) -> Option<usize> {
+        let mut best = None;
+        let mut header = self.right[0];
+        while header != 0 {
+            if best.is_none_or(|best| self.size[header] < self.size[best]) {
+                best = Some(header);
+            }
+            header = self.right[header];
+        }
+        best
+    }
+
+    // Choose the row of node: cover the columns of its other nodes (its own is covered already)
+    fn choose(&mut self, node: usize) {
+        let mut j = self.right[node];
+        while j != node {
+            self.cover(self.column[j]);
+            j = self.right[j];
+        }
+    }
+
+    fn unchoose(&mut self, node: usize) {
+        let mut j = self.left[node];
+        while j != node {
+            self.uncover(self.column[j]);
+            j = self.left[j];
+        }
+    }
+
+    // Depth-first search, pushing the rows of the solution onto rows. depth is the number of rows
+    // chosen above. A column left with a single row forces it, which is not counted as a guess.
+    // Err once the budget runs out.
+    fn search(&mut self, rows: &mut Vec<usize>, stats: &mut SolveStats, depth: usize, budget: &Budget, observer: &mut dyn SolverObserver) -> Result<bool, Limit> {
+        if let Some(limit) = budget.check(stats.nodes, 0) {
+            return Err(limit);
+        }
+        stats.nodes += 1;
+        stats.descend(depth);
+        let header = match self.branch_column() {
+            Some(header) => header,
+            None => return Ok(true),
+        };
+        let guess = self.size[header] > 1;
+        self.cover(header);
+        let mut node = self.down[header];
+        while node != header {
+            let (cell, digit) = (cell_name(self.row[node] / 9), self.row[node] % 9 + 1);
+            if guess {
+                stats.guesses += 1;
+            }
+            observer.on_assign(cell, digit, guess);
+            self.choose(node);
+            rows.push(self.row[node]);
+            if self.search(rows, stats, depth + 1, budget, observer)? {
+                return Ok(true);
+            }
+            rows.pop();
+            self.unchoose(node);
+            if guess {
+                stats.backtracks += 1;
+                observer.on_backtrack(cell, digit);
+            }
+            node = self.down[node];
+        }
+        self.uncover(header);
+        Ok(false)
+    }
+
+    // Count the exact covers, stopping at limit
+    fn count(&mut self, limit: usize) -> usize {
+        let header = match self.branch_column() {
+            Some(header) => header,
+            None => return 1,
+        };
+        let mut count = 0;
+        self.cover(header);
+        let mut node = self.down[header];
+        while node != header && count < limit {
+            self.choose(node);
+            count += self.count(limit - count);
+            self.unchoose(node);
+            node = self.down[node];
+        }
+        self.uncover(header);
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The first puzzles of data/easy.txt and data/hard.txt, each with a single solution
+    const EASY: &str = ".94...13..............76..2.8..1.....32.........2...6.....5.4.......8..7..63.4..8";
+    const HARD: &str = "52...6.........7.13...........4..8..6......5...........418.........3..2...87.....";
+    // Two 5s in row A
+    const CLASHING: &str = "55...............................................................................";
+    const EMPTY: &str = ".................................................................................";
+
+    fn board(puzzle: &str) -> Sudoku {
+        Sudoku::new(Some(puzzle)).unwrap()
+    }
+
+    // Whether grid is complete and valid, and keeps the givens of puzzle
+    fn solves(puzzle: &str, grid: &Grid) -> bool {
+        let mut sudoku = board(puzzle);
+        let keeps_givens = (0..81).all(|i| {
+            let (given, digit) = (sudoku.board[i / 9][i % 9], grid[i / 9][i % 9]);
+            digit != 0 && (given == 0 || given == digit)
+        });
+        sudoku.board = *grid;
+        keeps_givens && sudoku.board_correct()
+    }
+
+    #[test]
+    fn sat_solver_solves_puzzles() {
+        for puzzle in [EASY, HARD] {
+            let outcome = SatSolver::new().solve(&mut board(puzzle), &Budget::new());
+            let SolveOutcome::Solved(grid) = outcome else {
+                panic!("{}: {}", puzzle, outcome);
+            };
+            assert!(solves(puzzle, &grid), "{}", puzzle);
+        }
+        let outcome = SatSolver::new().solve(&mut board(CLASHING), &Budget::new());
+        assert!(matches!(outcome, SolveOutcome::Unsolvable(_)), "{}", outcome);
+    }
+
+    #[test]
+    fn sat_solver_counts_solutions_up_to_limit() {
+        let mut solver = SatSolver::new();
+        assert_eq!(solver.count_solutions(&board(CLASHING), 2), 0);
+        assert_eq!(solver.count_solutions(&board(EASY), 2), 1);
+        assert_eq!(solver.count_solutions(&board(HARD), 2), 1);
+        assert_eq!(solver.count_solutions(&board(EMPTY), 5), 5);
+    }
+
+    #[test]
+    fn ilp_solver_solves_puzzles_and_records_the_relaxation() {
+        for puzzle in [EASY, HARD] {
+            let mut solver = IlpSolver::new();
+            let outcome = solver.solve(&mut board(puzzle), &Budget::new());
+            let SolveOutcome::Solved(grid) = outcome else {
+                panic!("{}: {}", puzzle, outcome);
+            };
+            assert!(solves(puzzle, &grid), "{}", puzzle);
+            // An integral relaxation at the root needs no branching
+            assert_eq!(solver.stats().relaxation_integral, Some(solver.nodes() == 1), "{}", puzzle);
+        }
+
+        let mut solver = IlpSolver::new();
+        let outcome = solver.solve(&mut board(CLASHING), &Budget::new());
+        assert!(matches!(outcome, SolveOutcome::Unsolvable(_)), "{}", outcome);
+        assert_eq!(solver.stats().relaxation_integral, None);
+    }
+
+    #[test]
+    fn rule_based_solver_reports_a_contradiction_as_unsolvable() {
+        // A8 and A9 both need the 9 once B8 takes the 8 out of the box
+        let puzzle = format!("{}{}{}", "1234567..", "......8..", ".".repeat(63));
+        let mut board = board(&puzzle);
+        RuleBasedSolver::new().initialize_candidates(&mut board);
+        let outcome = RuleBasedSolver::new().solve(&mut board, &Budget::new());
+        assert!(matches!(outcome, SolveOutcome::Unsolvable(_)), "{}", outcome);
+    }
+
+    #[test]
+    fn exact_solvers_report_multiple_solutions_when_asked() {
+        let mut solver = FastSolver::new().with_check_unique(true);
+        assert_eq!(solver.solve(&mut board(EMPTY), &Budget::new()), SolveOutcome::MultipleSolutions);
+        assert!(solver.solve(&mut board(EASY), &Budget::new()).is_solved());
+        assert!(FastSolver::new().solve(&mut board(EMPTY), &Budget::new()).is_solved());
+
+        let mut solver = BruteForceSolver::new().with_check_unique(true);
+        let mut empty = board(EMPTY);
+        solver.initialize_candidates(&mut empty);
+        assert_eq!(solver.solve(&mut empty, &Budget::new()), SolveOutcome::MultipleSolutions);
+        let mut easy = board(EASY);
+        solver.initialize_candidates(&mut easy);
+        assert!(solver.solve(&mut easy, &Budget::new()).is_solved());
+    }
+
+    #[test]
+    fn exact_solvers_count_solutions_up_to_the_limit() {
+        type Count = fn(&Sudoku, usize) -> usize;
+        let counts: [(&str, Count); 3] = [
+            ("brute force", |board, limit| BruteForceSolver::new().count_solutions(board, limit)),
+            ("fast", |board, limit| FastSolver::new().count_solutions(board, limit)),
+            ("dancing links", |board, limit| DlxSolver::new().count_solutions(board, limit)),
+        ];
+        for (name, count) in counts {
+            assert_eq!(count(&board(CLASHING), 2), 0, "{}", name);
+            assert_eq!(count(&board(EASY), 2), 1, "{}", name);
+            assert_eq!(count(&board(EMPTY), 2), 2, "{}", name);
+            assert_eq!(count(&board(EMPTY), 0), 0, "{}", name);
+        }
+        // Brute force takes close to a minute on the hard puzzle in a debug build
+        assert_eq!(FastSolver::new().count_solutions(&board(HARD), 2), 1);
+        assert_eq!(DlxSolver::new().count_solutions(&board(HARD), 2), 1);
+    }
+
+    #[test]
+    fn dlx_solver_solves_puzzles() {
+        for puzzle in [EASY, HARD, EMPTY] {
+            let outcome = DlxSolver::new().solve(&mut board(puzzle), &Budget::new());
+            let SolveOutcome::Solved(grid) = outcome else {
+                panic!("{}: {}", puzzle, outcome);
+            };
+            assert!(solves(puzzle, &grid), "{}", puzzle);
+        }
+        let outcome = DlxSolver::new().solve(&mut board(CLASHING), &Budget::new());
+        assert!(matches!(outcome, SolveOutcome::Unsolvable(_)), "{}", outcome);
+        let outcome = DlxSolver::new().with_check_unique(true).solve(&mut board(EMPTY), &Budget::new());
+        assert_eq!(outcome, SolveOutcome::MultipleSolutions);
+    }
+
+    #[test]
+    fn parallel_solver_shares_the_node_limit_between_threads() {
+        // HARD splits into four subproblems at depth 3, taking 17, 41, 27 and 51 nodes, the
+        // solution being in the last one
+        let limit = 40;
+        let mut solver = ParallelSolver::new().with_threads(4).with_split_depth(3);
+        let outcome = solver.solve(&mut board(HARD), &Budget::new().with_node_limit(limit));
+        assert_eq!(outcome, SolveOutcome::Stopped(Limit::Nodes(limit)));
+        assert!(solver.stats().nodes <= limit, "{} nodes", solver.stats().nodes);
+
+        let outcome = solver.solve(&mut board(HARD), &Budget::new().with_node_limit(1_000_000));
+        let SolveOutcome::Solved(grid) = outcome else {
+            panic!("{}", outcome);
+        };
+        assert!(solves(HARD, &grid));
+    }
+
+    // Counts the candidate grids reported and keeps the summaries
+    #[derive(Default)]
+    struct Summaries {
+        candidates: usize,
+        summaries: Vec<String>,
+    }
+
+    impl SolverObserver for Summaries {
+        fn on_candidates(&mut self, _board: &Sudoku) {
+            self.candidates += 1;
+        }
+
+        fn on_finish(&mut self, summary: &str) {
+            self.summaries.push(summary.to_string());
+        }
+    }
+
+    #[test]
+    fn solvers_report_candidates_and_summary_to_the_observer() {
+        let solvers: Vec<(Box<dyn Solver>, &str)> = vec![
+            (Box::new(BruteForceSolver::new()), "Brute force solver finished"),
+            (Box::new(RuleBasedSolver::new()), "Rule-based solver finished"),
+        ];
+        for (mut solver, summary) in solvers {
+            let mut board = board(EASY);
+            solver.initialize_candidates(&mut board);
+            let mut observer = Summaries::default();
+            assert!(solver.solve_observed(&mut board, &Budget::new(), &mut observer).is_solved());
+            assert!(observer.candidates >= 1, "{}", solver.name());
+            assert_eq!(observer.summaries.len(), 1, "{}", solver.name());
+            assert!(observer.summaries[0].starts_with(summary), "{}", observer.summaries[0]);
+        }
+    }
+
+    #[test]
+    fn solve_trace_round_trips_through_json() {
+        let mut board = board(EASY);
+        let mut solver = RuleBasedSolver::new();
+        solver.initialize_candidates(&mut board);
+        let (outcome, trace) = solver.solve_traced(&mut board, &Budget::new());
+        assert!(outcome.is_solved(), "{}", outcome);
+        assert!(!trace.steps.is_empty());
+        assert_eq!(SolveTrace::from_json(&trace.to_json()), Ok(trace));
+        assert!(SolveTrace::from_json("{").is_err());
+    }
+
+    #[test]
+    fn solve_trace_replays_to_the_solution_and_undoes_guesses() {
+        let mut board = board(EASY);
+        let mut solver = BruteForceSolver::new();
+        solver.initialize_candidates(&mut board);
+        let (outcome, trace) = solver.solve_traced(&mut board, &Budget::new());
+        let SolveOutcome::Solved(grid) = outcome else {
+            panic!("{}", outcome);
+        };
+        assert_eq!(trace.state(trace.steps.len()).unwrap().board, grid);
+
+        // Backtracking from a guess brings back the board and candidates from before it
+        let backtrack = trace.steps.iter().position(|step| step.action == TraceAction::Backtrack).unwrap();
+        let undone = &trace.steps[backtrack];
+        let guess = trace.steps[..backtrack].iter()
+            .rposition(|step| step.action == TraceAction::Guess && step.cell == undone.cell && step.digit == undone.digit)
+            .unwrap();
+        let (before, after) = (trace.state(guess).unwrap(), trace.state(backtrack + 1).unwrap());
+        assert_eq!(before.board, after.board);
+        assert_eq!(before.candidates, after.candidates);
+        assert_ne!(trace.state(backtrack).unwrap().board, after.board);
+    }
+
+    #[test]
+    fn hint_finds_the_nishio_step_of_a_solve() {
+        // Puzzle 12 of data/hard.txt
+        let puzzle = "6..3.2....5.....1..........7.26............543.........8.15........4.2........7..";
+        let mut board = board(puzzle);
+        let mut solver = RuleBasedSolver::new();
+        solver.initialize_candidates(&mut board);
+        let (outcome, trace) = solver.solve_traced(&mut board, &Budget::new());
+        assert!(outcome.is_solved(), "{}", outcome);
+        let nishio = trace.steps.iter().position(|step| step.technique == "Nishio").unwrap();
+
+        let hint = hint(&trace.state(nishio).unwrap()).unwrap();
+        assert_eq!(hint.technique, "Nishio");
+        let step = &trace.steps[nishio];
+        assert_eq!(hint.eliminations, vec![(step.cell.clone(), step.digit)]);
+    }
+
+    // An empty board with every candidate open and propagation off, so that what a rule
+    // eliminates from the pattern it is given is left to see
+    fn open_board() -> Sudoku {
+        let mut board = board(EMPTY);
+        board.initialize_candidates_lw();
+        board.shallow = true;
+        board
+    }
+
+    // Take digit out of every cell but the ones kept
+    fn keep(board: &mut Sudoku, digit: usize, kept: impl Fn(&str) -> bool) {
+        for cell in board.cells.clone() {
+            if !kept(&cell) {
+                board.candidates.get_mut(&cell).unwrap().remove(&digit);
+            }
+        }
+    }
+
+    // The candidates removed from before to after, in board order
+    fn removed(before: &Sudoku, after: &Sudoku) -> Vec<(String, usize)> {
+        RuleBasedSolver::difference("", before, after).eliminations
+    }
+
+    fn pairs(expected: &[(&str, usize)]) -> Vec<(String, usize)> {
+        expected.iter().map(|&(cell, digit)| (cell.to_string(), digit)).collect()
+    }
+
+    type Rule = fn(&mut RuleBasedSolver, &mut Sudoku) -> Result<bool, Contradiction>;
+
+    // Run rule on a copy of board and return what it eliminated
+    fn eliminated_by(rule: Rule, board: &Sudoku) -> Vec<(String, usize)> {
+        let mut solver = RuleBasedSolver::new();
+        solver.update_cells_with_candidates(board);
+        let mut after = board.clone();
+        assert!(rule(&mut solver, &mut after).is_ok());
+        removed(board, &after)
+    }
+
+    fn single_digit_rules() -> [(&'static str, Rule); 4] {
+        [
+            ("Skyscraper", |solver, board| solver.skyscraper(board)),
+            ("2-String Kite", |solver, board| solver.two_string_kite(board)),
+            ("Turbot Fish", |solver, board| solver.turbot_fish(board)),
+            ("Empty Rectangle", |solver, board| solver.empty_rectangle(board)),
+        ]
+    }
+
+    // Check that the named single-digit rules eliminate expected from board and the others nothing
+    fn assert_single_digit(board: &Sudoku, applies: &[&str], expected: &[(&str, usize)]) {
+        for (technique, rule) in single_digit_rules() {
+            let expected = if applies.contains(&technique) { pairs(expected) } else { Vec::new() };
+            assert_eq!(eliminated_by(rule, board), expected, "{}", technique);
+        }
+    }
+
+    // Strong links on 1 in rows A and E, with the base ends A1 and E1 in one column
+    fn skyscraper_board() -> Sudoku {
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !"AE".contains(&cell[..1]) || ["A1", "A5", "E1", "E4"].contains(&cell));
+        board
+    }
+
+    #[test]
+    fn skyscraper_eliminates_from_the_cells_that_see_both_roofs() {
+        // Turbot Fish covers every Skyscraper
+        assert_single_digit(&skyscraper_board(), &["Skyscraper", "Turbot Fish"], &[("B4", 1), ("C4", 1), ("D5", 1), ("F5", 1)]);
+    }
+
+    #[test]
+    fn two_string_kite_eliminates_where_the_kite_ends_cross() {
+        // 1 is in A2 and A7 in row A and in B1 and H1 in column 1, with A2 and B1 in one box
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| (&cell[..1] != "A" && &cell[1..] != "1") || ["A2", "A7", "B1", "H1"].contains(&cell));
+        assert_single_digit(&board, &["2-String Kite", "Turbot Fish"], &[("H7", 1)]);
+    }
+
+    #[test]
+    fn turbot_fish_eliminates_through_box_links() {
+        // 1 is only in A2 and C1 in box 1 and in A9 and C8 in box 3; row B has it in box 2 only
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !"ABC".contains(&cell[..1]) || "456".contains(&cell[1..]) || ["A2", "C1", "A9", "C8"].contains(&cell));
+        assert_single_digit(&board, &["Turbot Fish"], &[("A4", 1), ("A5", 1), ("A6", 1), ("C4", 1), ("C5", 1), ("C6", 1)]);
+    }
+
+    #[test]
+    fn empty_rectangle_eliminates_where_the_link_meets_the_hinge() {
+        // In box 1, 1 is only on row A and column 1, and column 5 has it only in A5 and F5
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| {
+            let in_pattern = ("ABC".contains(&cell[..1]) && "123".contains(&cell[1..])) || &cell[1..] == "5";
+            !in_pattern || ["A2", "A3", "B1", "C1", "A5", "F5"].contains(&cell)
+        });
+        assert_single_digit(&board, &["Empty Rectangle"], &[("F1", 1)]);
+    }
+
+    #[test]
+    fn single_digit_rules_wait_for_the_easier_rules_to_stall() {
+        // A naked single to place besides the skyscraper
+        let mut board = skyscraper_board();
+        board.candidates.insert("I9".to_string(), HashSet::from([9]));
+        let mut solver = RuleBasedSolver::new();
+        for round in 0..2 {
+            solver.update_cells_with_candidates(&board);
+            let start = board.candidates.clone();
+            assert_eq!(solver.apply_rules(&mut board, &start, &mut NoObserver), Ok(true));
+            let techniques = &solver.stats().techniques;
+            assert!(techniques.contains_key("Naked Single"), "round {}", round);
+            assert_eq!(techniques.contains_key("Skyscraper"), round == 1, "round {}", round);
+        }
+    }
+
+    // Give each of the cells only the given candidates
+    fn set(board: &mut Sudoku, cells: &[(&str, &[usize])]) {
+        for &(cell, digits) in cells {
+            board.candidates.insert(cell.to_string(), digits.iter().cloned().collect());
+        }
+    }
+
+    // Run a rule that records steps on board and return the steps
+    fn steps_of(rule: Rule, board: &mut Sudoku) -> Vec<Step> {
+        let mut solver = RuleBasedSolver::new();
+        solver.update_cells_with_candidates(board);
+        assert!(matches!(rule(&mut solver, board), Ok(true)));
+        solver.steps().to_vec()
+    }
+
+    #[test]
+    fn xy_wing_eliminates_z_from_what_sees_both_pincers() {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A5", &[1, 3]), ("C2", &[2, 3])]);
+        let start = board.clone();
+        let steps = steps_of(|solver, board| solver.xy_wing(board), &mut board);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].cells, ["A1", "A5", "C2"]);
+        let expected = pairs(&[("A2", 3), ("A3", 3), ("C4", 3), ("C5", 3), ("C6", 3)]);
+        assert_eq!(steps[0].eliminations, expected);
+        assert_eq!(removed(&start, &board), expected);
+    }
+
+    #[test]
+    fn xyz_wing_eliminates_z_from_what_sees_all_three_cells() {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2, 3]), ("A5", &[1, 3]), ("B2", &[2, 3])]);
+        let start = board.clone();
+        let steps = steps_of(|solver, board| solver.xyz_wing(board), &mut board);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].cells, ["A1", "A5", "B2"]);
+        let expected = pairs(&[("A2", 3), ("A3", 3)]);
+        assert_eq!(steps[0].eliminations, expected);
+        assert_eq!(removed(&start, &board), expected);
+    }
+
+    #[test]
+    fn w_wing_eliminates_y_from_what_sees_both_wings() {
+        // A1 and E4 hold {1,2}, and row G has 1 only in G1, which sees A1, and G4, which sees E4
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("E4", &[1, 2])]);
+        keep(&mut board, 1, |cell| &cell[..1] != "G" || ["G1", "G4"].contains(&cell));
+        let start = board.clone();
+        let steps = steps_of(|solver, board| solver.w_wing(board), &mut board);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].cells, ["A1", "E4", "G1", "G4"]);
+        let expected = pairs(&[("A4", 2), ("E1", 2)]);
+        assert_eq!(steps[0].eliminations, expected);
+        assert_eq!(removed(&start, &board), expected);
+    }
+
+    // A1, A2 and D1 hold {1,2} and D2 {1,2,3}, with 1 and 2 already gone from the rest of row A,
+    // column 1 and box 1, as the naked pairs there leave them: only a Unique Rectangle applies
+    fn unique_rectangle_board() -> Sudoku {
+        let mut board = open_board();
+        for digit in [1, 2] {
+            keep(&mut board, digit, |cell| {
+                let (row, col) = utils::cell_to_coords(cell);
+                !(row == 0 || col == 0 || (row < 3 && col < 3)) || ["A1", "A2", "D1", "D2"].contains(&cell)
+            });
+        }
+        set(&mut board, &[("A1", &[1, 2]), ("A2", &[1, 2]), ("D1", &[1, 2]), ("D2", &[1, 2, 3])]);
+        board
+    }
+
+    #[test]
+    fn uniqueness_rules_only_run_when_assumed() {
+        for assume_unique in [false, true] {
+            let mut board = unique_rectangle_board();
+            let mut solver = RuleBasedSolver::new().with_assume_unique(assume_unique);
+            solver.update_cells_with_candidates(&board);
+            let start = board.candidates.clone();
+            assert_eq!(solver.apply_rules(&mut board, &start, &mut NoObserver), Ok(assume_unique));
+            let techniques: Vec<&String> = solver.stats().techniques.keys().collect();
+            assert_eq!(techniques, if assume_unique { vec!["Unique Rectangle"] } else { vec![] });
+            let digits: HashSet<usize> = if assume_unique { HashSet::from([3]) } else { HashSet::from([1, 2, 3]) };
+            assert_eq!(board.candidates["D2"], digits);
+        }
+    }
+
+    // Run the Unique Rectangle rule on board and check that it records one step of technique that
+    // eliminates expected
+    fn assert_unique_rectangle(board: &mut Sudoku, technique: &str, expected: &[(&str, usize)]) {
+        let start = board.clone();
+        let steps = steps_of(|solver, board| solver.unique_rectangles(board), board);
+        let techniques: Vec<&str> = steps.iter().map(|step| step.technique.as_str()).collect();
+        assert_eq!(techniques, [technique]);
+        assert_eq!(steps[0].cells, ["A1", "A2", "D1", "D2"]);
+        assert_eq!(removed(&start, board), pairs(expected), "{}", technique);
+    }
+
+    #[test]
+    fn unique_rectangle_type_1_clears_the_pair_from_the_fourth_corner() {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A2", &[1, 2]), ("D1", &[1, 2]), ("D2", &[1, 2, 3])]);
+        assert_unique_rectangle(&mut board, "Unique Rectangle Type 1", &[("D2", 1), ("D2", 2)]);
+    }
+
+    #[test]
+    fn unique_rectangle_type_2_clears_the_extra_digit_from_what_sees_the_roof() {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A2", &[1, 2]), ("D1", &[1, 2, 3]), ("D2", &[1, 2, 3])]);
+        let expected: Vec<(&str, usize)> = ["D3", "D4", "D5", "D6", "D7", "D8", "D9", "E1", "E2", "E3", "F1", "F2", "F3"]
+            .into_iter()
+            .map(|cell| (cell, 3))
+            .collect();
+        assert_unique_rectangle(&mut board, "Unique Rectangle Type 2", &expected);
+    }
+
+    #[test]
+    fn unique_rectangle_type_3_makes_a_naked_pair_of_the_roof_and_another_cell() {
+        // The roof holds 3 or 4, and so does D5: the rest of row D holds neither
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A2", &[1, 2]), ("D1", &[1, 2, 3]), ("D2", &[1, 2, 4]), ("D5", &[3, 4])]);
+        let expected: Vec<(&str, usize)> = ["D3", "D4", "D6", "D7", "D8", "D9"]
+            .into_iter()
+            .flat_map(|cell| [(cell, 3), (cell, 4)])
+            .collect();
+        assert_unique_rectangle(&mut board, "Unique Rectangle Type 3", &expected);
+    }
+
+    #[test]
+    fn unique_rectangle_type_4_clears_the_other_digit_from_the_roof() {
+        // Row D has 1 only in the roof, so the roof cannot also hold 2
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !cell.starts_with('D') || ["D1", "D2"].contains(&cell));
+        set(&mut board, &[("A1", &[1, 2]), ("A2", &[1, 2]), ("D1", &[1, 2, 3, 4]), ("D2", &[1, 2, 3, 4])]);
+        assert_unique_rectangle(&mut board, "Unique Rectangle Type 4", &[("D1", 2), ("D2", 2)]);
+    }
+
+    #[test]
+    fn unique_rectangle_type_5_clears_the_extra_digit_from_what_sees_the_diagonal_roof() {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A2", &[1, 2, 3]), ("D1", &[1, 2, 3]), ("D2", &[1, 2])]);
+        assert_unique_rectangle(&mut board, "Unique Rectangle Type 5", &[("B1", 3), ("C1", 3), ("E2", 3), ("F2", 3)]);
+    }
+
+    #[test]
+    fn unique_rectangle_type_6_clears_a_digit_confined_to_the_rows_from_the_roof() {
+        // Rows A and D have 1 only in the rectangle
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !"AD".contains(&cell[..1]) || ["A1", "A2", "D1", "D2"].contains(&cell));
+        set(&mut board, &[("A1", &[1, 2]), ("A2", &[1, 2, 3]), ("D1", &[1, 2, 4]), ("D2", &[1, 2])]);
+        assert_unique_rectangle(&mut board, "Unique Rectangle Type 6", &[("A2", 1), ("D1", 1)]);
+    }
+
+    #[test]
+    fn hidden_unique_rectangle_clears_the_other_digit_from_the_opposite_corner() {
+        // A1 holds {1,2}, and the row and column of D2 have 1 only in the rectangle
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !(cell.starts_with('D') || cell.ends_with('2')) || ["A2", "D1", "D2"].contains(&cell));
+        set(&mut board, &[("A1", &[1, 2])]);
+        let start = board.clone();
+        let steps = steps_of(|solver, board| solver.hidden_unique_rectangles(board), &mut board);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].cells, ["A1", "A2", "D1", "D2"]);
+        assert_eq!(removed(&start, &board), pairs(&[("D2", 2)]));
+    }
+
+    #[test]
+    fn bug_plus_one_places_the_digit_that_appears_three_times() {
+        // All cells solved but A1 {a,b,c}, A2 {a,b} and A3 {a,c}, a, b and c being their digits in
+        // the solution: a is in three cells of row A
+        let SolveOutcome::Solved(grid) = FastSolver::new().solve(&mut board(EASY), &Budget::new()) else {
+            panic!("no solution");
+        };
+        let mut board = open_board();
+        for cell in board.cells.clone() {
+            let (row, col) = utils::cell_to_coords(&cell);
+            board.candidates.insert(cell, HashSet::from([grid[row][col] as usize]));
+        }
+        let [a, b, c] = [0, 1, 2].map(|col| grid[0][col] as usize);
+        set(&mut board, &[("A1", &[a, b, c]), ("A2", &[a, b]), ("A3", &[a, c])]);
+        let steps = steps_of(|solver, board| solver.bug_plus_one(board), &mut board);
+        assert_eq!(steps.len(), 1);
+        assert_eq!(steps[0].placements, pairs(&[("A1", a)]));
+        assert_eq!(board.candidates["A1"], HashSet::from([a]));
+    }
+
+    // Run a rule that records steps on board, and check that it records exactly one step, which
+    // eliminates expected
+    fn only_step(rule: Rule, board: &mut Sudoku, expected: &[(&str, usize)]) -> Step {
+        let start = board.clone();
+        let steps = steps_of(rule, board);
+        assert_eq!(steps.len(), 1, "{:?}", steps.iter().map(|step| step.to_string()).collect::<Vec<_>>());
+        assert_eq!(removed(&start, board), pairs(expected));
+        steps[0].clone()
+    }
+
+    fn colored(coloring: &[(&str, usize, usize)]) -> Vec<(String, usize, usize)> {
+        coloring.iter().map(|&(cell, digit, color)| (cell.to_string(), digit, color)).collect()
+    }
+
+    #[test]
+    fn simple_coloring_traps_cells_that_see_both_colours() {
+        // Strong links on 1 from A1 down column 1 to G1, along row G to G7 and up column 7 to C7
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !(cell.ends_with('1') || cell.ends_with('7') || cell.starts_with('G')) || ["A1", "G1", "G7", "C7"].contains(&cell));
+        let step = only_step(|solver, board| solver.simple_coloring(board), &mut board, &[("A8", 1), ("A9", 1), ("C2", 1), ("C3", 1)]);
+        assert_eq!(step.description, "color trap on 1");
+        assert_eq!(step.coloring, colored(&[("A1", 1, 0), ("C7", 1, 1), ("G1", 1, 1), ("G7", 1, 0)]));
+    }
+
+    #[test]
+    fn simple_coloring_removes_a_colour_that_sees_itself() {
+        // Strong links on 1 from A1 along row A to A5, down column 5 to E5, along row E to E3 and up
+        // column 3 to B3, which shares box 1 with A1
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !(cell.starts_with('A') || cell.starts_with('E') || cell.ends_with('5') || cell.ends_with('3')) || ["A1", "A5", "E5", "E3", "B3"].contains(&cell));
+        let step = only_step(|solver, board| solver.simple_coloring(board), &mut board, &[("A1", 1), ("B3", 1), ("E5", 1)]);
+        assert_eq!(step.description, "color wrap on 1, colour 0 sees itself");
+        assert_eq!(step.coloring, colored(&[("A1", 1, 0), ("A5", 1, 1), ("B3", 1, 0), ("E3", 1, 1), ("E5", 1, 0)]));
+    }
+
+    #[test]
+    fn multi_coloring_eliminates_where_the_other_colours_meet() {
+        // Strong links on 1 between A1 and A5 in row A and between B4 and H4 in column 4. A5 sees B4,
+        // so A1 or H4 holds 1 and H1, which sees both, does not
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !(cell.starts_with('A') || cell.ends_with('4')) || ["A1", "A5", "B4", "H4"].contains(&cell));
+        let step = only_step(|solver, board| solver.multi_coloring(board), &mut board, &[("H1", 1)]);
+        assert_eq!(step.coloring, colored(&[("A1", 1, 0), ("A5", 1, 1), ("B4", 1, 2), ("H4", 1, 3)]));
+    }
+
+    #[test]
+    fn medusa_clears_a_cell_holding_both_colours() {
+        // A1 holds {1,2}, and column 1 has 1 and 2 only in A1 and E1: E1 holds 1 in one colour and 2 in
+        // the other, so it holds nothing else
+        let mut board = open_board();
+        for digit in [1, 2] {
+            keep(&mut board, digit, |cell| !cell.ends_with('1') || ["A1", "E1"].contains(&cell));
+        }
+        set(&mut board, &[("A1", &[1, 2])]);
+        let expected: Vec<(&str, usize)> = (3..=9).map(|digit| ("E1", digit)).collect();
+        let step = only_step(|solver, board| solver.medusa(board), &mut board, &expected);
+        assert_eq!(step.description, "color trap");
+        assert_eq!(step.coloring, colored(&[("A1", 1, 0), ("A1", 2, 1), ("E1", 1, 1), ("E1", 2, 0)]));
+    }
+
+    // Strong links on 1 down column 1 from A1 to G1 and up column 7 from G7 to C7, with G1 and G7
+    // weakly linked in row G
+    fn x_chain_board() -> Sudoku {
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !(cell.ends_with('1') || cell.ends_with('7')) || ["A1", "G1", "G7", "C7"].contains(&cell));
+        board
+    }
+
+    #[test]
+    fn x_chain_eliminates_from_what_sees_both_ends() {
+        let mut board = x_chain_board();
+        let step = only_step(|solver, board| solver.chain(board, ChainKind::XChain), &mut board, &[("A8", 1), ("A9", 1), ("C2", 1), ("C3", 1)]);
+        assert_eq!(step.technique, "X-Chain");
+        assert_eq!(step.description, "(1)A1=(1)G1-(1)G7=(1)C7");
+    }
+
+    #[test]
+    fn chains_stop_at_the_maximum_length() {
+        let mut board = x_chain_board();
+        let start = board.clone();
+        let mut solver = RuleBasedSolver::new().with_max_chain_length(2);
+        assert!(matches!(solver.chain(&mut board, ChainKind::XChain), Ok(false)));
+        assert!(removed(&start, &board).is_empty());
+
+        let mut solver = RuleBasedSolver::new().with_max_chain_length(3);
+        assert!(matches!(solver.chain(&mut board, ChainKind::XChain), Ok(true)));
+    }
+
+    #[test]
+    fn xy_chain_eliminates_from_what_sees_both_ends() {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A5", &[2, 3]), ("E5", &[3, 4]), ("E9", &[1, 4])]);
+        let step = only_step(|solver, board| solver.chain(board, ChainKind::XYChain), &mut board, &[("A9", 1), ("E1", 1)]);
+        assert_eq!(step.technique, "XY-Chain");
+        assert_eq!(step.description, "(1)A1=(2)A1-(2)A5=(3)A5-(3)E5=(4)E5-(4)E9=(1)E9");
+    }
+
+    #[test]
+    fn grouped_aic_links_through_a_group_of_cells() {
+        // Row A has 1 in A1, A2 and A7, so A7 or one of A1|A2 holds it; column 3 has 1 only in C3 and H3
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !(cell.starts_with('A') || cell.ends_with('3')) || ["A1", "A2", "A7", "C3", "H3"].contains(&cell));
+        let step = only_step(|solver, board| solver.chain(board, ChainKind::Aic), &mut board, &[("H7", 1)]);
+        assert_eq!(step.technique, "Grouped AIC");
+        assert_eq!(step.description, "(1)A7=(1)A1|A2-(1)C3=(1)H3");
+    }
+
+    #[test]
+    fn continuous_nice_loop_eliminates_along_its_weak_links() {
+        // An X-Wing: rows A and E have 1 only in columns 1 and 5
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| !"AE".contains(&cell[..1]) || ["A1", "A5", "E1", "E5"].contains(&cell));
+        let expected: Vec<(&str, usize)> = ["B1", "B5", "C1", "C5", "D1", "D5", "F1", "F5", "G1", "G5", "H1", "H5", "I1", "I5"]
+            .into_iter()
+            .map(|cell| (cell, 1))
+            .collect();
+        let step = only_step(|solver, board| solver.chain(board, ChainKind::XChain), &mut board, &expected);
+        assert_eq!(step.technique, "Continuous Nice Loop");
+        assert_eq!(step.description, "(1)A1=(1)A5-(1)E5=(1)E1");
+    }
+
+    #[test]
+    fn discontinuous_nice_loop_places_its_start() {
+        // Without 1 in A1, row A puts it in A5 and box 1 in B3, and then row E has no place for it
+        let mut board = open_board();
+        keep(&mut board, 1, |cell| {
+            let in_units = "AE".contains(&cell[..1]) || ("ABC".contains(&cell[..1]) && "123".contains(&cell[1..]));
+            !in_units || ["A1", "A5", "B3", "E3", "E5"].contains(&cell)
+        });
+        let expected: Vec<(&str, usize)> = (2..=9).map(|digit| ("A1", digit)).collect();
+        let step = only_step(|solver, board| solver.chain(board, ChainKind::XChain), &mut board, &expected);
+        assert_eq!(step.technique, "Discontinuous Nice Loop");
+        assert_eq!(step.placements, pairs(&[("A1", 1)]));
+        assert_eq!(step.description, "(1)A1 is true: (1)A1=(1)A5-(1)E5=(1)E3-(1)B3=(1)A1");
+    }
+
+    #[test]
+    fn als_xz_eliminates_the_common_digit_from_what_sees_it_in_both_sets() {
+        // A1 {1,2} and A5,B5 {1,2,3} share the restricted common 1, so one of them holds 2
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A5", &[1, 3]), ("B5", &[2, 3])]);
+        let expected = [("A4", 2), ("A6", 2), ("B1", 2), ("B2", 2), ("B3", 2)];
+        let step = only_step(|solver, board| solver.als_xz(board), &mut board, &expected);
+        assert_eq!(step.technique, "ALS-XZ");
+        assert_eq!(step.description, "A=A1 {1,2}, B=A5,B5 {1,2,3}, RCC 1");
+    }
+
+    #[test]
+    fn doubly_linked_als_xz_locks_both_sets() {
+        // A1 {1,2} and A5,A6 {1,2,3} share the restricted commons 1 and 2: together they hold 1, 2
+        // and 3 for row A, and A5,A6 hold 3 for box 2
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A5", &[1, 3]), ("A6", &[2, 3])]);
+        let mut expected: Vec<(&str, usize)> = ["A2", "A3", "A4", "A7", "A8", "A9"]
+            .into_iter()
+            .flat_map(|cell| [(cell, 1), (cell, 2), (cell, 3)])
+            .collect();
+        expected.extend(["B4", "B5", "B6", "C4", "C5", "C6"].map(|cell| (cell, 3)));
+        expected.sort();
+        let step = only_step(|solver, board| solver.als_xz(board), &mut board, &expected);
+        assert_eq!(step.technique, "ALS-XZ (doubly linked)");
+        assert_eq!(step.description, "A=A1 {1,2}, B=A5,A6 {1,2,3}, RCC 1,2");
+    }
+
+    #[test]
+    fn sue_de_coq_splits_the_intersection_between_line_and_box() {
+        // A1,A2 hold {1,2,3,4}, with A5 {1,2} in the rest of row A and B2 {3,4} in the rest of box 1.
+        // A3, in both, loses all four
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2, 3, 4]), ("A2", &[1, 2, 3, 4]), ("A5", &[1, 2]), ("B2", &[3, 4])]);
+        let mut expected: Vec<(&str, usize)> = ["A3", "A4", "A6", "A7", "A8", "A9"]
+            .into_iter()
+            .flat_map(|cell| [(cell, 1), (cell, 2)])
+            .collect();
+        expected.extend(["A3", "B1", "B3", "C1", "C2", "C3"].into_iter().flat_map(|cell| [(cell, 3), (cell, 4)]));
+        expected.sort();
+        let step = only_step(|solver, board| solver.sue_de_coq(board), &mut board, &expected);
+        assert_eq!(step.cells, ["A1", "A2", "A5", "B2"]);
+        assert_eq!(step.description, "intersection A1,A2, line A5, box B2");
+    }
 
-                if candidate_cells.is_empty() {
-                    continue;
-                }
+    // A1 {1,2}, A5 {1,3} and E1 {2,3}: whichever of A1's digits is placed, A5 or E1 takes 3, and
+    // E5 sees both
+    fn forcing_chain_board() -> Sudoku {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A5", &[1, 3]), ("E1", &[2, 3])]);
+        board
+    }
 
-                let rows: HashSet<_> = candidate_cells.iter().map(|cell| cell.chars().next().unwrap()).collect();
-                let cols: HashSet<_> = candidate_cells.iter().map(|cell| cell.chars().nth(1).unwrap()).collect();
+    #[test]
+    fn cell_forcing_chain_proves_what_every_candidate_of_a_cell_implies() {
+        let mut board = forcing_chain_board();
+        let step = only_step(|solver, board| solver.cell_forcing_chains(board), &mut board, &[("E5", 3)]);
+        assert_eq!(step.cells, ["A1"]);
+        assert_eq!(step.proof, [
+            "A1=1 -> A5<>1 -> A5=3 -> E5<>3",
+            "A1=2 -> E1<>2 -> E1=3 -> E5<>3",
+        ]);
+    }
 
-                if rows.len() == 1 {
-                    let row = rows.into_iter().next().unwrap();
-                    for cell in unit {
-                        if board.candidates[cell].len() == 1 {
-                            continue;
-                        }
-                        if cell.starts_with(row) && !candidate_cells.contains(&cell) && board.candidates[cell].contains(&digit) {
-                            board.eliminate(cell, digit);
-                            found = true;
-                        }
-                    }
-                } else if cols.len() == 1 {
-                    let col = cols.into_iter().next().unwrap();
-                    for cell in unit {
-                        if board.candidates[cell].len() == 1 {
-                            continue;
-                        }
-                        if cell.ends_with(col) && !candidate_cells.contains(&cell) && board.candidates[cell].contains(&digit) {
-                            if !board.eliminate(cell, digit) {
-                                panic!("Contradiction encountered during locked candidates type 1");
-                            }
-                            else {found = true;}
-                        }
-                    }
-                }
-            }
-        }
+    #[test]
+    fn unit_forcing_chain_proves_what_every_place_of_a_digit_implies() {
+        // Row A has 1 only in A1 and A5
+        let mut board = forcing_chain_board();
+        keep(&mut board, 1, |cell| !cell.starts_with('A') || ["A1", "A5"].contains(&cell));
+        let step = only_step(|solver, board| solver.unit_forcing_chains(board), &mut board, &[("E5", 3)]);
+        assert_eq!(step.cells, ["A1", "A5"]);
+        assert_eq!(step.proof, [
+            "A1=1 -> A5<>1 -> A5=3 -> E5<>3",
+            "A5=1 -> A1<>1 -> A1=2 -> E1<>2 -> E1=3 -> E5<>3",
+        ]);
     }
-    found
-}
 
-// Function to implement the Locked Candidates Type 2 rule
-fn locked_candidates_type_2(&self, board: &mut Sudoku) -> bool {
-    let mut found = false;
-    // For each cell on the board that has more than one candidate
-    for cell in &self.cells_with_candidates {
-        let mut row_inclusive = board.row_peers[cell].clone();
-        let mut col_inclusive = board.col_peers[cell].clone();
-        row_inclusive.insert(cell.to_string());
-        col_inclusive.insert(cell.to_string());
-        // For each cell, consider the row and column peers 
-        for unit in vec![row_inclusive, col_inclusive] {
-            // Check for each digit from 1 to 9
-            for digit in 1..=9 {
-                // Find the cells in the current unit (row or column) that contain the digit as a candidate
-                let candidate_cells: Vec<_> = unit.iter()
-                    .filter(|&cell| board.candidates[cell].contains(&digit))
-                    .collect();
+    #[test]
+    fn digit_forcing_chain_proves_what_a_candidate_implies_either_way() {
+        let mut board = forcing_chain_board();
+        let step = only_step(|solver, board| solver.digit_forcing_chains(board), &mut board, &[("E5", 3)]);
+        assert_eq!(step.description, "whether A1 is 1 or not");
+        assert_eq!(step.proof, [
+            "A1=1 -> A5<>1 -> A5=3 -> E5<>3",
+            "A1<>1 -> A1=2 -> E1<>2 -> E1=3 -> E5<>3",
+        ]);
+    }
 
-                // If there are no such cells, move on to the next digit
-                if candidate_cells.is_empty() {
-                    continue;
-                }
+    #[test]
+    fn forcing_chains_propagate_no_deeper_than_the_maximum() {
+        // E5<>3 lies three implications from the premise A1=1
+        let mut board = forcing_chain_board();
+        let start = board.clone();
+        let mut solver = RuleBasedSolver::new().with_max_forcing_depth(2);
+        solver.update_cells_with_candidates(&board);
+        assert!(matches!(solver.cell_forcing_chains(&mut board), Ok(false)));
+        assert!(removed(&start, &board).is_empty());
+
+        let mut solver = RuleBasedSolver::new().with_max_forcing_depth(3);
+        assert!(matches!(solver.cell_forcing_chains(&mut board), Ok(true)));
+        assert_eq!(removed(&start, &board), pairs(&[("E5", 3)]));
+    }
 
-                // Check if all candidate cells are in the same box
-                let peers = &board.box_peers[candidate_cells[0]].clone();
-                // peers.insert(candidate_cells[0].to_string());
-                let mut all_in_same_box = true;
-                for &cell in &candidate_cells[1..] {
-                    if !peers.contains(cell) {
-                        all_in_same_box = false;
-                        break;
-                    }
-                }
+    // A grid in every box of which each digit appears once, and which keeps the givens
+    fn keeps_boxes(givens: &[[u8; 9]; 9], grid: &[[u8; 9]; 9]) -> bool {
+        (0..9).all(|b| {
+            let digits: Vec<u8> = (0..9).map(|k| grid[b / 3 * 3 + k / 3][b % 3 * 3 + k % 3]).sorted().collect();
+            digits == (1..=9).collect::<Vec<u8>>()
+        }) && (0..81).all(|i| givens[i / 9][i % 9] == 0 || givens[i / 9][i % 9] == grid[i / 9][i % 9])
+    }
 
-                if !all_in_same_box {
-                    continue;
-                }
+    #[test]
+    fn annealing_accepts_every_improvement_and_fewer_worse_moves_as_it_cools() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for delta in [-3, -1, 0] {
+            assert!((0..100).all(|_| StochasticSolver::accept(delta, MIN_TEMPERATURE, &mut rng)), "{}", delta);
+        }
+        let accepted = |delta: i32, temperature: f64, rng: &mut StdRng| {
+            (0..1000).filter(|_| StochasticSolver::accept(delta, temperature, rng)).count()
+        };
+        assert_eq!(accepted(1, MIN_TEMPERATURE, &mut rng), 0);
+        assert!(accepted(1, 1000.0, &mut rng) > 990);
+        let (hot, cold) = (accepted(2, 2.0, &mut rng), accepted(2, 0.5, &mut rng));
+        // exp(-1) and exp(-4) of 1000 moves
+        assert!((300..440).contains(&hot), "{}", hot);
+        assert!((5..40).contains(&cold), "{}", cold);
+        assert!(accepted(1, 0.5, &mut rng) > cold);
+    }
 
-                // println!("Candidate cells length: {}", candidate_cells.len());
-                // println!("Candidate cells: {:?}", candidate_cells);
-                // println!("Unit: {:?}", unit);
-                // println!("Peers: {:?}", peers);
-                // If all candidates are in a single box, get that box
-                // Then in that box, eliminate the digit from the cells that are not in the row or column
-                for cell in peers {
-                    if board.candidates[cell].len() == 1 {
-                        continue;
-                    }
-                    if !candidate_cells.contains(&cell) && board.candidates[cell].contains(&digit) {
-                        if !board.eliminate(cell, digit) {
-                            println!("{:?}", cell);
-                            println!("{:?}", digit);
-                            panic!("Contradiction encountered during locked candidates type 2");
-                        }
-                        else {
-                            found = true;
-                        }
-                    }
-                }
-            }
+    #[test]
+    fn neighborhood_swaps_two_unfixed_cells_of_a_box_and_keeps_its_cost() {
+        let givens = board(EASY).board;
+        let mut rng = StdRng::seed_from_u64(11);
+        let mut neighborhood = Neighborhood::new(&givens, &mut rng).unwrap();
+        assert!(keeps_boxes(&givens, neighborhood.grid()));
+        assert_eq!(neighborhood.cost(), Neighborhood::score(neighborhood.grid()));
+        for _ in 0..2000 {
+            let swap = neighborhood.random_move(&mut rng);
+            assert_ne!(swap.a, swap.b);
+            assert_eq!((swap.a.0 / 3, swap.a.1 / 3), (swap.b.0 / 3, swap.b.1 / 3), "{:?}", swap);
+            assert_eq!((givens[swap.a.0][swap.a.1], givens[swap.b.0][swap.b.1]), (0, 0), "{:?}", swap);
+            let delta = neighborhood.delta(swap);
+            let before = *neighborhood.grid();
+            neighborhood.apply(swap, delta);
+            assert_eq!(neighborhood.cost(), Neighborhood::score(neighborhood.grid()));
+            assert_eq!(neighborhood.cost() - Neighborhood::score(&before), delta);
+            assert!(keeps_boxes(&givens, neighborhood.grid()));
         }
+        assert!(neighborhood.moves().all(|swap| givens[swap.a.0][swap.a.1] == 0 && givens[swap.b.0][swap.b.1] == 0));
+        neighborhood.restart(&mut rng);
+        assert!(keeps_boxes(&givens, neighborhood.grid()));
+        assert_eq!(neighborhood.cost(), Neighborhood::score(neighborhood.grid()));
     }
-    // If no elimination was possible, the function returns false indicating that no progress was made.
-    found
-}
 
+    #[test]
+    fn neighborhood_refuses_givens_that_repeat_in_a_box() {
+        let mut rng = StdRng::seed_from_u64(1);
+        assert!(Neighborhood::new(&board(CLASHING).board, &mut rng).is_none());
+        let outcome = StochasticSolver::default().solve(&mut board(CLASHING), &Budget::new());
+        assert!(matches!(outcome, SolveOutcome::Unsolvable(_)), "{}", outcome);
+    }
 
+    // Temperatures the solver reports, one per iteration
+    struct Temperatures(Vec<f64>);
 
+    impl SolverObserver for Temperatures {
+        fn on_iteration(&mut self, _iteration: usize, _cost: i32, temperature: Option<f64>) {
+            self.0.push(temperature.unwrap());
+        }
+    }
 
-    
+    fn annealing(max_iterations: usize, reheat: ReheatPolicy, restart: RestartPolicy) -> AnnealingConfig {
+        AnnealingConfig {
+            initial_temperature: 0.5,
+            cooling: Cooling::Exponential { factor: 0.9 },
+            max_iterations,
+            reheat,
+            restart,
+            seed: Some(3),
+        }
+    }
 
-    // // Complex rules: X-Wing, Swordfish
+    #[test]
+    fn annealing_reheats_and_restarts_once_stalled() {
+        let reheat = ReheatPolicy::Stalled { iterations: 50, temperature: 2.0 };
+        let mut solver = StochasticSolver::new(annealing(400, reheat, RestartPolicy::Never));
+        let mut temperatures = Temperatures(Vec::new());
+        let outcome = solver.solve_observed(&mut board(HARD), &Budget::new(), &mut temperatures);
+        assert!(matches!(outcome, SolveOutcome::Stopped(Limit::Iterations(400))), "{}", outcome);
+        assert_eq!(temperatures.0.len(), 400);
+        assert_eq!(temperatures.0[0], 0.5);
+        assert!(temperatures.0.contains(&2.0));
+        assert_eq!(solver.stats().restarts, 0);
+
+        let mut solver = StochasticSolver::new(annealing(400, ReheatPolicy::Never, RestartPolicy::Stalled { iterations: 50 }));
+        let mut temperatures = Temperatures(Vec::new());
+        solver.solve_observed(&mut board(HARD), &Budget::new(), &mut temperatures);
+        assert!(solver.stats().restarts > 0);
+        assert!(!temperatures.0.contains(&2.0));
+        assert_eq!(temperatures.0.iter().filter(|&&t| t == 0.5).count(), solver.stats().restarts + 1);
+    }
 
-    // // X-Wing:
-    // // Look for two rows (the base sets) with two candidates of the same digit (the fish digit).
-    // // If you can find two columns, such that all candidates of the specific digit in both rows
-    // // are contained in the columns (the cover sets), all fish candidates in the columns that are not
-    // // part of the rows can be eliminated. The result is called an X-Wing in the rows.
-    // fn x_wing(&self, board: &mut Sudoku) -> bool {
-    //     for digit in 1..=9 {
-    //         // Check rows as base sets
-    //         if self.x_wing_base_sets(board, digit, |i| (0..9).map(|j| utils::coords_to_cell(i, j)).collect()) {
-    //             return true;
-    //         }
-    //         // Check columns as base sets
-    //         if self.x_wing_base_sets(board, digit, |j| (0..9).map(|i| utils::coords_to_cell(i, j)).collect()) {
-    //             return true;
-    //         }
-    //     }
-    //     false
-    // }
-    
-    // fn x_wing_base_sets(&self, board: &mut Sudoku, digit: usize, base_set_fn: impl Fn(usize) -> Vec<String>) -> bool {
-    //     for i in 0..9 {
-    //         let i_base_set = base_set_fn(i);
-    //         let i_candidate_indices: Vec<_> = i_base_set.iter().enumerate().filter(|(_, cell)| board.candidates[cell].contains(&digit)).map(|(index, _)| index).collect();
-    //         if i_candidate_indices.len() != 2 {
-    //             continue;
-    //         }
-    //         for j in i + 1..9 {
-    //             let j_base_set = base_set_fn(j);
-    //             let j_candidate_indices: Vec<_> = j_base_set.iter().enumerate().filter(|(_, &cell)| board.candidates[&cell].contains(&digit)).map(|(index, _)| index).collect();
-    //             if j_candidate_indices == i_candidate_indices {
-    //                 for &index in &i_candidate_indices {
-    //                     for k in 0..9 {
-    //                         if k != i && k != j {
-    //                             let cell = base_set_fn(k)[index].clone();
-    //                             if board.candidates[&cell].contains(&digit) {
-    //                                 board.eliminate(&cell, digit);
-    //                                 return true;
-    //                             }
-    //                         }
-    //                     }
-    //                 }
-    //             }
-    //         }
-    //     }
-    //     false
-    // }
-    
+    #[test]
+    fn annealing_resets_its_counters_for_each_puzzle() {
+        let mut solver = StochasticSolver::new(annealing(300, ReheatPolicy::Never, RestartPolicy::Stalled { iterations: 50 }));
+        let outcome = solver.solve(&mut board(HARD), &Budget::new());
+        assert!(matches!(outcome, SolveOutcome::Stopped(Limit::Iterations(300))), "{}", outcome);
+        assert_eq!(solver.iterations(), 300);
+        assert!(solver.stats().accepted_moves > 0 && solver.stats().restarts > 0);
 
-    
+        let SolveOutcome::Solved(solution) = SatSolver::new().solve(&mut board(EASY), &Budget::new()) else {
+            panic!("EASY has a solution");
+        };
+        let mut solved = board(EASY);
+        solved.board = solution;
+        assert_eq!(solver.solve(&mut solved, &Budget::new()), SolveOutcome::Solved(solution));
+        assert_eq!((solver.iterations(), solver.stats().accepted_moves, solver.stats().restarts), (0, 0, 0));
+    }
 
-    // // Swordfish:
-    // // Look for three rows (the base sets) with two or three candidates of the same digit (the fish digit).
-    // // If you can find three columns, such that all candidates of the specific digit in the three rows
-    // // are contained in the columns (the cover sets), all fish candidates in the columns that are not
-    // // part of the rows can be eliminated. The result is called a Swordfish in the rows.
-    // fn swordfish(&self, board: &mut Sudoku) -> bool {
-    //     for digit in 1..=9 {
-    //         // Check rows as base sets
-    //         if self.swordfish_base_sets(board, digit, |i| (0..9).map(|j| utils::coords_to_cell(i, j)).collect()) {
-    //             return true;
-    //         }
-    //         // Check columns as base sets
-    //         if self.swordfish_base_sets(board, digit, |j| (0..9).map(|i| utils::coords_to_cell(i, j)).collect()) {
-    //             return true;
-    //         }
-    //     }
-    //     false
-    // }
-    
-    // fn swordfish_base_sets(&self, board: &mut Sudoku, digit: usize, base_set_fn: impl Fn(usize) -> Vec<String>) -> bool {
-    //     let base_sets_with_digit: Vec<_> = (0..9).filter(|&i| base_set_fn(i).iter().any(|cell| board.candidates[cell].contains(&digit))).collect();
-    //     if base_sets_with_digit.len() < 3 {
-    //         return false;
-    //     }
-    //     for combo in base_sets_with_digit.into_iter().combinations(3) {
-    //         let candidate_indices: Vec<_> = combo.iter().flat_map(|&i| {
-    //             let base_set = base_set_fn(i);
-    //             base_set.iter().enumerate().filter(move |(_, &cell)| board.candidates[&cell].contains(&digit)).map(|(index, _)| index).collect::<Vec<_>>()
-    //         }).collect();            
-    //         let unique_candidate_indices: HashSet<_> = candidate_indices.iter().cloned().collect();
-    //         if unique_candidate_indices.len() == 3 {
-    //             for &index in &unique_candidate_indices {
-    //                 for k in 0..9 {
-    //                     if !combo.contains(&k) {
-    //                         let cell = base_set_fn(k)[index].clone();
-    //                         if board.candidates[&cell].contains(&digit) {
-    //                             board.eliminate(&cell, digit);
-    //                             return true;
-    //                         }
-    //                     }
-    //                 }
-    //             }
-    //         }
-    //     }
-    //     false
-    // }
-    
+    #[test]
+    fn annealing_is_reproducible_with_a_seed() {
+        let run = || {
+            let mut solver = StochasticSolver::new(annealing(500, ReheatPolicy::Never, RestartPolicy::Never));
+            let mut sudoku = board(HARD);
+            solver.solve(&mut sudoku, &Budget::new());
+            (sudoku.board, solver.stats().accepted_moves)
+        };
+        assert_eq!(run(), run());
+    }
 
-    
-}
+    // The temperatures a schedule gives over n iterations from initial, every move accepted
+    fn cooled(schedule: &mut dyn CoolingSchedule, initial: f64, n: usize) -> Vec<f64> {
+        schedule.reset(initial);
+        let mut temperature = initial;
+        (0..n).map(|_| {
+            temperature = schedule.cool(temperature, true);
+            temperature
+        }).collect()
+    }
 
-// Crook's algorithm.
-// A little tough :( sadge
+    fn assert_cools(temperatures: &[f64], formula: impl Fn(usize) -> f64) {
+        assert!(temperatures.windows(2).all(|pair| pair[1] <= pair[0]), "{:?}", temperatures);
+        assert!(temperatures.iter().all(|&t| t > 0.0), "{:?}", temperatures);
+        for (k, &t) in temperatures.iter().enumerate() {
+            let expected = formula(k + 1).max(MIN_TEMPERATURE);
+            assert!((t - expected).abs() < 1e-9, "T_{} = {}, not {}", k + 1, t, expected);
+        }
+    }
 
-    
-// Stochastic search.
+    #[test]
+    fn exponential_cooling_multiplies_by_its_factor() {
+        assert_cools(&cooled(&mut ExponentialCooling::new(0.9), 2.0, 300), |k| 2.0 * 0.9f64.powi(k as i32));
+    }
 
-pub struct StochasticSolver {
-    temperature: f64,
-    temperature_start: f64,
-    cooling_factor: f64,
-    units: Vec<Vec<String>>,
-    counter: usize,
-}
+    #[test]
+    fn linear_cooling_subtracts_its_step_down_to_the_floor() {
+        let temperatures = cooled(&mut LinearCooling::new(0.25), 2.0, 12);
+        assert_cools(&temperatures, |k| 2.0 - 0.25 * k as f64);
+        assert_eq!(temperatures[11], MIN_TEMPERATURE);
+    }
 
-// Uses stochastic search with simulated annealing.
-// https://en.wikipedia.org/wiki/Simulated_annealing
+    #[test]
+    fn logarithmic_cooling_restarts_from_the_reset_temperature() {
+        let mut schedule = LogarithmicCooling::new();
+        assert_cools(&cooled(&mut schedule, 2.0, 300), |k| 2.0 * 2f64.ln() / (k as f64 + 2.0).ln());
+        // reheated: the count starts again from the new temperature
+        assert_cools(&cooled(&mut schedule, 0.5, 10), |k| 0.5 * 2f64.ln() / (k as f64 + 2.0).ln());
+    }
 
-impl Solver for StochasticSolver {
-    fn solve(&mut self, board: &mut Sudoku) -> bool {
-        let mut digit_count = [0; 9];
+    #[test]
+    fn lundy_mees_cooling_divides_by_one_plus_beta_t() {
+        // T_k = T_0 / (1 + k * beta * T_0)
+        assert_cools(&cooled(&mut LundyMeesCooling::new(0.01), 2.0, 300), |k| 2.0 / (1.0 + k as f64 * 0.01 * 2.0));
+    }
 
-        // Count the occurrences of each digit in the board
-        for row in 0..9 {
-            for col in 0..9 {
-                let digit = board.board[row][col];
-                if digit != 0 {
-                    digit_count[(digit - 1) as usize] += 1;
+    #[test]
+    fn adaptive_cooling_changes_the_temperature_once_a_window() {
+        let mut schedule = AdaptiveCooling::new(0.5, 4, 0.5);
+        schedule.reset(1.0);
+        let accepting: Vec<f64> = (0..8).scan(1.0, |t, _| { *t = schedule.cool(*t, true); Some(*t) }).collect();
+        assert_eq!(accepting, [1.0, 1.0, 1.0, 0.5, 0.5, 0.5, 0.5, 0.25]);
+        schedule.reset(1.0);
+        let rejecting: Vec<f64> = (0..8).scan(1.0, |t, _| { *t = schedule.cool(*t, false); Some(*t) }).collect();
+        assert_eq!(rejecting, [1.0, 1.0, 1.0, 2.0, 2.0, 2.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn adaptive_cooling_moves_towards_its_target_acceptance_rate() {
+        // Moves one worse than the current grid, accepted with probability exp(-1 / T)
+        let rate = |initial: f64| {
+            let mut schedule = AdaptiveCooling::new(0.3, 100, 0.9);
+            let mut rng = StdRng::seed_from_u64(5);
+            let mut temperature = initial;
+            schedule.reset(temperature);
+            let mut accepted = 0;
+            for iteration in 0..20_000 {
+                let accept = StochasticSolver::accept(1, temperature, &mut rng);
+                if iteration >= 10_000 && accept {
+                    accepted += 1;
                 }
+                temperature = schedule.cool(temperature, accept);
             }
+            accepted as f64 / 10_000.0
+        };
+        // Starting far too hot (rate 0.9) and far too cold (rate 0)
+        for initial in [10.0, 0.1] {
+            let rate = rate(initial);
+            assert!((0.2..0.4).contains(&rate), "from T={}: {}", initial, rate);
         }
-        let mut rng = thread_rng();
+    }
 
-        let mut missing_digits = Vec::new();
-        let mut extra_digits = Vec::new();
+    fn genetic(crossover: Crossover, max_generations: usize) -> GeneticSolver {
+        GeneticSolver::new(GeneticConfig { crossover, max_generations, seed: Some(4), ..GeneticConfig::default() })
+    }
 
-        // Separate missing digits and extra digits
-        for (digit, &count) in digit_count.iter().enumerate() {
-            let digit_value = (digit + 1) as u8;
-            if count < 9 {
-                missing_digits.extend(std::iter::repeat(digit_value).take(9 - count));
-            } else if count > 9 {
-                extra_digits.extend(std::iter::repeat(digit_value).take(count - 9));
+    #[test]
+    fn genetic_chromosomes_and_their_children_keep_the_boxes_and_givens() {
+        let givens = board(EASY).board;
+        let boxes = Neighborhood::unfixed_cells(&givens);
+        let mut rng = StdRng::seed_from_u64(9);
+        for crossover in [Crossover::RowBlocks, Crossover::BoxBlocks] {
+            let solver = genetic(crossover.clone(), 1);
+            let population = solver.seed_population(&givens, &mut rng).unwrap();
+            assert_eq!(population.len(), 100);
+            assert!(population.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+            for (grid, cost) in &population {
+                assert!(keeps_boxes(&givens, grid));
+                assert_eq!(*cost, Neighborhood::score(grid));
+            }
+            // The cells a child inherits whole from one parent: a band of rows or a box
+            let blocks: Vec<Vec<(usize, usize)>> = match crossover {
+                Crossover::RowBlocks => (0..3).map(|band| (band * 27..band * 27 + 27).map(|i| (i / 9, i % 9)).collect()).collect(),
+                Crossover::BoxBlocks => (0..9).map(|b| (0..9).map(|k| (b / 3 * 3 + k / 3, b % 3 * 3 + k % 3)).collect()).collect(),
+            };
+            for pair in population.chunks(2) {
+                let (first, second) = (&pair[0].0, &pair[1].0);
+                let mut child = solver.crossover(first, second, &mut rng);
+                assert!(keeps_boxes(&givens, &child), "{:?}", crossover);
+                for block in &blocks {
+                    let from = |parent: &[[u8; 9]; 9]| block.iter().all(|&(row, col)| child[row][col] == parent[row][col]);
+                    assert!(from(first) || from(second), "{:?}", crossover);
+                }
+                GeneticSolver::mutate(&mut child, &givens, &boxes, &mut rng);
+                assert!(keeps_boxes(&givens, &child));
             }
         }
+    }
+
+    #[test]
+    fn genetic_solver_solves_or_stops_at_its_generation_limit() {
+        // EASY with a third of its empty cells filled in from the solution, which the default
+        // population solves in a few dozen generations
+        let SolveOutcome::Solved(solution) = SatSolver::new().solve(&mut board(EASY), &Budget::new()) else {
+            panic!("EASY has a solution");
+        };
+        let mut puzzle = board(EASY);
+        for i in (0..81).step_by(3) {
+            puzzle.board[i / 9][i % 9] = solution[i / 9][i % 9];
+        }
+        for crossover in [Crossover::RowBlocks, Crossover::BoxBlocks] {
+            let mut solver = genetic(crossover, 1000);
+            assert_eq!(solver.solve(&mut puzzle.clone(), &Budget::new()), SolveOutcome::Solved(solution));
+            assert!(solver.generations() < 1000);
+        }
 
-        // Shuffle the missing digits randomly
-        missing_digits.shuffle(&mut rng);
+        let mut solver = genetic(Crossover::BoxBlocks, 5);
+        let mut sudoku = board(EASY);
+        let outcome = solver.solve(&mut sudoku, &Budget::new());
+        assert!(matches!(outcome, SolveOutcome::Stopped(Limit::Generations(5))), "{}", outcome);
+        assert_eq!(solver.generations(), 5);
+        // the best grid found is left on the board
+        assert!(keeps_boxes(&board(EASY).board, &sudoku.board));
+    }
 
-        println!("{:?}", missing_digits);
+    // The solution of EASY, as a neighborhood whose only move swaps A1 and B2, starting swapped
+    fn one_swap_neighborhood() -> (Neighborhood, Swap) {
+        let SolveOutcome::Solved(solution) = SatSolver::new().solve(&mut board(EASY), &Budget::new()) else {
+            panic!("EASY has a solution");
+        };
+        let mut givens = solution;
+        givens[0][0] = 0;
+        givens[1][1] = 0;
+        let mut grid = solution;
+        Neighborhood::swap(&mut grid, (0, 0), (1, 1));
+        let neighborhood = Neighborhood { givens, boxes: Neighborhood::unfixed_cells(&givens), grid, cost: Neighborhood::score(&grid) };
+        (neighborhood, Swap { a: (0, 0), b: (1, 1) })
+    }
 
-        // Replace extra occurrences with missing digits
-        let mut index = 0;
-        for row in 0..9 {
-            for col in 0..9 {
-                let digit = board.board[row][col];
-                if digit == 0 {
-                    board.board[row][col] = missing_digits[index];
-                    index += 1;
+    #[test]
+    fn tabu_search_rejects_a_recent_move_unless_it_beats_the_best() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let (mut neighborhood, swap) = one_swap_neighborhood();
+        let worse = neighborhood.cost();
+        assert!(worse > Neighborhood::SOLVED);
+        assert_eq!(neighborhood.moves().collect::<Vec<_>>(), [swap]);
+        let mut tabu = TabuSearch::new(1);
+        tabu.reset(&neighborhood);
+        assert!(tabu.step(&mut neighborhood, worse, &mut rng));
+        assert_eq!(neighborhood.cost(), Neighborhood::SOLVED);
+
+        // Swapping back is tabu, even though it is the best (only) move
+        assert!(!tabu.step(&mut neighborhood, Neighborhood::SOLVED, &mut rng));
+        assert_eq!(neighborhood.cost(), Neighborhood::SOLVED);
+        // unless it would beat the best cost seen (aspiration)
+        assert!(tabu.step(&mut neighborhood, worse + 1, &mut rng));
+        assert_eq!(neighborhood.cost(), worse);
+        // or once it has left the tabu list
+        let (mut neighborhood, _) = one_swap_neighborhood();
+        let mut forgetful = TabuSearch::new(0);
+        forgetful.reset(&neighborhood);
+        assert!(forgetful.step(&mut neighborhood, worse, &mut rng));
+        assert!(forgetful.step(&mut neighborhood, Neighborhood::SOLVED, &mut rng));
+        assert_eq!(neighborhood.cost(), worse);
+    }
+
+    #[test]
+    fn late_acceptance_compares_with_the_cost_length_iterations_ago() {
+        let mut rng = StdRng::seed_from_u64(0);
+        // Each step swaps A1 and B2, so the cost alternates between worse and solved while accepted
+        let accepts = |length: usize, rng: &mut StdRng| {
+            let (mut neighborhood, _) = one_swap_neighborhood();
+            let mut late = LateAcceptance::new(length);
+            late.reset(&neighborhood);
+            (0..5).map(|_| late.step(&mut neighborhood, 0, rng)).collect::<Vec<bool>>()
+        };
+        // Solving is always accepted; making it worse again only when the cost length iterations
+        // ago was as bad: with a history of 3, step 4 looks back at the solved grid of step 1, and
+        // step 5 at the worse one of step 2
+        assert_eq!(accepts(1, &mut rng), [true, false, false, false, false]);
+        assert_eq!(accepts(3, &mut rng), [true, true, true, false, true]);
+        assert_eq!(accepts(5, &mut rng), [true, true, true, true, true]);
+    }
+
+    #[test]
+    fn local_search_keeps_the_givens_and_stops_when_the_criterion_is_met() {
+        let config = |stopping: StoppingCriterion| LocalSearchConfig { stopping, restart: RestartPolicy::Never, seed: Some(2) };
+        type Run = Box<dyn Fn(StoppingCriterion, &mut Sudoku) -> (SolveOutcome, usize)>;
+        let runs: [Run; 2] = [
+            Box::new(move |stopping, sudoku| {
+                let mut solver = LocalSearchSolver::new(TabuSearch::new(10), config(stopping));
+                (solver.solve(sudoku, &Budget::new()), solver.iterations())
+            }),
+            Box::new(move |stopping, sudoku| {
+                let mut solver = LocalSearchSolver::new(LateAcceptance::new(50), config(stopping));
+                (solver.solve(sudoku, &Budget::new()), solver.iterations())
+            }),
+        ];
+        for run in &runs {
+            let mut sudoku = board(HARD);
+            let (outcome, iterations) = run(StoppingCriterion::Iterations(300), &mut sudoku);
+            assert!(matches!(outcome, SolveOutcome::Stopped(Limit::Iterations(300))), "{}", outcome);
+            assert_eq!(iterations, 300);
+            assert!(keeps_boxes(&board(HARD).board, &sudoku.board));
+
+            // Stalled counts from the last better best cost, so it stops within the limit
+            let (outcome, iterations) = run(StoppingCriterion::Stalled(20), &mut board(HARD));
+            assert!(matches!(outcome, SolveOutcome::Stopped(Limit::Stalled(20))), "{}", outcome);
+            assert!(iterations >= 20);
+
+            let any = StoppingCriterion::Any(vec![StoppingCriterion::Stalled(1_000_000), StoppingCriterion::Iterations(40)]);
+            let (outcome, iterations) = run(any, &mut board(HARD));
+            assert!(matches!(outcome, SolveOutcome::Stopped(Limit::Iterations(40))), "{}", outcome);
+            assert_eq!(iterations, 40);
+        }
+    }
+
+    #[test]
+    fn ant_construction_undoes_the_picks_that_fail() {
+        let mut solver = AntColonySolver::new(AntColonyConfig { seed: Some(1), ..AntColonyConfig::default() });
+        let mut sudoku = board(HARD);
+        solver.initialize_candidates(&mut sudoku);
+        let start = sudoku.candidates.clone();
+        let initial = 1.0 / 81.0;
+        solver.pheromone = vec![[initial; 9]; 81];
+        // An observed solve's log of changes not reported yet, which the ants must leave alone
+        let unreported = vec![("A1".to_string(), 5, true)];
+        sudoku.changes = Some(unreported.clone());
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..50 {
+            sudoku.candidates = start.clone();
+            solver.construct(&mut sudoku, initial, &mut rng);
+            assert_eq!(sudoku.changes, Some(unreported.clone()));
+            for cell in &sudoku.cells {
+                let candidates = &sudoku.candidates[cell];
+                assert!(!candidates.is_empty() && candidates.is_subset(&start[cell]), "{}", cell);
+                // A placed digit is gone from every peer, so no failed pick left a clash behind
+                if candidates.len() == 1 {
+                    let digit = candidates.iter().next().unwrap();
+                    assert!(sudoku.peers[cell].iter().all(|peer| !sudoku.candidates[peer].contains(digit)), "{}", cell);
+                }
+            }
+        }
+        // HARD makes ants pick digits that fail
+        assert!(solver.stats().backtracks > 0);
+    }
+
+    #[test]
+    fn ant_colony_solves_puzzles_with_a_seed() {
+        for puzzle in [EASY, HARD] {
+            let mut solver = AntColonySolver::new(AntColonyConfig { seed: Some(0), ..AntColonyConfig::default() });
+            let mut sudoku = board(puzzle);
+            solver.initialize_candidates(&mut sudoku);
+            let outcome = solver.solve(&mut sudoku, &Budget::new());
+            let SolveOutcome::Solved(grid) = outcome else {
+                panic!("{}: {}", puzzle, outcome);
+            };
+            assert!(solves(puzzle, &grid), "{}", puzzle);
+            assert_eq!(sudoku.board, grid);
+        }
+        let mut solver = AntColonySolver::new(AntColonyConfig { seed: Some(0), max_iterations: 0, ..AntColonyConfig::default() });
+        let mut sudoku = board(HARD);
+        solver.initialize_candidates(&mut sudoku);
+        let outcome = solver.solve(&mut sudoku, &Budget::new());
+        assert!(matches!(outcome, SolveOutcome::Stopped(Limit::Iterations(0))), "{}", outcome);
+    }
+
+    #[test]
+    fn deep_dfs_undoes_its_guesses_when_it_cannot_tell_which_was_wrong() {
+        // Puzzle 12 of data/hard.txt as well
+        for puzzle in [HARD, "6..3.2....5.....1..........7.26............543.........8.15........4.2........7.."] {
+            let SolveOutcome::Solved(solution) = SatSolver::new().solve(&mut board(puzzle), &Budget::new()) else {
+                panic!("{} has a solution", puzzle);
+            };
+            let mut sudoku = board(puzzle);
+            let mut solver = DeepDFSSolver::new();
+            solver.initialize_candidates(&mut sudoku);
+            let (outcome, trace) = solver.solve_traced(&mut sudoku, &Budget::new());
+            match outcome {
+                SolveOutcome::Solved(grid) => assert_eq!(grid, solution),
+                // Only what was deduced is left, which the solution still fits
+                SolveOutcome::PartiallySolved(candidates) => {
+                    assert_eq!(candidates, sudoku.candidates);
+                    assert!(sudoku.cells.iter().enumerate().all(|(i, cell)| candidates[cell].contains(&(solution[i / 9][i % 9] as usize))));
+                    assert_eq!(trace.state(trace.steps.len()).unwrap().candidates, candidates);
                 }
+                outcome => panic!("{}: {}", puzzle, outcome),
             }
         }
+    }
 
-        let mut score = self.score(board);
-        while score > -243 && self.counter < 1000000 {
-            let old_board = board.clone();
-            let old_score = score;
+    #[test]
+    fn deep_dfs_reports_a_cell_that_can_neither_hold_a_digit_nor_not() {
+        let mut sudoku = board(EMPTY);
+        let mut solver = DeepDFSSolver::new();
+        solver.initialize_candidates(&mut sudoku);
+        // Three cells of row A share two digits
+        set(&mut sudoku, &[("A1", &[1, 2]), ("A2", &[1, 2]), ("A3", &[1, 2])]);
+        let outcome = solver.solve(&mut sudoku, &Budget::new());
+        assert_eq!(outcome, SolveOutcome::Unsolvable("every candidate of A1 leads to a contradiction".to_string()));
+    }
 
-            self.swap_random(board);
-            score = self.score(board);
+    #[test]
+    fn dlx_solver_agrees_with_the_other_exact_solvers() {
+        // EASY without its given in C9, and without the one in E3
+        let puzzles = [
+            (EASY, 1),
+            (HARD, 1),
+            (".94...13..............76....8..1.....32.........2...6.....5.4.......8..7..63.4..8", 16),
+            (".94...13..............76..2.8..1.....3..........2...6.....5.4.......8..7..63.4..8", 36),
+        ];
+        for (puzzle, solutions) in puzzles {
+            assert_eq!(DlxSolver::new().count_solutions(&board(puzzle), 100), solutions, "{}", puzzle);
+            assert_eq!(FastSolver::new().count_solutions(&board(puzzle), 100), solutions, "{}", puzzle);
+            assert_eq!(SatSolver::new().count_solutions(&board(puzzle), 100), solutions, "{}", puzzle);
+            for limit in [1, 2, 20] {
+                assert_eq!(DlxSolver::new().count_solutions(&board(puzzle), limit), limit.min(solutions), "{}", puzzle);
+            }
 
-            if !self.accept(score - old_score) {
-                println!("{}", score - old_score);
-                println!("Rejecting swap");
-                *board = old_board;
-                score = old_score;
+            let outcome = DlxSolver::new().solve(&mut board(puzzle), &Budget::new());
+            let SolveOutcome::Solved(grid) = outcome else {
+                panic!("{}: {}", puzzle, outcome);
+            };
+            assert!(solves(puzzle, &grid), "{}", puzzle);
+            if solutions == 1 {
+                assert_eq!(FastSolver::new().solve(&mut board(puzzle), &Budget::new()), SolveOutcome::Solved(grid));
+            }
+            let dlx = DlxSolver::new().with_check_unique(true).solve(&mut board(puzzle), &Budget::new());
+            let fast = FastSolver::new().with_check_unique(true).solve(&mut board(puzzle), &Budget::new());
+            for outcome in [dlx, fast] {
+                assert_eq!(outcome == SolveOutcome::MultipleSolutions, solutions > 1, "{}: {}", puzzle, outcome);
             }
-            self.cool_down();
         }
-        println!("Stochastic solver finished.");
-        self.temperature = self.temperature_start;
-        score == -243
     }
 
-    fn name(&self) -> String {
-        format!("Stochastic (T={}, cooling={})", self.temperature, self.cooling_factor)
+    #[test]
+    fn dlx_solver_stops_at_its_limits_on_the_empty_grid() {
+        for limit in [1, 2, 10, 1000] {
+            assert_eq!(DlxSolver::new().count_solutions(&board(EMPTY), limit), limit);
+        }
+        let mut solver = DlxSolver::new();
+        let outcome = solver.solve(&mut board(EMPTY), &Budget::new().with_node_limit(20));
+        assert_eq!(outcome, SolveOutcome::Stopped(Limit::Nodes(20)));
+        assert_eq!(solver.stats().nodes, 20);
+        assert_eq!(solver.stats().max_depth, 19);
     }
 
-    fn initialize_candidates(&mut self, _board: &mut Sudoku) {
-        // unneeded
+    #[test]
+    fn solutions_yields_each_solution_once() {
+        assert_eq!(solutions(&board(EASY)).count(), 1);
+        assert!(solutions(&board(EASY)).all(|grid| solves(EASY, &grid)));
+        assert!(solutions(&board(CLASHING)).next().is_none());
+        // EASY without its given in C9
+        let puzzle = ".94...13..............76....8..1.....32.........2...6.....5.4.......8..7..63.4..8";
+        let grids: Vec<Grid> = solutions(&board(puzzle)).collect();
+        assert_eq!(grids.len(), 16);
+        assert!(grids.iter().all(|grid| solves(puzzle, grid)));
+        assert_eq!(grids.iter().unique().count(), 16);
+
+        let grids: Vec<Grid> = solutions(&board(EMPTY)).take(50).collect();
+        assert_eq!(grids.len(), 50);
+        assert!(grids.iter().all(|grid| solves(EMPTY, grid)));
+        assert_eq!(grids.iter().unique().count(), 50);
     }
 
-    fn is_correct(&self, board: &mut Sudoku) -> bool {
-        board.board_correct()
+    #[test]
+    fn solutions_come_in_the_same_order_and_resume_where_they_stopped() {
+        let first: Vec<Grid> = solutions(&board(EMPTY)).take(30).collect();
+        assert_eq!(solutions(&board(EMPTY)).take(30).collect::<Vec<_>>(), first);
+        let mut iterator = solutions(&board(EMPTY));
+        let mut resumed: Vec<Grid> = iterator.by_ref().take(12).collect();
+        resumed.extend(iterator.take(18));
+        assert_eq!(resumed, first);
     }
-} 
 
-impl StochasticSolver {
-    pub fn new(temperature: f64, cooling_factor: f64, board: Sudoku) -> Self {
-        let units: Vec<Vec<String>> = board.cells.iter()
-            .flat_map(|cell| vec![board.row_peers[cell].clone(), board.col_peers[cell].clone(), board.box_peers[cell].clone()])
-            .map(|unit| unit.iter().cloned().collect())
-            .collect();
+    fn tabu(stopping: StoppingCriterion) -> LocalSearchSolver<TabuSearch> {
+        LocalSearchSolver::new(TabuSearch::new(10), LocalSearchConfig { stopping, restart: RestartPolicy::Never, seed: Some(1) })
+    }
+
+    #[test]
+    fn solvers_stop_at_the_deadline() {
+        let mut solver = BruteForceSolver::new();
+        let mut easy = board(EASY);
+        solver.initialize_candidates(&mut easy);
+        assert!(solver.solve(&mut easy, &Budget::new()).is_solved());
+        assert!(solver.stats().nodes > 0);
+        // Already past: stopped before the first node, with the last solve's stats cleared
+        let mut hard = board(HARD);
+        solver.initialize_candidates(&mut hard);
+        assert_eq!(solver.solve(&mut hard.clone(), &Budget::new().with_deadline(Instant::now())), SolveOutcome::Stopped(Limit::Deadline));
+        assert_eq!(solver.stats().nodes, 0);
+        // Passing during the search, which on HARD takes close to a minute: the nodes so far
+        let outcome = solver.solve(&mut hard, &Budget::new().with_time_limit(Duration::from_millis(50)));
+        assert_eq!(outcome, SolveOutcome::Stopped(Limit::Deadline));
+        assert!(solver.stats().nodes >= BUDGET_POLL_INTERVAL && solver.stats().guesses > 0, "{:?}", solver.stats());
+
+        let mut solver = tabu(StoppingCriterion::Iterations(1_000_000));
+        let outcome = solver.solve(&mut board(HARD), &Budget::new().with_time_limit(Duration::from_millis(50)));
+        assert_eq!(outcome, SolveOutcome::Stopped(Limit::Deadline));
+        assert!(solver.iterations() > 0 && solver.iterations() < 1_000_000);
+        assert_eq!(solver.stats().iterations, solver.iterations());
+    }
 
-        let counter = 0;
+    #[test]
+    fn solvers_stop_once_cancelled() {
+        let cancelled = Budget::new().with_cancel_flag(Arc::new(AtomicBool::new(true)));
+        let mut solver = BruteForceSolver::new();
+        let mut hard = board(HARD);
+        solver.initialize_candidates(&mut hard);
+        assert_eq!(solver.solve(&mut hard, &cancelled), SolveOutcome::Stopped(Limit::Cancelled));
+        assert_eq!(FastSolver::new().solve(&mut board(HARD), &cancelled), SolveOutcome::Stopped(Limit::Cancelled));
+        assert_eq!(tabu(StoppingCriterion::Iterations(1_000_000)).solve(&mut board(HARD), &cancelled), SolveOutcome::Stopped(Limit::Cancelled));
+        assert_eq!(StochasticSolver::default().solve(&mut board(HARD), &cancelled), SolveOutcome::Stopped(Limit::Cancelled));
+        // Not set, the flag changes nothing
+        let running = Budget::new().with_cancel_flag(Arc::new(AtomicBool::new(false)));
+        assert!(FastSolver::new().solve(&mut board(HARD), &running).is_solved());
+    }
+
+    #[test]
+    fn local_search_stops_at_the_budget_iteration_limit() {
+        let budget = Budget::new().with_iteration_limit(100);
+        let mut solver = StochasticSolver::new(AnnealingConfig { seed: Some(1), ..AnnealingConfig::default() });
+        assert_eq!(solver.solve(&mut board(HARD), &budget), SolveOutcome::Stopped(Limit::Iterations(100)));
+        assert_eq!(solver.iterations(), 100);
+        let mut solver = tabu(StoppingCriterion::Iterations(1_000_000));
+        assert_eq!(solver.solve(&mut board(HARD), &budget), SolveOutcome::Stopped(Limit::Iterations(100)));
+        assert_eq!(solver.iterations(), 100);
+        // whichever limit comes first
+        let mut solver = tabu(StoppingCriterion::Iterations(40));
+        assert_eq!(solver.solve(&mut board(HARD), &budget), SolveOutcome::Stopped(Limit::Iterations(40)));
+    }
 
-        StochasticSolver { 
-            temperature, 
-            temperature_start: temperature,
-            cooling_factor,
-            units,
-            counter
+    #[test]
+    fn solve_trace_with_an_invalid_step_fails_replay() {
+        let mut easy = board(EASY);
+        let mut solver = BruteForceSolver::new();
+        solver.initialize_candidates(&mut easy);
+        let (_, trace) = solver.solve_traced(&mut easy, &Budget::new());
+        assert_eq!(trace.replay_error, None);
+        assert!(trace.state(trace.steps.len()).is_ok());
+
+        let step = |action: TraceAction, cell: &str, digit: usize| TraceStep {
+            action,
+            technique: "Propagation".to_string(),
+            cell: cell.to_string(),
+            digit,
+            cells: Vec::new(),
+            reason: String::new(),
+        };
+        let invalid = [
+            step(TraceAction::Backtrack, "A1", 9),
+            step(TraceAction::Placement, "J1", 1),
+            step(TraceAction::Elimination, "A1", 0),
+        ];
+        for invalid in invalid {
+            let mut broken = trace.clone();
+            broken.steps.insert(5, invalid.clone());
+            assert!(broken.state(5).is_ok());
+            assert!(broken.state(6).is_err(), "{}", invalid);
+            assert!(broken.state(broken.steps.len()).is_err(), "{}", invalid);
         }
+
+        // A solver reporting a backtrack from a guess it never made is flagged as it is recorded
+        let mut recorder = TraceRecorder::new(&board(EASY));
+        recorder.on_assign("A1", 5, true);
+        recorder.on_backtrack("A1", 6);
+        recorder.on_backtrack("A1", 5);
+        let trace = recorder.into_trace();
+        assert_eq!(trace.steps.len(), 3);
+        assert!(trace.replay_error.as_ref().is_some_and(|error| error.starts_with("step 2: ")), "{:?}", trace.replay_error);
+        assert_eq!(SolveTrace::from_json(&trace.to_json()), Ok(trace));
     }
 
-    // First get a random unit,
-    // Then get two random cells within that unit, and swap their values.
-    fn swap_random(&mut self, board: &mut Sudoku) {
-        println!("Swapping random!");
-        println!("Board: {:?}", board.board);
-        let unit_index = rand::thread_rng().gen_range(0..self.units.len());
-        let unit = &self.units[unit_index];
-        let mut rng = rand::thread_rng();
-        let (i, j) = (rng.gen_range(0..unit.len()), rng.gen_range(0..unit.len()));
-        
-        let coords_i = utils::cell_to_coords(&unit[i]);
-        let coords_j = utils::cell_to_coords(&unit[j]);
+    // The hint's region, technique and explanation, one disclosure level at a time
+    fn assert_discloses(hint: &Hint, region: &str, technique: &str, explanation: &str) {
+        assert_eq!(hint.disclose(Disclosure::Region), format!("Look at {}.", region));
+        assert_eq!(hint.disclose(Disclosure::Technique), format!("Look for a {} in {}.", technique, region));
+        let disclosed = hint.disclose(Disclosure::Explanation);
+        assert!(disclosed.starts_with(&format!("{}: {} => ", technique, explanation)), "{}", disclosed);
+    }
 
-        let temp = board.board[coords_i.0][coords_i.1];
-        board.board[coords_i.0][coords_i.1] = board.board[coords_j.0][coords_j.1];
-        board.board[coords_j.0][coords_j.1] = temp;
-        self.counter += 1;
+    #[test]
+    fn hint_gives_one_pointing_box_and_only_its_eliminations() {
+        let mut board = open_board();
+        keep(&mut board, 5, |cell| !["B1", "B2", "B3", "C1", "C2", "C3", "A3"].contains(&cell));
+        // A second pattern, 7 only in column 9 of box 9
+        keep(&mut board, 7, |cell| !["G7", "G8", "H7", "H8", "I7", "I8", "I9"].contains(&cell));
+        let hint = hint(&board).unwrap();
+        assert_eq!(hint.eliminations, pairs(&[("A4", 5), ("A5", 5), ("A6", 5), ("A7", 5), ("A8", 5), ("A9", 5)]));
+        assert_discloses(&hint, "box 1", "Locked Candidates Type 1",
+            "5 can only go in row A of box 1 (A1, A2), so not in the rest of row A");
     }
 
-    fn score(&self, board: &Sudoku) -> i32 {
-        let mut score = 0;
-        for i in 0..9 {
-            let row = board.board[i];
-            let column: [u8; 9] = (0..9).map(|j| board.board[j][i]).collect::<Vec<u8>>().try_into().unwrap();
-            score -= Sudoku::unique_elements(row) + Sudoku::unique_elements(column);
-        }
-        // New box checking section
-        for box_x in 0..3 {
-            for box_y in 0..3 {
-                let box_values: [u8; 9] = (0..9)
-                    .map(|i| board.board[box_x*3 + i/3][box_y*3 + i%3])
-                    .collect::<Vec<u8>>().try_into().unwrap();
-                score -= Sudoku::unique_elements(box_values);
-            }
-        }
-        println!("Score: {}", score);
-        score
+    #[test]
+    fn hint_gives_one_claiming_line_and_only_its_eliminations() {
+        let mut board = open_board();
+        keep(&mut board, 7, |cell| !cell.starts_with('E') || ["E1", "E2"].contains(&cell));
+        let hint = hint(&board).unwrap();
+        assert_eq!(hint.eliminations, pairs(&[("D1", 7), ("D2", 7), ("D3", 7), ("F1", 7), ("F2", 7), ("F3", 7)]));
+        assert_discloses(&hint, "row E", "Locked Candidates Type 2",
+            "7 can only go in box 4 of row E (E1, E2), so not in the rest of box 4");
     }
 
-    fn cool_down(&mut self) {
-        self.temperature *= self.cooling_factor;
+    #[test]
+    fn hint_discloses_a_naked_single() {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[5])]);
+        let hint = hint(&board).unwrap();
+        assert_eq!(hint.placements, pairs(&[("A1", 5)]));
+        assert_eq!(hint.eliminations.len(), 20);
+        assert_discloses(&hint, "box 1", "Naked Single", "A1 has no candidate left but 5");
     }
 
-    fn accept(&self, delta_s: i32) -> bool {
-        if delta_s <= 0 {
-            true
-        } else {
-            let u: f64 = rand::thread_rng().gen();
-            println!("u: {}", u);
-            println!("delta_s: {}", delta_s);
-            println!("temperature: {}", self.temperature);
-            println!("exp: {}", (delta_s as f64 / self.temperature).exp());
-            (delta_s as f64 / self.temperature).exp() < u
-        }
+    #[test]
+    fn hint_has_no_naked_single_that_places_nothing_new() {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[5])]);
+        keep(&mut board, 5, |cell| cell == "A1");
+        let solver = RuleBasedSolver::new();
+        assert!(solver.naked_single_hint(&board).is_none());
+        // Once placed, the digit is not a single to place either
+        board.board[0][0] = 5;
+        set(&mut board, &[("B1", &[3])]);
+        assert_eq!(solver.naked_single_hint(&board).unwrap().placements, pairs(&[("B1", 3)]));
     }
-}
 
+    #[test]
+    fn hint_discloses_a_hidden_single() {
+        let mut board = open_board();
+        keep(&mut board, 5, |cell| !cell.starts_with('A') || cell == "A1");
+        let hint = hint(&board).unwrap();
+        assert_eq!(hint.placements, pairs(&[("A1", 5)]));
+        assert_eq!(hint.eliminations, pairs(&[("A1", 1), ("A1", 2), ("A1", 3), ("A1", 4), ("A1", 6), ("A1", 7), ("A1", 8), ("A1", 9)]));
+        assert_discloses(&hint, "row A", "Hidden Single", "A1 is the only cell of row A that can hold 5");
+    }
 
+    #[test]
+    fn hint_discloses_a_naked_pair() {
+        let mut board = open_board();
+        set(&mut board, &[("A1", &[1, 2]), ("A2", &[1, 2])]);
+        let hint = hint(&board).unwrap();
+        assert_eq!(hint.cells, vec!["A1", "A2"]);
+        assert_eq!(hint.eliminations.len(), 14);
+        assert!(hint.eliminations.iter().all(|(cell, digit)| cell.starts_with('A') && [1, 2].contains(digit)));
+        assert_discloses(&hint, "row A", "Naked Pair", "A1 and A2 can only hold 1 and 2, so no other cell of row A can");
+    }
 
-// Knuth's Algorithm X, with dancing links.
-// This definitely won't work right now, or anytime in the future.
-// Due to Rust borrow rules.
-
-// struct Node {
-//     row: usize,
-//     col: usize,
-
-//     // The size of the column this node is in.
-//     size: usize,
-
-//     // Neighboring nodes.
-//     up: Option<Rc<RefCell<Node>>>,
-//     down: Option<Rc<RefCell<Node>>>,
-//     left: Option<Rc<RefCell<Node>>>,
-//     right: Option<Rc<RefCell<Node>>>,
-// }
-
-// impl Node {
-//     fn new(row: usize, col: usize) -> Self {
-//         Node {
-//             row,
-//             col,
-//             up: None,
-//             down: None,
-//             left: None,
-//             right: None,
-//         }
-//     }
-// }
-
-// struct Column {
-//     node: Rc<RefCell<Node>>,
-// }
-
-// impl Column {
-//     fn cover(&mut self) {
-//         self.node.borrow_mut().right.as_ref().unwrap().borrow_mut().left = self.node.borrow().left.clone();
-//         self.node.borrow_mut().left.as_ref().unwrap().borrow_mut().right = self.node.borrow().right.clone();
-
-//         let mut i = self.node.borrow().down.clone();
-//         while let Some(node) = i {
-//             let mut j = node.borrow().right.clone();
-//             while let Some(node_j) = j {
-//                 node_j.borrow_mut().down.as_ref().unwrap().borrow_mut().up = node_j.borrow().up.clone();
-//                 node_j.borrow_mut().up.as_ref().unwrap().borrow_mut().down = node_j.borrow().down.clone();
-//                 node_j.borrow().column.borrow_mut().size -= 1;
-
-//                 j = node_j.borrow().right.clone();
-//             }
-//             i = node.borrow().down.clone();
-//         }
-//     }
-
-//     fn uncover(&mut self) {
-//         let mut i = self.node.borrow().up.clone();
-//         while let Some(node) = i {
-//             let mut j = node.borrow().left.clone();
-//             while let Some(node_j) = j {
-//                 node_j.borrow().column.borrow_mut().size += 1;
-//                 node_j.borrow_mut().down.as_ref().unwrap().borrow_mut().up = node_j.clone();
-//                 node_j.borrow_mut().up.as_ref().unwrap().borrow_mut().down = node_j.clone();
-
-//                 j = node_j.borrow().left.clone();
-//             }
-//             i = node.borrow().up.clone();
-//         }
-
-//         self.node.borrow_mut().right.as_ref().unwrap().borrow_mut().left = self.node.clone();
-//         self.node.borrow_mut().left.as_ref().unwrap().borrow_mut().right = self.node.clone();
-//     }
-// }
-
-// struct DancingLinks {
-//     header: Rc<RefCell<Node>>,
-//     columns: Vec<Column>,
-// }
-
-// impl DancingLinks {
-//     fn new(matrix: &Vec<Vec<bool>>) -> Self {
-//         let header = Rc::new(RefCell::new(Node::new(0, 0)));
-
-//         let mut columns = Vec::new();
-//         for i in 0..matrix[0].len() {
-//             let column_node = Rc::new(RefCell::new(Node::new(0, i)));
-//             column_node.borrow_mut().left = Some(if let Some(last_column) = columns.last() {
-//                 last_column.node.clone()
-//             } else {
-//                 header.clone()
-//             });
-
-//             columns.push(Column { node: column_node.clone() });
-
-//             if let Some(prev_column_node) = column_node.borrow().left {
-//                 prev_column_node.borrow_mut().right = Some(column_node.clone());
-//             }
-//         }
-
-//         // Link the last column to the header and vice versa
-//         columns.last().unwrap().node.borrow_mut().right = Some(header.clone());
-//         header.borrow_mut().left = Some(columns.last().unwrap().node.clone());
-
-//         // Create all row nodes and link them to the corresponding column nodes
-//         for (i, row) in matrix.iter().enumerate() {
-//             let mut last_node_in_row = None;
-//             for (j, &value) in row.iter().enumerate() {
-//                 if value {
-//                     let node = Rc::new(RefCell::new(Node::new(i, j)));
-//                     node.borrow_mut().left = last_node_in_row.clone();
-
-//                     let column = &mut columns[j];
-//                     column.node.borrow_mut().size += 1;
-
-//                     if let Some(last_node) = last_node_in_row {
-//                         last_node.borrow_mut().right = Some(node.clone());
-//                         node.borrow_mut().left = Some(last_node.clone());
-//                     }
-
-//                     node.borrow_mut().up = Some(column.node.clone());
-//                     column.node.borrow_mut().down = Some(node.clone());
-
-//                     last_node_in_row = Some(node);
-//                 }
-//             }
-//         }
-
-//         fn search(&self, k: usize, o: &mut Vec<Rc<RefCell<Node>>>) -> Option<Vec<Rc<RefCell<Node>>>> {
-//             if self.header.borrow().right.as_ref().unwrap().borrow().as_ptr() == self.header.borrow().as_ptr() {
-//                 return Some(o.clone());
-//             } else {
-//                 let mut c = self.header.borrow().right.clone();
-//                 self.cover(c.borrow().column.borrow_mut());
-    
-//                 let mut r = c.borrow().down.clone();
-//                 while let Some(node_r) = r {
-//                     o.push(node_r.clone());
-    
-//                     let mut j = node_r.borrow().right.clone();
-//                     while let Some(node_j) = j {
-//                         self.cover(node_j.borrow().column.borrow_mut());
-    
-//                         j = node_j.borrow().right.clone();
-//                     }
-    
-//                     let result = self.search(k + 1, o);
-//                     if result.is_some() {
-//                         return result;
-//                     }
-    
-//                     r = o.pop().unwrap();
-//                     c = r.borrow().column.clone();
-    
-//                     let mut j = r.borrow().left.clone();
-//                     while let Some(node_j) = j {
-//                         self.uncover(node_j.borrow().column.borrow_mut());
-    
-//                         j = node_j.borrow().left.clone();
-//                     }
-    
-//                     r = r.borrow().down.clone();
-//                 }
-    
-//                 self.uncover(c.borrow().column.borrow_mut());
-//             }
-    
-//             None
-//         }
-        
-//         DancingLinks { header, columns }
-//     }
-// }
+    #[test]
+    fn hint_discloses_a_hidden_pair() {
+        let mut board = open_board();
+        for cell in ["A3", "A4", "A5", "A6", "A7", "A8", "A9"] {
+            board.candidates.get_mut(cell).unwrap().retain(|digit| ![1, 2].contains(digit));
+        }
+        let hint = hint(&board).unwrap();
+        assert_eq!(hint.cells, vec!["A1", "A2"]);
+        assert_eq!(hint.eliminations.len(), 14);
+        assert!(hint.eliminations.iter().all(|(cell, digit)| ["A1", "A2"].contains(&cell.as_str()) && ![1, 2].contains(digit)));
+        assert_discloses(&hint, "box 1", "Hidden Pair", "A1 and A2 are the only cells of row A that can hold 1 and 2, so they hold nothing else");
+    }
+}
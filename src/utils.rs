@@ -7,7 +7,7 @@ pub fn cross<A: Clone  + std::fmt::Display, B: Clone + std::fmt::Display>(a: &[A
         }
     }
 
-    return result;
+    result
 }
 
 pub fn coords_to_cell(row: usize, col: usize) -> String {
@@ -15,7 +15,7 @@ pub fn coords_to_cell(row: usize, col: usize) -> String {
 }
 
 pub fn cell_to_coords(cell: &str) -> (usize, usize) {
-    let row = cell.chars().nth(0).unwrap() as usize - 'A' as usize;
+    let row = cell.chars().next().unwrap() as usize - 'A' as usize;
     let col = cell.chars().nth(1).unwrap().to_digit(10).unwrap() as usize - 1;
     (row, col)
 }